@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::process::Stdio;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::UnixStream;
 use tokio::process::Command as TokioCommand;
 use tauri::{AppHandle, Emitter, Manager};
@@ -10,6 +10,7 @@ use tauri::tray::{TrayIconBuilder, TrayIconEvent, TrayIconId};
 use tracing::{error, info, warn};
 
 const TRAY_ID: &str = "sentinel-tray";
+const SOCKET_PATH: &str = "/tmp/system-sentinel.soc";
 
 // Mirroring the daemon's structs for IPC
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +22,8 @@ pub struct ProcessInfo {
     pub memory_mb: f64,
     pub cpu_usage: f32,
     pub exe: Option<String>,
+    #[serde(default)]
+    pub memory_growth_rate: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +42,24 @@ pub struct SystemMetrics {
     pub top_processes: Vec<ProcessInfo>,
     pub aggregated_processes: Vec<ProcessInfo>,
     pub memory_growth_rate: Option<f64>,
+    #[serde(default)]
+    pub top_growers: Vec<ProcessInfo>,
+    #[serde(default)]
+    pub frozen_processes: Vec<FrozenProcess>,
+    #[serde(default)]
+    pub cgroups: Vec<serde_json::Value>,
+}
+
+/// A process the daemon has frozen (cgroup freezer or SIGSTOP)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrozenProcess {
+    pub pid: u32,
+    pub name: String,
+    pub pids: Vec<u32>,
+    pub memory_bytes: u64,
+    pub requested_by: String,
+    pub frozen_at: chrono::DateTime<chrono::Local>,
+    pub thaw_at: chrono::DateTime<chrono::Local>,
 }
 
 /// System health state for tray icon
@@ -100,6 +121,7 @@ pub fn run() {
                 app,
                 &[
                     &MenuItem::with_id(app, "show", "Show Dashboard", true, None::<&str>)?,
+                    &MenuItem::with_id(app, "ack", "Acknowledge Alerts", true, None::<&str>)?,
                     &MenuItem::with_id(app, "quit", "Quit Sentinel", true, None::<&str>)?,
                 ],
             )?;
@@ -115,6 +137,14 @@ pub fn run() {
                             let _ = window.show().and_then(|_| window.set_focus());
                         }
                     }
+                    "ack" => {
+                        tauri::async_runtime::spawn(async {
+                            match daemon_request(serde_json::json!({"command": "ack"})).await {
+                                Ok(resp) => info!("Acknowledged alerts: {}", resp),
+                                Err(e) => error!("Failed to acknowledge alerts: {}", e),
+                            }
+                        });
+                    }
                     "quit" => {
                         app.exit(0);
                     }
@@ -147,7 +177,7 @@ pub fn run() {
             Ok(())
         })
         .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![submit_query, execute_action])
+        .invoke_handler(tauri::generate_handler![submit_query, execute_action, acknowledge_alerts, daemon_status])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -271,49 +301,100 @@ Provide concise, actionable advice."#,
     Ok(())
 }
 
-/// Execute a confirmed action
+/// Execute a confirmed action.
+/// The daemon applies the protected-process policy, runs the action and audits it;
+/// the UI never signals processes itself. Fails closed if the daemon is unreachable.
 #[tauri::command]
 async fn execute_action(app: AppHandle, action: SuggestedAction) -> Result<String, String> {
     info!("Executing action: {:?}", action);
 
-    match action.action_type.as_str() {
-        "kill_process" => {
-            if let Some(pid) = action.pid {
-                // Safety check - don't kill protected processes
-                let _protected = ["Terminal", "Ghostty", "Code", "Zed", "Safari", "Arc", "Claude", "Finder"];
-                // TODO: Check process name against protected list before killing
-
-                let output = std::process::Command::new("kill")
-                    .args(["-TERM", &pid.to_string()])
-                    .output()
-                    .map_err(|e| format!("Kill failed: {}", e))?;
-
-                if output.status.success() {
-                    let _ = app.emit("action-result", serde_json::json!({"success": true, "message": "Process terminated"}));
-                    Ok("Process terminated".into())
-                } else {
-                    let err = String::from_utf8_lossy(&output.stderr);
-                    Err(format!("Kill failed: {}", err))
-                }
-            } else {
-                Err("No PID specified".into())
-            }
+    let daemon_action = match action.action_type.as_str() {
+        "kill_process" | "kill_process_tree" => {
+            let pid = action.pid.ok_or("No PID specified")?;
+            let tree = action.action_type == "kill_process_tree";
+            serde_json::json!({"kind": "kill", "pid": pid, "tree": tree})
+        }
+        "freeze_process" => {
+            let pid = action.pid.ok_or("No PID specified")?;
+            serde_json::json!({"kind": "freeze", "pid": pid})
+        }
+        "thaw_process" => {
+            let pid = action.pid.ok_or("No PID specified")?;
+            serde_json::json!({"kind": "thaw", "pid": pid})
+        }
+        "clear_cache" => serde_json::json!({"kind": "purge"}),
+        _ => return Err(format!("Unknown action type: {}", action.action_type)),
+    };
+
+    let request = serde_json::json!({
+        "command": "action",
+        "action": daemon_action,
+        "requested_by": "sentinel-ui",
+        "reason": action.description,
+        "requester_pid": std::process::id(),
+    });
+
+    match daemon_request(request).await {
+        Ok(response) => {
+            let message = response.get("message").and_then(|m| m.as_str()).unwrap_or("Done").to_string();
+            let _ = app.emit("action-result", serde_json::json!({"success": true, "message": message}));
+            Ok(message)
         }
-        "clear_cache" => {
-            // Safe operation: clear system caches
-            let _ = std::process::Command::new("sudo")
-                .args(["purge"])
-                .output();
-            Ok("Cache cleared".into())
+        Err(e) => {
+            let message = e.to_string();
+            warn!("Action {} refused: {}", action.action_type, message);
+            let _ = app.emit("action-result", serde_json::json!({"success": false, "message": message}));
+            Err(message)
         }
-        _ => Err(format!("Unknown action type: {}", action.action_type)),
     }
 }
 
+/// Acknowledge escalating alerts (all, or a single anomaly type)
+#[tauri::command]
+async fn acknowledge_alerts(anomaly_type: Option<String>) -> Result<serde_json::Value, String> {
+    daemon_request(serde_json::json!({"command": "ack", "anomaly_type": anomaly_type}))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Fetch daemon state (escalations, ...)
+#[tauri::command]
+async fn daemon_status() -> Result<serde_json::Value, String> {
+    daemon_request(serde_json::json!({"command": "status"}))
+        .await
+        .map_err(|e| e.to_string())
+}
+
 // ========== IPC CLIENT ==========
 
+/// Send a single command to the daemon and wait for its response line.
+/// Metrics lines broadcast on the same connection are skipped.
+async fn daemon_request(request: serde_json::Value) -> Result<serde_json::Value, anyhow::Error> {
+    let stream = UnixStream::connect(SOCKET_PATH).await?;
+    let (reader, mut writer) = stream.into_split();
+
+    writer.write_all(format!("{}\n", request).as_bytes()).await?;
+    writer.flush().await?;
+
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        let value: serde_json::Value = match serde_json::from_str(&line) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        if value.get("type").and_then(|t| t.as_str()) == Some("response") {
+            if value.get("ok").and_then(|ok| ok.as_bool()) == Some(true) {
+                return Ok(value);
+            }
+            let message = value.get("message").and_then(|m| m.as_str()).unwrap_or("unknown error");
+            anyhow::bail!("{}", message);
+        }
+    }
+    anyhow::bail!("Daemon closed the connection without responding")
+}
+
 async fn run_ipc_client(app: AppHandle) -> Result<(), anyhow::Error> {
-    let socket_path = "/tmp/system-sentinel.soc";
+    let socket_path = SOCKET_PATH;
     let mut current_state = HealthState::Healthy;
 
     loop {
@@ -12,6 +12,8 @@ const swapDetail = document.getElementById('swap-detail');
 
 const growthValue = document.getElementById('growth-value');
 const processItems = document.getElementById('process-items');
+const frozenList = document.getElementById('frozen-list');
+const frozenItems = document.getElementById('frozen-items');
 const statusDot = document.getElementById('status-dot');
 
 // Helper to format bytes
@@ -43,6 +45,7 @@ listen('metrics-update', (event) => {
 
   // Processes
   renderProcesses(metrics.top_processes, metrics.aggregated_processes);
+  renderFrozen(metrics.frozen_processes || []);
 
   // Status Dot
   updateStatus(metrics);
@@ -73,6 +76,44 @@ function renderProcesses(top, aggregated) {
   }).join('');
 }
 
+// Frozen processes stay listed until thawed, so they aren't forgotten
+function renderFrozen(frozen) {
+  frozenList.classList.toggle('hidden', frozen.length === 0);
+
+  frozenItems.innerHTML = frozen.map(p => {
+    const gb = formatGB(p.memory_bytes);
+    const minutesLeft = Math.max(0, Math.round((new Date(p.thaw_at) - Date.now()) / 60000));
+    const extra = p.pids.length > 1 ? ` +${p.pids.length - 1}` : '';
+
+    return `
+      <div class="process-item frozen">
+        <div class="process-name">❄️ ${p.name}${extra}</div>
+        <div class="process-meta">
+          <div class="process-memory">${gb} GB</div>
+          <div class="process-thaw">thaws in ${minutesLeft}m</div>
+          <button class="thaw-btn" data-pid="${p.pid}">Thaw</button>
+        </div>
+      </div>
+    `;
+  }).join('');
+}
+
+frozenItems.addEventListener('click', async (e) => {
+  const button = e.target.closest('.thaw-btn');
+  if (!button) return;
+
+  button.disabled = true;
+  const pid = Number(button.dataset.pid);
+  try {
+    await invoke('execute_action', {
+      action: { action_type: 'thaw_process', description: `Thaw PID ${pid}`, risk: 'low', command: null, pid }
+    });
+  } catch (err) {
+    button.disabled = false;
+    console.error('Thaw failed', err);
+  }
+});
+
 function updateStatus(metrics) {
   let color = '#10b981'; // Green
   let glow = 'rgba(16, 185, 129, 0.5)';
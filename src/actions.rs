@@ -0,0 +1,676 @@
+//! Remediation actions executed by the daemon
+//!
+//! Clients (the UI, the AI advisor) only submit `ActionRequest`s over IPC; the
+//! daemon checks them against the `ProcessPolicy`, executes them, and appends
+//! every attempt, refused or not, to an audit log (one JSON object per line).
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessStatus, ProcessesToUpdate, System};
+use tracing::{debug, info, warn};
+
+use crate::cgroup::{CgroupFs, CgroupLimiter};
+use crate::config::{expand_tilde, Config};
+use crate::freeze::Freezer;
+use crate::metrics::SystemMetrics;
+use crate::policy::{PolicyViolation, ProcessPolicy, ProcessTarget};
+
+/// What to do
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Action {
+    /// Terminate the process: SIGTERM, then SIGKILL after the grace period.
+    /// With `tree`, its descendants are terminated along with it.
+    Kill {
+        pid: u32,
+        #[serde(default)]
+        tree: bool,
+    },
+    /// Suspend the process (cgroup freezer or SIGSTOP) without losing its state.
+    /// It is thawed automatically after `thaw_after_seconds` (default from config).
+    Freeze {
+        pid: u32,
+        #[serde(default)]
+        tree: bool,
+        #[serde(default)]
+        thaw_after_seconds: Option<u64>,
+    },
+    /// Resume a frozen process
+    Thaw { pid: u32 },
+    /// Cap the process (or its tree) with cgroup v2 memory.high (Linux)
+    Limit {
+        pid: u32,
+        memory_high_mb: u64,
+        #[serde(default)]
+        tree: bool,
+    },
+    /// Lift a cap set by `Limit`, restoring the group's previous memory.high
+    Unlimit { pid: u32 },
+    /// Change scheduling priority (-20..19, higher is nicer)
+    Renice { pid: u32, nice: i32 },
+    /// Drop filesystem caches
+    Purge,
+}
+
+/// An action plus who asked for it and why
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionRequest {
+    pub action: Action,
+    /// Free-form requester identity, e.g. "sentinel-ui" or "autonomous"
+    #[serde(default = "default_requested_by")]
+    pub requested_by: String,
+    #[serde(default)]
+    pub reason: String,
+    /// PID of the requesting client; it can never target itself
+    #[serde(default)]
+    pub requester_pid: Option<u32>,
+}
+
+fn default_requested_by() -> String {
+    "unknown".to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionStatus {
+    Succeeded,
+    Refused,
+    Failed,
+}
+
+/// Result of an action attempt, returned over IPC and recorded in the audit log
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionOutcome {
+    pub status: ActionStatus,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<ProcessTarget>,
+    /// Resident memory of the processes confirmed gone after a kill
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub freed_bytes: Option<u64>,
+}
+
+impl ActionOutcome {
+    fn new(status: ActionStatus, message: impl Into<String>, target: Option<ProcessTarget>) -> Self {
+        Self { status, message: message.into(), target, freed_bytes: None }
+    }
+}
+
+/// One line of the audit log
+#[derive(Serialize)]
+struct AuditEntry<'a> {
+    timestamp: chrono::DateTime<chrono::Local>,
+    requested_by: &'a str,
+    reason: &'a str,
+    requester_pid: Option<u32>,
+    action: &'a Action,
+    metrics: Option<&'a SystemMetrics>,
+    outcome: &'a ActionOutcome,
+}
+
+/// Executes actions; cheap to clone so each request can run on a blocking thread
+#[derive(Debug, Clone)]
+pub struct ActionExecutor {
+    policy: ProcessPolicy,
+    audit_log: PathBuf,
+    purge_command: Vec<String>,
+    kill_grace: Duration,
+    freeze_timeout: Duration,
+    freezer: Freezer,
+    limiter: CgroupLimiter,
+}
+
+impl ActionExecutor {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            policy: ProcessPolicy::new(&config.policy),
+            audit_log: expand_tilde(&config.actions.audit_log),
+            purge_command: config.actions.purge_command.clone(),
+            kill_grace: Duration::from_secs_f64(config.actions.kill_grace_seconds.max(0.0)),
+            freeze_timeout: Duration::from_secs(config.actions.freeze_timeout_seconds),
+            freezer: Freezer::new(CgroupFs::new(&config.cgroups.root)),
+            limiter: CgroupLimiter::new(CgroupFs::new(&config.cgroups.root)),
+        }
+    }
+
+    pub fn policy(&self) -> &ProcessPolicy {
+        &self.policy
+    }
+
+    pub fn freezer(&self) -> &Freezer {
+        &self.freezer
+    }
+
+    pub fn limiter(&self) -> &CgroupLimiter {
+        &self.limiter
+    }
+
+    /// Thaw frozen processes whose deadline has passed, or all of them (on shutdown).
+    /// Each thaw goes through `execute` so it is audited like any other action;
+    /// a failed one is retried after AUTO_THAW_RETRY rather than every tick.
+    pub fn auto_thaw(&self, all: bool, metrics: Option<&SystemMetrics>) {
+        let now = chrono::Local::now();
+        for frozen in self.freezer.frozen() {
+            if !all && frozen.thaw_at > now {
+                continue;
+            }
+            let request = ActionRequest {
+                action: Action::Thaw { pid: frozen.pid },
+                requested_by: "auto-thaw".to_string(),
+                reason: if all { "daemon shutting down".to_string() } else { "freeze timeout expired".to_string() },
+                requester_pid: None,
+            };
+            if self.execute(&request, metrics).status != ActionStatus::Succeeded {
+                self.freezer.postpone(frozen.pid, AUTO_THAW_RETRY);
+            }
+        }
+    }
+
+    /// Run the action and record it in the audit log together with the metrics snapshot
+    pub fn execute(&self, request: &ActionRequest, metrics: Option<&SystemMetrics>) -> ActionOutcome {
+        let outcome = self.run(request, metrics);
+
+        match outcome.status {
+            ActionStatus::Succeeded => info!("Action {:?} by {}: {}", request.action, request.requested_by, outcome.message),
+            _ => warn!("Action {:?} by {} {:?}: {}", request.action, request.requested_by, outcome.status, outcome.message),
+        }
+
+        if let Err(e) = self.audit(request, metrics, &outcome) {
+            warn!("Failed to write audit log {:?}: {:#}", self.audit_log, e);
+        }
+
+        outcome
+    }
+
+    fn run(&self, request: &ActionRequest, metrics: Option<&SystemMetrics>) -> ActionOutcome {
+        let check = |pid: u32| {
+            self.policy
+                .check(pid, request.requester_pid)
+                .map_err(|violation| ActionOutcome::new(ActionStatus::Refused, violation.to_string(), None))
+        };
+
+        match &request.action {
+            Action::Kill { pid, tree } => match check(*pid) {
+                Ok(target) => self.terminate(target, *tree, metrics, request.requester_pid),
+                Err(refused) => refused,
+            },
+            Action::Freeze { pid, tree, thaw_after_seconds } => match check(*pid) {
+                Ok(target) => {
+                    let thaw_after = thaw_after_seconds.map(Duration::from_secs).unwrap_or(self.freeze_timeout);
+                    self.freeze(target, *tree, thaw_after, metrics, request)
+                }
+                Err(refused) => refused,
+            },
+            Action::Thaw { pid } => self.thaw(*pid, check),
+            // memory.high of 0 would throttle the process into reclaim forever
+            Action::Limit { memory_high_mb: 0, .. } => {
+                ActionOutcome::new(ActionStatus::Refused, "memory_high_mb must be greater than 0".to_string(), None)
+            }
+            Action::Limit { pid, memory_high_mb, tree } => match check(*pid) {
+                Ok(target) => self.limit(target, *memory_high_mb, *tree, metrics, request.requester_pid),
+                Err(refused) => refused,
+            },
+            // Lifting our own cap needs no policy check, like thawing
+            Action::Unlimit { pid } => match self.limiter.unlimit(*pid) {
+                Ok(Some(limit)) => {
+                    let restored = limit.previous_high_bytes.map_or("max".to_string(), format_bytes);
+                    let message = format!("Lifted the cap on {} (PID {}); memory.high is {} again", limit.name, pid, restored);
+                    ActionOutcome::new(ActionStatus::Succeeded, message, None)
+                }
+                Ok(None) => ActionOutcome::new(ActionStatus::Failed, format!("No memory limit applied to PID {}", pid), None),
+                Err(e) => ActionOutcome::new(ActionStatus::Failed, format!("{:#}", e), None),
+            },
+            Action::Renice { pid, nice } => match check(*pid) {
+                Ok(target) => {
+                    let result = renice(*pid, *nice).map(|()| format!("Reniced PID {} to {}", pid, nice));
+                    outcome_of(result, Some(target))
+                }
+                Err(refused) => refused,
+            },
+            Action::Purge => outcome_of(self.purge().map(|()| "Caches purged".to_string()), None),
+        }
+    }
+
+    /// Thaw a process frozen by this daemon, or SIGCONT any other (policy permitting)
+    fn thaw(&self, pid: u32, check: impl Fn(u32) -> Result<ProcessTarget, ActionOutcome>) -> ActionOutcome {
+        // Thawing something we froze must work even if it no longer passes the policy
+        match self.freezer.thaw(pid) {
+            Ok(Some(frozen)) => {
+                let held = crate::templates::format_duration(chrono::Local::now() - frozen.frozen_at);
+                ActionOutcome::new(ActionStatus::Succeeded, format!("Thawed {} (PID {}) after {}", frozen.name, pid, held), None)
+            }
+            Ok(None) => match check(pid) {
+                Ok(target) => outcome_of(send_signal(pid, "CONT").map(|()| format!("Sent SIGCONT to PID {}", pid)), Some(target)),
+                Err(refused) => refused,
+            },
+            Err(e) => ActionOutcome::new(ActionStatus::Failed, format!("{:#}", e), None),
+        }
+    }
+
+    /// `root` plus, with `tree`, every descendant that passes the policy.
+    /// Also returns why any descendants were skipped.
+    fn with_descendants(
+        &self,
+        root: &ProcessTarget,
+        tree: bool,
+        metrics: Option<&SystemMetrics>,
+        requester_pid: Option<u32>,
+    ) -> Result<(Vec<ProcessTarget>, Vec<String>), String> {
+        let mut targets = vec![root.clone()];
+        let mut skipped = Vec::new();
+
+        if tree {
+            let metrics = metrics.ok_or("No process snapshot yet; cannot resolve the process tree")?;
+            for pid in metrics.descendants(root.pid) {
+                match self.policy.check(pid, requester_pid) {
+                    Ok(child) => targets.push(child),
+                    Err(PolicyViolation::NotFound(_)) => {}
+                    Err(violation) => skipped.push(violation.to_string()),
+                }
+            }
+        }
+
+        Ok((targets, skipped))
+    }
+
+    fn freeze(
+        &self,
+        root: ProcessTarget,
+        tree: bool,
+        thaw_after: Duration,
+        metrics: Option<&SystemMetrics>,
+        request: &ActionRequest,
+    ) -> ActionOutcome {
+        let (targets, skipped) = match self.with_descendants(&root, tree, metrics, request.requester_pid) {
+            Ok(resolved) => resolved,
+            Err(message) => return ActionOutcome::new(ActionStatus::Failed, message, Some(root)),
+        };
+
+        let pids: Vec<u32> = targets.iter().map(|t| t.pid).collect();
+        let memory: u64 = targets.iter().map(|t| t.memory_bytes).sum();
+
+        match self.freezer.freeze(&root.name, &pids, memory, thaw_after, &request.requested_by) {
+            Ok(frozen) => {
+                let mut message = format!(
+                    "Froze {} (PID {}, {} process(es), {}); auto-thaw at {}",
+                    root.name, root.pid, pids.len(), format_bytes(memory), frozen.thaw_at.format("%H:%M")
+                );
+                if !skipped.is_empty() {
+                    message.push_str(&format!("; skipped: {}", skipped.join("; ")));
+                }
+                ActionOutcome::new(ActionStatus::Succeeded, message, Some(root))
+            }
+            Err(e) => ActionOutcome::new(ActionStatus::Failed, format!("{:#}", e), Some(root)),
+        }
+    }
+
+    fn limit(
+        &self,
+        root: ProcessTarget,
+        memory_high_mb: u64,
+        tree: bool,
+        metrics: Option<&SystemMetrics>,
+        requester_pid: Option<u32>,
+    ) -> ActionOutcome {
+        let (targets, skipped) = match self.with_descendants(&root, tree, metrics, requester_pid) {
+            Ok(resolved) => resolved,
+            Err(message) => return ActionOutcome::new(ActionStatus::Failed, message, Some(root)),
+        };
+        let pids: Vec<u32> = targets.iter().map(|t| t.pid).collect();
+
+        let result = self.limiter.limit(&root.name, &pids, memory_high_mb * 1024 * 1024).map(|limit| {
+            let mut message = format!(
+                "Capped {} (PID {}) at {} via {}{}",
+                root.name,
+                root.pid,
+                format_bytes(limit.memory_high_bytes),
+                limit.path,
+                if limit.transient { " (transient)" } else { "" }
+            );
+            if !skipped.is_empty() {
+                message.push_str(&format!("; skipped: {}", skipped.join("; ")));
+            }
+            message
+        });
+        outcome_of(result, Some(root))
+    }
+
+    /// Staged terminate: SIGTERM, wait up to the grace period, SIGKILL whatever
+    /// is left, then verify every targeted PID is gone
+    fn terminate(
+        &self,
+        root: ProcessTarget,
+        tree: bool,
+        metrics: Option<&SystemMetrics>,
+        requester_pid: Option<u32>,
+    ) -> ActionOutcome {
+        let (targets, skipped) = match self.with_descendants(&root, tree, metrics, requester_pid) {
+            Ok(resolved) => resolved,
+            Err(message) => return ActionOutcome::new(ActionStatus::Failed, message, Some(root)),
+        };
+
+        let pids: Vec<u32> = targets.iter().map(|t| t.pid).collect();
+        for &pid in &pids {
+            // A descendant may exit on its own between the snapshot and now
+            if let Err(e) = send_signal(pid, "TERM") {
+                debug!("SIGTERM to PID {} failed: {:#}", pid, e);
+            }
+        }
+
+        let started = Instant::now();
+        let mut alive = wait_for_exit(&pids, self.kill_grace);
+        let exited_after = started.elapsed();
+
+        let mut killed = 0;
+        if !alive.is_empty() {
+            info!("{} process(es) still alive after {:.1}s grace; sending SIGKILL", alive.len(), exited_after.as_secs_f64());
+            for &pid in &alive {
+                if let Err(e) = send_signal(pid, "KILL") {
+                    debug!("SIGKILL to PID {} failed: {:#}", pid, e);
+                }
+            }
+            killed = alive.len();
+            alive = wait_for_exit(&alive, KILL_VERIFY_TIMEOUT);
+        }
+
+        let freed: u64 = targets.iter().filter(|t| !alive.contains(&t.pid)).map(|t| t.memory_bytes).sum();
+
+        let subject = match targets.len() {
+            1 => format!("{} (PID {})", root.name, root.pid),
+            n => format!("{} (PID {}) and {} descendant(s)", root.name, root.pid, n - 1),
+        };
+        let mut message = if !alive.is_empty() {
+            format!("{}: PID(s) {:?} survived SIGKILL", subject, alive)
+        } else if killed > 0 {
+            format!(
+                "Killed {}; {} ignored SIGTERM for {:.0}s and needed SIGKILL",
+                subject, killed, self.kill_grace.as_secs_f64()
+            )
+        } else {
+            format!("Terminated {} with SIGTERM in {:.1}s", subject, exited_after.as_secs_f64())
+        };
+        message.push_str(&format!("; freed {}", format_bytes(freed)));
+        if !skipped.is_empty() {
+            message.push_str(&format!("; skipped: {}", skipped.join("; ")));
+        }
+
+        let status = if alive.is_empty() { ActionStatus::Succeeded } else { ActionStatus::Failed };
+        let mut outcome = ActionOutcome::new(status, message, Some(root));
+        outcome.freed_bytes = Some(freed);
+        outcome
+    }
+
+    fn purge(&self) -> Result<()> {
+        let (program, args) = self
+            .purge_command
+            .split_first()
+            .context("No purge command configured")?;
+        run_command(Command::new(program).args(args))
+    }
+
+    fn audit(&self, request: &ActionRequest, metrics: Option<&SystemMetrics>, outcome: &ActionOutcome) -> Result<()> {
+        if let Some(dir) = self.audit_log.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let entry = AuditEntry {
+            timestamp: chrono::Local::now(),
+            requested_by: &request.requested_by,
+            reason: &request.reason,
+            requester_pid: request.requester_pid,
+            action: &request.action,
+            metrics,
+            outcome,
+        };
+
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.audit_log)?;
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
+
+fn outcome_of(result: Result<String>, target: Option<ProcessTarget>) -> ActionOutcome {
+    match result {
+        Ok(message) => ActionOutcome::new(ActionStatus::Succeeded, message, target),
+        Err(e) => ActionOutcome::new(ActionStatus::Failed, format!("{:#}", e), target),
+    }
+}
+
+/// How long a failed auto-thaw waits before it is tried again
+const AUTO_THAW_RETRY: Duration = Duration::from_secs(300);
+
+/// How long to wait for the kernel to reap processes after SIGKILL
+const KILL_VERIFY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Poll until all `pids` have exited or `timeout` passes; returns those still alive
+fn wait_for_exit(pids: &[u32], timeout: Duration) -> Vec<u32> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let alive = still_running(pids);
+        if alive.is_empty() || Instant::now() >= deadline {
+            return alive;
+        }
+        std::thread::sleep(Duration::from_millis(100).min(deadline - Instant::now()));
+    }
+}
+
+/// The subset of `pids` that is still running (zombies count as gone)
+pub fn still_running(pids: &[u32]) -> Vec<u32> {
+    let wanted: Vec<Pid> = pids.iter().map(|&p| Pid::from_u32(p)).collect();
+    let mut system = System::new();
+    system.refresh_processes_specifics(ProcessesToUpdate::Some(&wanted), true, ProcessRefreshKind::new());
+
+    pids.iter()
+        .copied()
+        .filter(|&pid| {
+            system
+                .process(Pid::from_u32(pid))
+                .is_some_and(|p| p.status() != ProcessStatus::Zombie)
+        })
+        .collect()
+}
+
+fn format_bytes(bytes: u64) -> String {
+    let mb = bytes as f64 / 1024.0 / 1024.0;
+    if mb >= 1024.0 {
+        format!("{:.1} GB", mb / 1024.0)
+    } else {
+        format!("{:.0} MB", mb)
+    }
+}
+
+/// Send a signal by name (e.g. "TERM", "STOP") via `kill`
+pub fn send_signal(pid: u32, signal: &str) -> Result<()> {
+    run_command(Command::new("kill").arg(format!("-{}", signal)).arg(pid.to_string()))
+}
+
+fn renice(pid: u32, nice: i32) -> Result<()> {
+    if !(-20..=19).contains(&nice) {
+        anyhow::bail!("Nice value {} is outside -20..19", nice);
+    }
+    run_command(Command::new("renice").args(["-n", &nice.to_string(), "-p", &pid.to_string()]))
+}
+
+/// Run a command to completion, turning a non-zero exit into an error with its stderr
+fn run_command(cmd: &mut Command) -> Result<()> {
+    let output = cmd
+        .output()
+        .with_context(|| format!("Failed to execute {:?}", cmd.get_program()))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("{:?} failed: {}", cmd.get_program(), stderr.trim())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refused_action_is_audited() {
+        let dir = std::env::temp_dir().join(format!("sentinel-audit-{}", std::process::id()));
+        let mut config = Config::default();
+        config.actions.audit_log = dir.join("audit.jsonl").to_string_lossy().to_string();
+        let executor = ActionExecutor::new(&config);
+
+        let request: ActionRequest = serde_json::from_str(
+            r#"{"action": {"kind": "kill", "pid": 1}, "requested_by": "test", "reason": "runaway"}"#,
+        )
+        .unwrap();
+        let outcome = executor.execute(&request, None);
+        assert_eq!(outcome.status, ActionStatus::Refused);
+
+        let log = std::fs::read_to_string(dir.join("audit.jsonl")).unwrap();
+        let entry: serde_json::Value = serde_json::from_str(log.lines().last().unwrap()).unwrap();
+        assert_eq!(entry["requested_by"], "test");
+        assert_eq!(entry["action"]["kind"], "kill");
+        assert_eq!(entry["outcome"]["status"], "refused");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_zero_limit_is_refused() {
+        let dir = std::env::temp_dir().join(format!("sentinel-limit-audit-{}", std::process::id()));
+        let mut config = Config::default();
+        config.actions.audit_log = dir.join("audit.jsonl").to_string_lossy().to_string();
+        let executor = ActionExecutor::new(&config);
+
+        let request = ActionRequest {
+            action: Action::Limit { pid: std::process::id(), memory_high_mb: 0, tree: false },
+            requested_by: "test".to_string(),
+            reason: String::new(),
+            requester_pid: None,
+        };
+        let outcome = executor.execute(&request, None);
+        assert_eq!(outcome.status, ActionStatus::Refused);
+        assert!(outcome.message.contains("memory_high_mb"), "{}", outcome.message);
+
+        let log = std::fs::read_to_string(dir.join("audit.jsonl")).unwrap();
+        let entry: serde_json::Value = serde_json::from_str(log.lines().last().unwrap()).unwrap();
+        assert_eq!(entry["action"]["kind"], "limit");
+        assert_eq!(entry["outcome"]["status"], "refused");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_kill_escalates_to_sigkill() {
+        let mut config = Config::default();
+        config.actions.audit_log = std::env::temp_dir().join(format!("sentinel-kill-test-{}.jsonl", std::process::id())).to_string_lossy().to_string();
+        config.actions.kill_grace_seconds = 0.3;
+        let executor = ActionExecutor::new(&config);
+
+        // A process that ignores SIGTERM
+        let mut child = Command::new("sh")
+            .args(["-c", "trap '' TERM; exec sleep 30"])
+            .spawn()
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+
+        let request = ActionRequest {
+            action: Action::Kill { pid: child.id(), tree: false },
+            requested_by: "test".to_string(),
+            reason: String::new(),
+            requester_pid: None,
+        };
+        let outcome = executor.execute(&request, None);
+        let _ = child.wait();
+        let _ = std::fs::remove_file(&config.actions.audit_log);
+
+        assert_eq!(outcome.status, ActionStatus::Succeeded, "{}", outcome.message);
+        assert!(outcome.message.contains("needed SIGKILL"), "{}", outcome.message);
+        assert!(outcome.freed_bytes.is_some());
+    }
+
+    #[test]
+    fn test_descendants() {
+        let metrics = SystemMetrics {
+            parent_pids: [(20, 10), (21, 10), (30, 20), (40, 99)].into_iter().collect(),
+            ..SystemMetrics::default()
+        };
+        let mut found = metrics.descendants(10);
+        found.sort();
+        assert_eq!(found, vec![20, 21, 30]);
+    }
+
+    #[test]
+    fn test_freeze_and_auto_thaw() {
+        let mut config = Config::default();
+        config.actions.audit_log = std::env::temp_dir().join(format!("sentinel-freeze-test-{}.jsonl", std::process::id())).to_string_lossy().to_string();
+        let executor = ActionExecutor::new(&config);
+
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        let pid = child.id();
+        let status = |pid: u32| {
+            let mut system = System::new();
+            system.refresh_processes_specifics(ProcessesToUpdate::Some(&[Pid::from_u32(pid)]), true, ProcessRefreshKind::new());
+            system.process(Pid::from_u32(pid)).map(|p| p.status())
+        };
+
+        let request = ActionRequest {
+            action: Action::Freeze { pid, tree: false, thaw_after_seconds: Some(0) },
+            requested_by: "test".to_string(),
+            reason: String::new(),
+            requester_pid: None,
+        };
+        let outcome = executor.execute(&request, None);
+        assert_eq!(outcome.status, ActionStatus::Succeeded, "{}", outcome.message);
+        assert!(executor.freezer().is_frozen(pid));
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(status(pid), Some(ProcessStatus::Stop));
+
+        executor.auto_thaw(false, None);
+        assert!(!executor.freezer().is_frozen(pid));
+        std::thread::sleep(Duration::from_millis(100));
+        assert_ne!(status(pid), Some(ProcessStatus::Stop));
+
+        let _ = child.kill();
+        let _ = child.wait();
+        let _ = std::fs::remove_file(&config.actions.audit_log);
+    }
+    #[test]
+    fn test_failed_auto_thaw_backs_off() {
+        let root = std::env::temp_dir().join(format!("sentinel-thaw-retry-{}", std::process::id()));
+        let (proc_root, cgroup_root) = (root.join("proc"), root.join("cgroup"));
+        let app = cgroup_root.join("app.scope");
+        std::fs::create_dir_all(proc_root.join("100")).unwrap();
+        std::fs::create_dir_all(&app).unwrap();
+        std::fs::write(proc_root.join("100/cgroup"), "0::/app.scope\n").unwrap();
+        std::fs::write(app.join("cgroup.freeze"), "0").unwrap();
+        std::fs::write(app.join("cgroup.procs"), "100\n").unwrap();
+
+        let mut config = Config::default();
+        config.actions.audit_log = root.join("audit.jsonl").to_string_lossy().to_string();
+        let executor = ActionExecutor {
+            freezer: Freezer::new(CgroupFs::with_roots(&proc_root, &cgroup_root)),
+            ..ActionExecutor::new(&config)
+        };
+        executor.freezer().freeze("app", &[100], 0, Duration::ZERO, "test").unwrap();
+
+        // cgroup.freeze can no longer be written
+        std::fs::remove_file(app.join("cgroup.freeze")).unwrap();
+        std::fs::create_dir(app.join("cgroup.freeze")).unwrap();
+
+        executor.auto_thaw(false, None);
+        executor.auto_thaw(false, None);
+        let log = std::fs::read_to_string(root.join("audit.jsonl")).unwrap();
+        assert_eq!(log.lines().count(), 1, "a failed thaw is not retried on the next tick");
+        let frozen = executor.freezer().frozen();
+        assert_eq!(frozen.len(), 1);
+        assert!(frozen[0].thaw_at > chrono::Local::now() + chrono::Duration::minutes(4));
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,338 @@
+//! Tier 3: protective actions taken without asking
+//!
+//! Opt-in rules fire only after a system metric has stayed above its threshold
+//! for a number of consecutive ticks, and only against the fastest-growing
+//! process that is on the rule's allowlist and not protected.
+//! Decisions are capped per rolling hour; in dry-run mode they are only logged.
+//! Execution goes through `ActionExecutor`, so the process policy and the
+//! audit log apply as for any other action.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use crate::actions::{Action, ActionOutcome, ActionRequest, ActionStatus};
+use crate::config::{AutonomousAction, AutonomousConfig, AutonomousMetric, AutonomousRule};
+use crate::metrics::{extract_app_name, ProcessInfo, SystemMetrics};
+use crate::pattern::glob_match;
+
+/// An action a rule decided to take
+#[derive(Debug, Clone)]
+pub struct AutonomousDecision {
+    pub rule: String,
+    pub culprit: ProcessInfo,
+    pub request: ActionRequest,
+    pub dry_run: bool,
+}
+
+impl AutonomousDecision {
+    fn verb(&self) -> &'static str {
+        match self.request.action {
+            Action::Kill { .. } => "kill",
+            _ => "freeze",
+        }
+    }
+
+    /// Popup title and details reporting what was done
+    pub fn notification(&self, outcome: &ActionOutcome) -> (String, Vec<String>) {
+        let name = self.culprit.human_name();
+        let title = match outcome.status {
+            ActionStatus::Succeeded => format!("Sentinel had to {} {}", self.verb(), name),
+            _ => format!("Sentinel failed to {} {}", self.verb(), name),
+        };
+        let details = vec![outcome.message.clone(), self.request.reason.clone(), format!("Rule: {}", self.rule)];
+        (title, details)
+    }
+}
+
+pub struct AutonomousGuard {
+    enabled: bool,
+    dry_run: bool,
+    max_per_hour: u32,
+    protected: Vec<String>,
+    rules: Vec<AutonomousRule>,
+    /// Consecutive ticks each rule's metric has been at or above its threshold
+    streaks: HashMap<String, u32>,
+    /// When recent decisions were made, for the hourly cap
+    recent: VecDeque<Instant>,
+}
+
+impl AutonomousGuard {
+    pub fn new(config: &AutonomousConfig) -> Self {
+        if config.enabled {
+            info!(
+                "Autonomous actions enabled ({} rule(s){})",
+                config.rules.len(),
+                if config.dry_run { ", dry run" } else { "" }
+            );
+        }
+        Self {
+            enabled: config.enabled,
+            dry_run: config.dry_run,
+            max_per_hour: config.max_actions_per_hour,
+            protected: config.protected_processes.clone(),
+            rules: config.rules.clone(),
+            streaks: HashMap::new(),
+            recent: VecDeque::new(),
+        }
+    }
+
+    /// Update rule streaks with this tick's metrics and return at most one decision
+    pub fn evaluate(&mut self, metrics: &SystemMetrics, now: Instant) -> Option<AutonomousDecision> {
+        if !self.enabled {
+            return None;
+        }
+
+        for rule in &self.rules {
+            let streak = self.streaks.entry(rule.name.clone()).or_insert(0);
+            if metric_value(rule.metric, metrics) >= rule.threshold {
+                *streak += 1;
+            } else {
+                *streak = 0;
+            }
+        }
+
+        // The first triggered rule that finds a process it may act on decides
+        let (rule, culprit) = self
+            .rules
+            .iter()
+            .filter(|r| self.streaks.get(&r.name).copied().unwrap_or(0) >= r.consecutive_ticks.max(1))
+            .find_map(|rule| match self.culprit_for(rule, metrics) {
+                Ok(culprit) => Some((rule.clone(), culprit)),
+                Err(why) => {
+                    debug!("Autonomous rule '{}' triggered but not acting: {}", rule.name, why);
+                    None
+                }
+            })?;
+
+        // The condition has been handled (or deliberately skipped); require a fresh streak
+        self.streaks.insert(rule.name.clone(), 0);
+
+        let hour = Duration::from_secs(3600);
+        while self.recent.front().is_some_and(|t| now.duration_since(*t) >= hour) {
+            self.recent.pop_front();
+        }
+        if self.recent.len() >= self.max_per_hour as usize {
+            warn!(
+                "Autonomous rule '{}' would act on {} (PID {}) but the cap of {}/hour is reached",
+                rule.name, culprit.human_name(), culprit.pid, self.max_per_hour
+            );
+            return None;
+        }
+        self.recent.push_back(now);
+
+        let value = metric_value(rule.metric, metrics);
+        let reason = format!(
+            "{} at {:.1} for {} ticks (threshold {:.1}); {} growing {:.1} GB/h at {:.1} GB",
+            metric_label(rule.metric),
+            value,
+            rule.consecutive_ticks,
+            rule.threshold,
+            culprit.human_name(),
+            culprit.memory_growth_rate.unwrap_or(0.0),
+            culprit.memory_mb / 1024.0
+        );
+
+        let action = match rule.action {
+            AutonomousAction::Freeze => Action::Freeze { pid: culprit.pid, tree: rule.tree, thaw_after_seconds: None },
+            AutonomousAction::Kill => Action::Kill { pid: culprit.pid, tree: rule.tree },
+        };
+
+        Some(AutonomousDecision {
+            rule: rule.name.clone(),
+            request: ActionRequest {
+                action,
+                requested_by: format!("autonomous:{}", rule.name),
+                reason,
+                requester_pid: None,
+            },
+            culprit,
+            dry_run: self.dry_run,
+        })
+    }
+
+    /// The fastest-growing process the rule may act on. Protected and
+    /// non-allowlisted growers are passed over for the next one; the reasons
+    /// are returned when none qualifies.
+    fn culprit_for(&self, rule: &AutonomousRule, metrics: &SystemMetrics) -> Result<ProcessInfo, String> {
+        let mut passed_over = Vec::new();
+
+        // top_growers is sorted by growth, fastest first, and only holds
+        // processes sampled long enough for their growth to be trusted
+        for culprit in &metrics.top_growers {
+            let growth = culprit.memory_growth_rate.unwrap_or(0.0);
+            let name = culprit.human_name();
+
+            if growth <= 0.0 || growth < rule.min_growth_gb_per_hour {
+                passed_over.push(format!("{} grows {:.2} GB/h, below {:.2}", name, growth, rule.min_growth_gb_per_hour));
+                break;
+            }
+            if let Some(pattern) = matching_pattern(&self.protected, culprit) {
+                passed_over.push(format!("{} matches protected pattern \"{}\"", name, pattern));
+                continue;
+            }
+            if matching_pattern(&rule.allowlist, culprit).is_none() {
+                passed_over.push(format!("{} is not on the allowlist", name));
+                continue;
+            }
+
+            return Ok(culprit.clone());
+        }
+
+        if passed_over.is_empty() {
+            return Err("no process is growing".to_string());
+        }
+        Err(passed_over.join("; "))
+    }
+}
+
+fn metric_value(metric: AutonomousMetric, metrics: &SystemMetrics) -> f64 {
+    match metric {
+        AutonomousMetric::MemoryPercent => metrics.memory_percent,
+        AutonomousMetric::SwapPercent => metrics.swap_percent,
+        AutonomousMetric::Load1m => metrics.load_1m,
+    }
+}
+
+fn metric_label(metric: AutonomousMetric) -> &'static str {
+    match metric {
+        AutonomousMetric::MemoryPercent => "Memory %",
+        AutonomousMetric::SwapPercent => "Swap %",
+        AutonomousMetric::Load1m => "Load",
+    }
+}
+
+/// First pattern matching the process name, display name, executable path or file name
+fn matching_pattern<'a>(patterns: &'a [String], process: &ProcessInfo) -> Option<&'a str> {
+    let mut candidates = vec![process.name.clone(), process.human_name()];
+    if let Some(exe) = &process.exe {
+        candidates.push(exe.clone());
+        if let Some(file) = std::path::Path::new(exe).file_name() {
+            candidates.push(file.to_string_lossy().to_string());
+        }
+        if let Some(app) = extract_app_name(exe) {
+            candidates.push(app);
+        }
+    }
+
+    patterns
+        .iter()
+        .find(|p| candidates.iter().any(|c| glob_match(p, c)))
+        .map(String::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(name: &str, growth: f64) -> ProcessInfo {
+        ProcessInfo {
+            pid: 4242,
+            parent_pid: Some(1),
+            name: name.to_string(),
+            memory_bytes: 20 * 1024 * 1024 * 1024,
+            memory_mb: 20.0 * 1024.0,
+            cpu_usage: 5.0,
+            exe: Some(format!("/Applications/{0}.app/Contents/MacOS/{0}", name)),
+            memory_growth_rate: Some(growth),
+            open_fds: None,
+            threads: None,
+            fd_growth_rate: None,
+            thread_growth_rate: None,
+        }
+    }
+
+    fn metrics(memory_percent: f64, grower: ProcessInfo) -> SystemMetrics {
+        SystemMetrics { memory_percent, top_growers: vec![grower], ..SystemMetrics::default() }
+    }
+
+    fn guard(max_actions_per_hour: u32) -> AutonomousGuard {
+        AutonomousGuard::new(&AutonomousConfig {
+            enabled: true,
+            dry_run: true,
+            max_actions_per_hour,
+            protected_processes: vec!["Safari".to_string()],
+            rules: vec![AutonomousRule {
+                name: "runaway".to_string(),
+                metric: AutonomousMetric::MemoryPercent,
+                threshold: 97.0,
+                consecutive_ticks: 3,
+                action: AutonomousAction::Freeze,
+                allowlist: vec!["Ghostty".to_string(), "Safari".to_string()],
+                min_growth_gb_per_hour: 1.0,
+                tree: false,
+            }],
+        })
+    }
+
+    #[test]
+    fn test_requires_consecutive_ticks() {
+        let mut g = guard(5);
+        let t0 = Instant::now();
+        let hot = metrics(98.0, process("Ghostty", 4.0));
+
+        assert!(g.evaluate(&hot, t0).is_none());
+        assert!(g.evaluate(&metrics(90.0, process("Ghostty", 4.0)), t0).is_none()); // streak broken
+        assert!(g.evaluate(&hot, t0).is_none());
+        assert!(g.evaluate(&hot, t0).is_none());
+
+        let decision = g.evaluate(&hot, t0).unwrap();
+        assert!(decision.dry_run);
+        assert!(matches!(decision.request.action, Action::Freeze { pid: 4242, .. }));
+        assert_eq!(decision.request.requested_by, "autonomous:runaway");
+    }
+
+    #[test]
+    fn test_allowlist_protection_and_growth() {
+        let t0 = Instant::now();
+        for culprit in [process("node", 4.0), process("Safari", 4.0), process("Ghostty", 0.5)] {
+            let mut g = guard(5);
+            let m = metrics(99.0, culprit);
+            for _ in 0..5 {
+                assert!(g.evaluate(&m, t0).is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn test_falls_through_to_next_candidate_and_rule() {
+        let t0 = Instant::now();
+        let safari = process("Safari", 8.0);
+        let ghostty = ProcessInfo { pid: 4343, ..process("Ghostty", 4.0) };
+        let m = SystemMetrics { memory_percent: 99.0, top_growers: vec![safari, ghostty], ..SystemMetrics::default() };
+
+        // Safari grows fastest but is protected; Ghostty is next in line
+        let mut g = guard(5);
+        let decision = (0..3).find_map(|_| g.evaluate(&m, t0)).unwrap();
+        assert_eq!(decision.culprit.pid, 4343);
+
+        // A triggered rule with nobody to act on gives way to the next one
+        let mut g = guard(5);
+        g.rules.insert(0, AutonomousRule { name: "node-only".to_string(), allowlist: vec!["node".to_string()], ..g.rules[0].clone() });
+        let decision = (0..3).find_map(|_| g.evaluate(&m, t0)).unwrap();
+        assert_eq!(decision.rule, "runaway");
+        assert_eq!(decision.culprit.pid, 4343);
+    }
+
+    #[test]
+    fn test_hourly_cap() {
+        let mut g = guard(1);
+        let t0 = Instant::now();
+        let hot = metrics(98.0, process("Ghostty", 4.0));
+        let mut decisions = 0;
+        for i in 0..9 {
+            if g.evaluate(&hot, t0 + Duration::from_secs(i * 30)).is_some() {
+                decisions += 1;
+            }
+        }
+        assert_eq!(decisions, 1);
+
+        for i in 0..3 {
+            let later = t0 + Duration::from_secs(3600 + i * 30);
+            if g.evaluate(&hot, later).is_some() {
+                decisions += 1;
+            }
+        }
+        assert_eq!(decisions, 2);
+    }
+}
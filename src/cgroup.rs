@@ -0,0 +1,576 @@
+//! cgroup v2 memory accounting and limits (Linux)
+//!
+//! `CgroupFs` reads and writes the unified hierarchy under a configurable root
+//! (normally /sys/fs/cgroup). `CgroupLimiter` caps a process group with
+//! `memory.high` instead of killing it: a systemd scope or service holding only
+//! that group is capped in place, anything else is first moved into a transient
+//! `sentinel-<pid>.scope` next to its current cgroup. `CgroupMonitor` samples
+//! memory.current, memory.events and memory.stat of watched and limited groups
+//! on every tick and remembers when each last hit its limit.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info, warn};
+
+use crate::config::CgroupConfig;
+use crate::pattern::glob_match;
+
+/// memory.stat keys copied into `CgroupMemory::stat`
+const STAT_KEYS: &[&str] = &["anon", "file", "kernel", "shmem", "sock", "file_dirty", "file_writeback", "pgmajfault"];
+
+/// How deep `CgroupMonitor` descends when matching `watch` patterns
+const WATCH_DEPTH: usize = 8;
+
+/// errno of a cgroup.procs write for a PID that has exited (Linux)
+const ESRCH: i32 = 3;
+
+/// Moves a PID into the cgroup directory by writing its cgroup.procs
+type ProcsWriter = fn(&Path, u32) -> std::io::Result<()>;
+
+/// Cumulative counters from memory.events
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryEvents {
+    /// Reclaimed while under memory.low protection
+    pub low: u64,
+    /// Throttled for exceeding memory.high
+    pub high: u64,
+    /// Usage reached memory.max
+    pub max: u64,
+    /// Allocation failed at memory.max
+    pub oom: u64,
+    /// Processes killed by the OOM killer
+    pub oom_kill: u64,
+}
+
+/// Memory state of one cgroup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CgroupMemory {
+    /// Path relative to the cgroup root, e.g. "user.slice/user-1000.slice/app-ghostty.scope"
+    pub path: String,
+    pub current_bytes: u64,
+    /// memory.high, None when unlimited ("max")
+    pub high_bytes: Option<u64>,
+    /// memory.max, None when unlimited ("max")
+    pub max_bytes: Option<u64>,
+    pub events: MemoryEvents,
+    /// Selected memory.stat counters (bytes, except pgmajfault)
+    pub stat: BTreeMap<String, u64>,
+    /// memory.high was set by this daemon
+    #[serde(default)]
+    pub limited_by_sentinel: bool,
+    /// Last time the group was throttled at memory.high
+    #[serde(default)]
+    pub last_limit_hit: Option<DateTime<Local>>,
+    /// Last time the group hit memory.max, failed an allocation or had a process OOM-killed
+    #[serde(default)]
+    pub last_oom: Option<DateTime<Local>>,
+}
+
+impl CgroupMemory {
+    /// Last path component, e.g. "app-ghostty.scope"
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+/// Access to the cgroup v2 filesystem
+#[derive(Debug, Clone)]
+pub struct CgroupFs {
+    proc_root: PathBuf,
+    root: PathBuf,
+    move_pid: ProcsWriter,
+}
+
+impl CgroupFs {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self::with_roots("/proc", root)
+    }
+
+    /// Use alternative procfs / cgroupfs mount points (for tests)
+    pub fn with_roots(proc_root: impl Into<PathBuf>, root: impl Into<PathBuf>) -> Self {
+        Self { proc_root: proc_root.into(), root: root.into(), move_pid: write_procs }
+    }
+
+    /// Whether a cgroup v2 hierarchy is mounted at the root
+    pub fn is_available(&self) -> bool {
+        self.root.join("cgroup.controllers").exists()
+    }
+
+    pub fn dir(&self, path: &str) -> PathBuf {
+        self.root.join(path.trim_start_matches('/'))
+    }
+
+    /// cgroup v2 path of `pid`, relative to the root ("" for the root cgroup)
+    pub fn cgroup_of(&self, pid: u32) -> Option<String> {
+        let membership = std::fs::read_to_string(self.proc_root.join(pid.to_string()).join("cgroup")).ok()?;
+        // cgroup v2 has a single "0::/path" line
+        let path = membership.lines().find_map(|l| l.strip_prefix("0::"))?.trim();
+        Some(path.trim_start_matches('/').to_string())
+    }
+
+    /// PIDs directly in the cgroup
+    pub fn procs(&self, path: &str) -> Option<HashSet<u32>> {
+        let text = std::fs::read_to_string(self.dir(path).join("cgroup.procs")).ok()?;
+        Some(text.lines().filter_map(|l| l.trim().parse().ok()).collect())
+    }
+
+    /// memory.* files of the cgroup; None if it has no memory controller
+    pub fn read_memory(&self, path: &str) -> Option<CgroupMemory> {
+        let dir = self.dir(path);
+        let current_bytes = read_u64(&dir.join("memory.current"))?;
+
+        let events = std::fs::read_to_string(dir.join("memory.events")).unwrap_or_default();
+        let events = parse_flat_keyed(&events);
+        let stat = std::fs::read_to_string(dir.join("memory.stat")).unwrap_or_default();
+        let stat = parse_flat_keyed(&stat);
+
+        Some(CgroupMemory {
+            path: path.to_string(),
+            current_bytes,
+            high_bytes: read_u64(&dir.join("memory.high")),
+            max_bytes: read_u64(&dir.join("memory.max")),
+            events: MemoryEvents {
+                low: events.get("low").copied().unwrap_or(0),
+                high: events.get("high").copied().unwrap_or(0),
+                max: events.get("max").copied().unwrap_or(0),
+                oom: events.get("oom").copied().unwrap_or(0),
+                oom_kill: events.get("oom_kill").copied().unwrap_or(0),
+            },
+            stat: STAT_KEYS
+                .iter()
+                .filter_map(|k| stat.get(k).map(|v| (k.to_string(), *v)))
+                .collect(),
+            limited_by_sentinel: false,
+            last_limit_hit: None,
+            last_oom: None,
+        })
+    }
+
+    /// Set memory.high; None lifts the limit
+    pub fn set_memory_high(&self, path: &str, bytes: Option<u64>) -> Result<()> {
+        let file = self.dir(path).join("memory.high");
+        let value = bytes.map_or_else(|| "max".to_string(), |b| b.to_string());
+        std::fs::write(&file, value).with_context(|| format!("Failed to write {:?}", file))
+    }
+
+    /// Create `parent/name` and move `pids` into it; returns the new path.
+    /// PIDs that exited meanwhile are skipped. On any other failure the PIDs
+    /// moved so far go back where they came from and the group is removed.
+    pub fn create_transient(&self, parent: &str, name: &str, pids: &[u32]) -> Result<String> {
+        let controllers = std::fs::read_to_string(self.dir(parent).join("cgroup.subtree_control")).unwrap_or_default();
+        if !controllers.split_whitespace().any(|c| c == "memory") {
+            anyhow::bail!("memory controller is not enabled for children of {:?}", self.dir(parent));
+        }
+
+        let path = if parent.is_empty() { name.to_string() } else { format!("{}/{}", parent, name) };
+        let dir = self.dir(&path);
+        std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create cgroup {:?}", dir))?;
+
+        // cgroup.procs takes one PID per write
+        let mut moved: Vec<(u32, String)> = Vec::new();
+        for &pid in pids {
+            let origin = self.cgroup_of(pid);
+            match (self.move_pid)(&dir, pid) {
+                Ok(()) => moved.extend(origin.map(|origin| (pid, origin))),
+                Err(e) if e.raw_os_error() == Some(ESRCH) => debug!("PID {} exited before it could be moved", pid),
+                Err(e) => {
+                    for (pid, origin) in &moved {
+                        if let Err(e) = (self.move_pid)(&self.dir(origin), *pid) {
+                            warn!("Failed to move PID {} back to {}: {}", pid, origin, e);
+                        }
+                    }
+                    if let Err(e) = std::fs::remove_dir(&dir) {
+                        warn!("Failed to remove cgroup {:?}: {}", dir, e);
+                    }
+                    return Err(e).with_context(|| format!("Failed to move PID {} into {:?}", pid, dir));
+                }
+            }
+        }
+        Ok(path)
+    }
+
+    /// Every cgroup path below the root, up to `max_depth` levels
+    pub fn walk(&self, max_depth: usize) -> Vec<String> {
+        let mut found = Vec::new();
+        let mut stack = vec![(String::new(), 0)];
+        while let Some((path, depth)) = stack.pop() {
+            if depth >= max_depth {
+                continue;
+            }
+            let Ok(entries) = std::fs::read_dir(self.dir(&path)) else { continue };
+            for entry in entries.flatten() {
+                if !entry.file_type().is_ok_and(|t| t.is_dir()) {
+                    continue;
+                }
+                let name = entry.file_name().to_string_lossy().to_string();
+                let child = if path.is_empty() { name } else { format!("{}/{}", path, name) };
+                found.push(child.clone());
+                stack.push((child, depth + 1));
+            }
+        }
+        found
+    }
+}
+
+/// A memory.high cap applied by the daemon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CgroupLimit {
+    pub path: String,
+    pub pid: u32,
+    pub name: String,
+    pub memory_high_bytes: u64,
+    /// memory.high before the cap (None for "max"), restored by `unlimit`
+    #[serde(default)]
+    pub previous_high_bytes: Option<u64>,
+    /// The cgroup was created by the daemon and is removed once empty
+    pub transient: bool,
+    pub applied_at: DateTime<Local>,
+}
+
+/// Applies memory.high caps; clones share the registry of applied limits
+#[derive(Debug, Clone)]
+pub struct CgroupLimiter {
+    fs: CgroupFs,
+    limits: Arc<Mutex<BTreeMap<String, CgroupLimit>>>,
+}
+
+impl CgroupLimiter {
+    pub fn new(fs: CgroupFs) -> Self {
+        Self { fs, limits: Arc::new(Mutex::new(BTreeMap::new())) }
+    }
+
+    /// Cap `pids` (the root process first) at `memory_high_bytes`
+    pub fn limit(&self, name: &str, pids: &[u32], memory_high_bytes: u64) -> Result<CgroupLimit> {
+        if !self.fs.is_available() {
+            anyhow::bail!("cgroup v2 is not available on this system");
+        }
+        let &pid = pids.first().context("Nothing to limit")?;
+        let current = self
+            .fs
+            .cgroup_of(pid)
+            .with_context(|| format!("Could not read the cgroup of PID {}", pid))?;
+
+        let wanted: HashSet<u32> = pids.iter().copied().collect();
+        let members = self.fs.procs(&current).unwrap_or_default();
+        let is_unit = current.ends_with(".scope") || current.ends_with(".service");
+
+        let (path, transient) = if is_unit && !members.is_empty() && members.is_subset(&wanted) {
+            (current, false)
+        } else {
+            let parent = current.rsplit_once('/').map_or("", |(parent, _)| parent);
+            let created = self.fs.create_transient(parent, &format!("sentinel-{}.scope", pid), pids)?;
+            (created, true)
+        };
+
+        // A group capped twice keeps the value from before the first cap
+        let previous_high_bytes = match self.limits.lock().unwrap().get(&path) {
+            Some(existing) => existing.previous_high_bytes,
+            None => read_u64(&self.fs.dir(&path).join("memory.high")),
+        };
+        self.fs.set_memory_high(&path, Some(memory_high_bytes))?;
+
+        let limit = CgroupLimit {
+            path: path.clone(),
+            pid,
+            name: name.to_string(),
+            memory_high_bytes,
+            previous_high_bytes,
+            transient,
+            applied_at: Local::now(),
+        };
+        info!("Capped {} (PID {}) at memory.high={} in {}", name, pid, memory_high_bytes, path);
+        self.limits.lock().unwrap().insert(path, limit.clone());
+        Ok(limit)
+    }
+
+    /// Lift the cap this daemon put on `pid`'s group, restoring the previous
+    /// memory.high. Returns `None` if no cap was applied for that PID.
+    pub fn unlimit(&self, pid: u32) -> Result<Option<CgroupLimit>> {
+        let found = self.limits.lock().unwrap().values().find(|l| l.pid == pid).cloned();
+        let Some(limit) = found else {
+            return Ok(None);
+        };
+
+        // The group disappears with its last process
+        if self.fs.dir(&limit.path).exists() {
+            self.fs.set_memory_high(&limit.path, limit.previous_high_bytes)?;
+        }
+        self.limits.lock().unwrap().remove(&limit.path);
+        info!("Lifted the memory.high cap on {} (PID {}) in {}", limit.name, pid, limit.path);
+        Ok(Some(limit))
+    }
+
+    pub fn limited(&self) -> Vec<CgroupLimit> {
+        self.limits.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Forget limits whose cgroup is gone or empty, removing empty transient cgroups
+    pub fn prune(&self) {
+        self.limits.lock().unwrap().retain(|path, limit| {
+            let dir = self.fs.dir(path);
+            let empty = self.fs.procs(path).is_none_or(|p| p.is_empty());
+            if !empty {
+                return true;
+            }
+            if limit.transient && dir.exists() {
+                if let Err(e) = std::fs::remove_dir(&dir) {
+                    warn!("Failed to remove empty cgroup {:?}: {}", dir, e);
+                    return true;
+                }
+            }
+            debug!("Dropping memory limit on {} (no processes left)", path);
+            false
+        });
+    }
+}
+
+/// Per-group state carried between samples
+#[derive(Debug, Default)]
+struct GroupState {
+    events: MemoryEvents,
+    last_limit_hit: Option<DateTime<Local>>,
+    last_oom: Option<DateTime<Local>>,
+}
+
+/// Samples watched and limited cgroups every tick
+pub struct CgroupMonitor {
+    fs: CgroupFs,
+    watch: Vec<String>,
+    state: HashMap<String, GroupState>,
+    /// New OOM kills per group since the last `take_oom_kills`
+    oom_kills: Vec<(String, u64)>,
+}
+
+impl CgroupMonitor {
+    pub fn new(config: &CgroupConfig) -> Self {
+        Self::with_fs(CgroupFs::new(&config.root), config.watch.clone())
+    }
+
+    pub fn with_fs(fs: CgroupFs, watch: Vec<String>) -> Self {
+        Self { fs, watch, state: HashMap::new(), oom_kills: Vec::new() }
+    }
+
+    /// Read every group matching a `watch` pattern plus every group the daemon limited
+    pub fn collect(&mut self, limited: &[CgroupLimit]) -> Vec<CgroupMemory> {
+        if !self.fs.is_available() {
+            return Vec::new();
+        }
+
+        let mut paths: BTreeSet<String> = limited.iter().map(|l| l.path.clone()).collect();
+        if !self.watch.is_empty() {
+            paths.extend(
+                self.fs
+                    .walk(WATCH_DEPTH)
+                    .into_iter()
+                    .filter(|p| self.watch.iter().any(|w| glob_match(w, p))),
+            );
+        }
+
+        let now = Local::now();
+        let mut groups = Vec::new();
+        for path in &paths {
+            let Some(mut group) = self.fs.read_memory(path) else { continue };
+
+            match self.state.get_mut(path) {
+                Some(state) => {
+                    let (before, after) = (state.events, group.events);
+                    if after.high > before.high {
+                        state.last_limit_hit = Some(now);
+                    }
+                    if after.max > before.max || after.oom > before.oom || after.oom_kill > before.oom_kill {
+                        state.last_oom = Some(now);
+                    }
+                    if after.oom_kill > before.oom_kill {
+                        self.oom_kills.push((path.clone(), after.oom_kill - before.oom_kill));
+                    }
+                    state.events = after;
+                }
+                // Counters are cumulative; the first sample only sets the baseline
+                None => {
+                    self.state.insert(path.clone(), GroupState { events: group.events, ..Default::default() });
+                }
+            }
+
+            let state = &self.state[path];
+            group.last_limit_hit = state.last_limit_hit;
+            group.last_oom = state.last_oom;
+            group.limited_by_sentinel = limited.iter().any(|l| &l.path == path);
+            groups.push(group);
+        }
+
+        self.state.retain(|path, _| paths.contains(path));
+        groups
+    }
+
+    /// OOM kills seen in memory.events since the last call, as (group, count)
+    pub fn take_oom_kills(&mut self) -> Vec<(String, u64)> {
+        std::mem::take(&mut self.oom_kills)
+    }
+}
+
+fn write_procs(dir: &Path, pid: u32) -> std::io::Result<()> {
+    std::fs::write(dir.join("cgroup.procs"), pid.to_string())
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// "key value" lines, as in memory.events and memory.stat
+fn parse_flat_keyed(text: &str) -> HashMap<&str, u64> {
+    text.lines()
+        .filter_map(|l| {
+            let (key, value) = l.split_once(' ')?;
+            Some((key, value.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fake /proc and cgroup2 mount below the temp directory
+    fn cgroup_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("sentinel-cgroup-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("cgroup")).unwrap();
+        std::fs::write(root.join("cgroup/cgroup.controllers"), "cpu memory pids").unwrap();
+        root
+    }
+
+    fn cgroup_fs(root: &Path) -> CgroupFs {
+        CgroupFs::with_roots(root.join("proc"), root.join("cgroup"))
+    }
+
+    fn group(root: &Path, path: &str, procs: &[u32], current: u64, events: &str) {
+        let dir = root.join("cgroup").join(path);
+        std::fs::create_dir_all(&dir).unwrap();
+        let procs: String = procs.iter().map(|p| format!("{}\n", p)).collect();
+        std::fs::write(dir.join("cgroup.procs"), procs).unwrap();
+        std::fs::write(dir.join("memory.current"), current.to_string()).unwrap();
+        std::fs::write(dir.join("memory.high"), "max\n").unwrap();
+        std::fs::write(dir.join("memory.max"), "max\n").unwrap();
+        std::fs::write(dir.join("memory.events"), events).unwrap();
+        std::fs::write(dir.join("memory.stat"), "anon 100\nfile 200\nslab 5\n").unwrap();
+    }
+
+    fn process(root: &Path, pid: u32, path: &str) {
+        let dir = root.join("proc").join(pid.to_string());
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("cgroup"), format!("0::/{}\n", path)).unwrap();
+    }
+
+    #[test]
+    fn test_limit_in_place_or_transient() {
+        let root = cgroup_root("limit");
+        group(&root, "app.slice", &[], 0, "");
+        std::fs::write(root.join("cgroup/app.slice/cgroup.subtree_control"), "memory pids").unwrap();
+        group(&root, "app.slice/app-ghostty.scope", &[100, 101], 1 << 30, "");
+        group(&root, "app.slice/shared.service", &[200, 300], 1 << 30, "");
+        process(&root, 100, "app.slice/app-ghostty.scope");
+        process(&root, 200, "app.slice/shared.service");
+        std::fs::write(root.join("cgroup/app.slice/app-ghostty.scope/memory.high"), "8589934592\n").unwrap();
+
+        let limiter = CgroupLimiter::new(cgroup_fs(&root));
+
+        // A scope holding only the group is capped in place
+        let limit = limiter.limit("ghostty", &[100, 101], 4 << 30).unwrap();
+        assert_eq!(limit.path, "app.slice/app-ghostty.scope");
+        assert!(!limit.transient);
+        let high = std::fs::read_to_string(root.join("cgroup/app.slice/app-ghostty.scope/memory.high")).unwrap();
+        assert_eq!(high, (4u64 << 30).to_string());
+
+        // A shared unit gets a transient sibling instead
+        let limit = limiter.limit("worker", &[200], 1 << 30).unwrap();
+        assert_eq!(limit.path, "app.slice/sentinel-200.scope");
+        assert!(limit.transient);
+        let procs = std::fs::read_to_string(root.join("cgroup/app.slice/sentinel-200.scope/cgroup.procs")).unwrap();
+        assert_eq!(procs, "200");
+        assert_eq!(limiter.limited().len(), 2);
+
+        // Lifting a cap restores what was there before the first one
+        limiter.limit("ghostty", &[100, 101], 2 << 30).unwrap();
+        assert_eq!(limiter.unlimit(100).unwrap().unwrap().previous_high_bytes, Some(8 << 30));
+        let high = std::fs::read_to_string(root.join("cgroup/app.slice/app-ghostty.scope/memory.high")).unwrap();
+        assert_eq!(high, "8589934592");
+        assert!(limiter.unlimit(200).unwrap().unwrap().previous_high_bytes.is_none());
+        let high = std::fs::read_to_string(root.join("cgroup/app.slice/sentinel-200.scope/memory.high")).unwrap();
+        assert_eq!(high, "max");
+        assert!(limiter.unlimit(200).unwrap().is_none());
+        assert!(limiter.limited().is_empty());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    thread_local! {
+        static MOVES: std::cell::RefCell<Vec<(String, u32)>> = const { std::cell::RefCell::new(Vec::new()) };
+    }
+
+    /// Records moves instead of writing cgroup.procs. PID 666 has exited and
+    /// PID 999 may not be moved.
+    fn fake_move(dir: &Path, pid: u32) -> std::io::Result<()> {
+        match pid {
+            666 => Err(std::io::Error::from_raw_os_error(ESRCH)),
+            999 => Err(std::io::Error::from(std::io::ErrorKind::PermissionDenied)),
+            _ => {
+                let group = dir.file_name().unwrap().to_string_lossy().to_string();
+                MOVES.with(|m| m.borrow_mut().push((group, pid)));
+                Ok(())
+            }
+        }
+    }
+
+    #[test]
+    fn test_transient_skips_exited_and_rolls_back() {
+        let root = cgroup_root("transient");
+        group(&root, "app.slice", &[], 0, "");
+        std::fs::write(root.join("cgroup/app.slice/cgroup.subtree_control"), "memory").unwrap();
+        group(&root, "app.slice/shared.service", &[200, 201, 300], 1 << 30, "");
+        for pid in [200, 201, 999] {
+            process(&root, pid, "app.slice/shared.service");
+        }
+        let fs = CgroupFs { move_pid: fake_move, ..cgroup_fs(&root) };
+        let moves = || MOVES.with(|m| m.borrow_mut().drain(..).collect::<Vec<_>>());
+
+        let path = fs.create_transient("app.slice", "sentinel-200.scope", &[200, 666, 201]).unwrap();
+        assert_eq!(path, "app.slice/sentinel-200.scope");
+        assert_eq!(moves(), vec![("sentinel-200.scope".to_string(), 200), ("sentinel-200.scope".to_string(), 201)]);
+
+        let err = fs.create_transient("app.slice", "sentinel-201.scope", &[201, 999]).unwrap_err();
+        assert!(format!("{:#}", err).contains("PID 999"), "{:#}", err);
+        assert_eq!(moves(), vec![("sentinel-201.scope".to_string(), 201), ("shared.service".to_string(), 201)]);
+        assert!(!root.join("cgroup/app.slice/sentinel-201.scope").exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_monitor_tracks_events() {
+        let root = cgroup_root("monitor");
+        group(&root, "user.slice/app-ghostty.scope", &[100], 3 << 30, "low 0\nhigh 5\nmax 0\noom 0\noom_kill 0\n");
+        group(&root, "user.slice/other.scope", &[200], 1 << 20, "high 0\n");
+
+        let mut monitor = CgroupMonitor::with_fs(cgroup_fs(&root), vec!["*ghostty*".to_string()]);
+        let groups = monitor.collect(&[]);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].name(), "app-ghostty.scope");
+        assert_eq!(groups[0].current_bytes, 3 << 30);
+        assert_eq!(groups[0].high_bytes, None);
+        assert_eq!(groups[0].events.high, 5);
+        assert_eq!(groups[0].stat.get("file"), Some(&200));
+        assert!(!groups[0].stat.contains_key("slab"));
+        // Pre-existing counters are only a baseline
+        assert!(groups[0].last_limit_hit.is_none());
+
+        group(&root, "user.slice/app-ghostty.scope", &[100], 3 << 30, "low 0\nhigh 9\nmax 0\noom 0\noom_kill 1\n");
+        let groups = monitor.collect(&[]);
+        assert!(groups[0].last_limit_hit.is_some());
+        assert!(groups[0].last_oom.is_some());
+        assert_eq!(monitor.take_oom_kills(), vec![("user.slice/app-ghostty.scope".to_string(), 1)]);
+        assert!(monitor.take_oom_kills().is_empty());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -1,11 +1,13 @@
 //! Configuration loading and defaults
 
 use anyhow::{Context, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use crate::detector::{AlertLevel, AnomalyType};
+use regex::Regex;
 use std::fs;
 use std::path::PathBuf;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, Default)]
 pub struct Config {
     #[serde(default)]
     pub general: GeneralConfig,
@@ -15,6 +17,42 @@ pub struct Config {
     pub detection: DetectionConfig,
     #[serde(default)]
     pub notification: NotificationConfig,
+    #[serde(default)]
+    pub narration: NarrationConfig,
+    #[serde(default)]
+    pub escalation: EscalationConfig,
+    #[serde(default)]
+    pub templates: Vec<TemplateConfig>,
+    #[serde(default)]
+    pub policy: PolicyConfig,
+    #[serde(default)]
+    pub actions: ActionsConfig,
+    #[serde(default)]
+    pub autonomous: AutonomousConfig,
+    #[serde(default)]
+    pub cgroups: CgroupConfig,
+    #[serde(default)]
+    pub kernel_events: KernelEventsConfig,
+    #[serde(default)]
+    pub history: HistoryConfig,
+    #[serde(default)]
+    pub probes: Vec<ProbeConfig>,
+    #[serde(default)]
+    pub plugins: Vec<PluginConfig>,
+    #[serde(default)]
+    pub scripting: ScriptingConfig,
+    #[serde(default)]
+    pub rules: Vec<RuleConfig>,
+    #[serde(default)]
+    pub inhibitions: Vec<InhibitionConfig>,
+    #[serde(default)]
+    pub thermal: ThermalConfig,
+    #[serde(default)]
+    pub disk_io: DiskIoConfig,
+    #[serde(default)]
+    pub network: NetworkConfig,
+    #[serde(default)]
+    pub handles: HandlesConfig,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -43,6 +81,12 @@ pub struct ThresholdConfig {
     pub load_warning: f64,
     #[serde(default = "default_load_critical")]
     pub load_critical: f64,
+    /// Load per logical core; when set, these replace load_warning and
+    /// load_critical so one config fits machines of any size
+    #[serde(default)]
+    pub load_per_core_warning: Option<f64>,
+    #[serde(default)]
+    pub load_per_core_critical: Option<f64>,
     #[serde(default = "default_memory_growth_rate_warning")]
     pub memory_growth_rate_warning: f64,
     #[serde(default = "default_memory_growth_rate_critical")]
@@ -61,6 +105,63 @@ pub struct DetectionConfig {
     pub notification_cooldown_minutes: u64,
     #[serde(default = "default_persistent_breach_threshold")]
     pub persistent_breach_threshold: u32,
+    /// Alert when the same executable is restarted this many times...
+    #[serde(default = "default_restart_loop_count")]
+    pub restart_loop_count: u32,
+    /// ...within this many minutes
+    #[serde(default = "default_restart_loop_window_minutes")]
+    pub restart_loop_window_minutes: u64,
+    /// Processes (globs on name or executable path) whose exit is Critical
+    /// until they are running again
+    #[serde(default)]
+    pub must_run: Vec<String>,
+    /// Processes that should always be running
+    #[serde(default)]
+    pub required_processes: Vec<RequiredProcess>,
+    /// A process using at least this much CPU (percent of one core)...
+    #[serde(default = "default_cpu_pinned_percent")]
+    pub cpu_pinned_percent: f64,
+    /// ...for this many minutes is reported as pinned
+    #[serde(default = "default_cpu_pinned_minutes")]
+    pub cpu_pinned_minutes: u64,
+    /// Processes (globs on name or executable path) allowed to keep a core busy
+    #[serde(default)]
+    pub cpu_pinned_ignore: Vec<String>,
+}
+
+/// A process that should be running. Every matcher that is set must match;
+/// `name` is compared case-insensitively, `exe` is a glob on the executable
+/// path, `regex` is tried against both name and executable path.
+#[derive(Debug, Deserialize, Clone)]
+pub struct RequiredProcess {
+    /// Shown in alerts; defaults to the first matcher
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub exe: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_regex")]
+    pub regex: Option<Regex>,
+    /// How long it may be absent before alerting (covers restarts and login)
+    #[serde(default = "default_required_grace_seconds")]
+    pub grace_seconds: u64,
+}
+
+impl RequiredProcess {
+    pub fn label(&self) -> String {
+        self.label
+            .clone()
+            .or_else(|| self.name.clone())
+            .or_else(|| self.exe.clone())
+            .or_else(|| self.regex.as_ref().map(|r| r.as_str().to_string()))
+            .unwrap_or_else(|| "required process".to_string())
+    }
+}
+
+fn deserialize_regex<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<Regex>, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+    Regex::new(&pattern).map(Some).map_err(serde::de::Error::custom)
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -73,6 +174,476 @@ pub struct NotificationConfig {
     pub warning_color: String,
     #[serde(default = "default_critical_color")]
     pub critical_color: String,
+    /// Windows during which Warnings are buffered into a digest instead of shown
+    #[serde(default)]
+    pub quiet_hours: Vec<QuietHoursConfig>,
+    /// Outside quiet hours, also digest Warnings on this interval (0 = disabled)
+    #[serde(default)]
+    pub digest_interval_minutes: u64,
+}
+
+/// A daily quiet-hours window, e.g. `{ start = "22:30", end = "07:00" }`.
+/// Windows where `end` is earlier than `start` wrap past midnight.
+#[derive(Debug, Deserialize, Clone)]
+pub struct QuietHoursConfig {
+    pub start: String,
+    pub end: String,
+}
+
+/// Spoken alerts: TTS daemon socket, with a local command as fallback
+#[derive(Debug, Deserialize, Clone)]
+pub struct NarrationConfig {
+    #[serde(default = "default_narration_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_tts_socket_path")]
+    pub socket_path: String,
+    #[serde(default = "default_tts_voice")]
+    pub voice: String,
+    /// Base directory for relative sound paths (~ expanded)
+    #[serde(default = "default_sound_dir")]
+    pub sound_dir: String,
+    #[serde(default = "default_warning_sound")]
+    pub warning_sound: Option<String>,
+    #[serde(default = "default_critical_sound")]
+    pub critical_sound: Option<String>,
+    #[serde(default = "default_tts_connect_timeout_ms")]
+    pub connect_timeout_ms: u64,
+    #[serde(default = "default_tts_read_timeout_ms")]
+    pub read_timeout_ms: u64,
+    /// Local TTS used when the daemon socket is missing; the text is appended
+    /// as the last argument. Empty disables the fallback.
+    #[serde(default = "default_fallback_tts_command")]
+    pub fallback_command: Vec<String>,
+    /// Local audio player for sound files in fallback mode; the file is appended
+    #[serde(default = "default_fallback_audio_player")]
+    pub fallback_player: Vec<String>,
+    /// Narrations arriving within this window are merged into one utterance
+    #[serde(default = "default_narration_merge_window_ms")]
+    pub merge_window_ms: u64,
+    /// Minimum pause between two spoken alerts
+    #[serde(default = "default_narration_min_gap_seconds")]
+    pub min_gap_seconds: u64,
+    /// Cap on spoken alerts per rolling hour; overflow is shown visually only (0 = no cap)
+    #[serde(default = "default_narration_max_per_hour")]
+    pub max_per_hour: u32,
+}
+
+/// Escalation chain for Critical alerts that nobody acknowledges
+#[derive(Debug, Deserialize, Clone)]
+pub struct EscalationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_escalation_steps")]
+    pub steps: Vec<EscalationStep>,
+}
+
+/// One step of an escalation chain, fired `after_minutes` into an unacknowledged Critical
+#[derive(Debug, Deserialize, Clone)]
+pub struct EscalationStep {
+    #[serde(default)]
+    pub after_minutes: f64,
+    pub channel: EscalationChannel,
+    /// Webhook URL or email address (required for those channels)
+    #[serde(default)]
+    pub target: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EscalationChannel {
+    Popup,
+    Narration,
+    Webhook,
+    Email,
+}
+
+/// Override for alert wording. Leave `anomaly_type` or `level` unset to
+/// apply to all types or levels; the most specific matching entry wins.
+/// Placeholders: {value}, {threshold}, {culprit}, {rate}, {duration}, {level}, {type},
+/// with optional precision, e.g. {value:.1}. Kernel events also set {pid}, {group}
+/// and {detail}; load alerts set {load}, {load_5m}, {load_15m}, {cores} and {trend}.
+#[derive(Debug, Deserialize, Clone)]
+pub struct TemplateConfig {
+    #[serde(default)]
+    pub anomaly_type: Option<AnomalyType>,
+    #[serde(default)]
+    pub level: Option<AlertLevel>,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub details: Option<Vec<String>>,
+    #[serde(default)]
+    pub narration: Option<String>,
+}
+
+/// Safety policy applied before any action signals a process
+#[derive(Debug, Deserialize, Clone)]
+pub struct PolicyConfig {
+    /// Glob patterns (case-insensitive) matched against process name,
+    /// executable path, executable file name and app bundle name
+    #[serde(default = "default_protected_processes")]
+    pub protected_processes: Vec<String>,
+}
+
+/// Remediation actions executed by the daemon on behalf of clients
+#[derive(Debug, Deserialize, Clone)]
+pub struct ActionsConfig {
+    /// Append-only JSONL record of every requested action (~ expanded)
+    #[serde(default = "default_audit_log")]
+    pub audit_log: String,
+    /// Command used by the "purge" action to drop filesystem caches
+    #[serde(default = "default_purge_command")]
+    pub purge_command: Vec<String>,
+    /// How long a killed process gets to exit after SIGTERM before SIGKILL
+    #[serde(default = "default_kill_grace_seconds")]
+    pub kill_grace_seconds: f64,
+    /// Frozen processes are thawed automatically after this long unless the request says otherwise
+    #[serde(default = "default_freeze_timeout_seconds")]
+    pub freeze_timeout_seconds: u64,
+}
+
+/// Opt-in protective actions the daemon takes without asking (Tier 3)
+#[derive(Debug, Deserialize, Clone)]
+pub struct AutonomousConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Only log what would have been done
+    #[serde(default = "default_autonomous_dry_run")]
+    pub dry_run: bool,
+    /// Rolling hourly cap on autonomous actions (dry-run decisions count too)
+    #[serde(default = "default_autonomous_max_actions_per_hour")]
+    pub max_actions_per_hour: u32,
+    /// Never acted on autonomously, in addition to `[policy] protected_processes`
+    #[serde(default)]
+    pub protected_processes: Vec<String>,
+    #[serde(default)]
+    pub rules: Vec<AutonomousRule>,
+}
+
+/// "If `metric` >= `threshold` for `consecutive_ticks` ticks and the fastest-growing
+/// process matches `allowlist`, apply `action` to it"
+#[derive(Debug, Deserialize, Clone)]
+pub struct AutonomousRule {
+    pub name: String,
+    pub metric: AutonomousMetric,
+    pub threshold: f64,
+    #[serde(default = "default_autonomous_consecutive_ticks")]
+    pub consecutive_ticks: u32,
+    pub action: AutonomousAction,
+    /// Globs matched against the culprit's name, executable and app bundle.
+    /// An empty allowlist matches nothing.
+    #[serde(default)]
+    pub allowlist: Vec<String>,
+    /// The culprit must be growing at least this fast (GB/hour)
+    #[serde(default)]
+    pub min_growth_gb_per_hour: f64,
+    /// Also act on the culprit's descendants
+    #[serde(default)]
+    pub tree: bool,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AutonomousMetric {
+    MemoryPercent,
+    SwapPercent,
+    Load1m,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AutonomousAction {
+    Freeze,
+    Kill,
+}
+
+/// cgroup v2 memory accounting and limits (Linux only)
+#[derive(Debug, Deserialize, Clone)]
+pub struct CgroupConfig {
+    /// Mount point of the unified hierarchy
+    #[serde(default = "default_cgroup_root")]
+    pub root: String,
+    /// Globs matched against cgroup paths relative to `root` whose memory is
+    /// reported every tick (groups capped by the daemon are always reported)
+    #[serde(default)]
+    pub watch: Vec<String>,
+    /// How long a group stays in alert after hitting its limit
+    #[serde(default = "default_cgroup_hit_window_minutes")]
+    pub hit_window_minutes: u64,
+}
+
+/// Temperature and throttling alerts from sysfs sensors (Linux only)
+#[derive(Debug, Deserialize, Clone)]
+pub struct ThermalConfig {
+    /// Where sysfs is mounted
+    #[serde(default = "default_sysfs_root")]
+    pub sysfs_root: String,
+    /// Hottest sensor, in degrees Celsius
+    #[serde(default = "default_temperature_warning")]
+    pub temperature_warning: f64,
+    #[serde(default = "default_temperature_critical")]
+    pub temperature_critical: f64,
+    /// How long heat or throttling must last before it alerts
+    #[serde(default = "default_thermal_sustain_seconds")]
+    pub sustain_seconds: u64,
+    /// Throttled: average CPU frequency below this fraction of its maximum...
+    #[serde(default = "default_throttle_ratio")]
+    pub throttle_ratio: f64,
+    /// ...while overall CPU usage is at least this percent
+    #[serde(default = "default_throttle_min_cpu_percent")]
+    pub throttle_min_cpu_percent: f64,
+    /// Sensors left out (globs on "chip/label", e.g. "thermal_zone*/acpitz")
+    #[serde(default)]
+    pub ignore: Vec<String>,
+}
+
+/// I/O saturation alerts from /proc/diskstats (Linux only). A device is
+/// saturated while it is busy at least `utilization_percent` of the time and
+/// requests take at least `await_warning_ms` on average.
+#[derive(Debug, Deserialize, Clone)]
+pub struct DiskIoConfig {
+    #[serde(default = "default_io_utilization_percent")]
+    pub utilization_percent: f64,
+    #[serde(default = "default_io_await_warning_ms")]
+    pub await_warning_ms: f64,
+    #[serde(default = "default_io_await_critical_ms")]
+    pub await_critical_ms: f64,
+    /// How long saturation must last before it alerts
+    #[serde(default = "default_io_sustain_seconds")]
+    pub sustain_seconds: u64,
+    /// Devices left out (globs, e.g. "sdb" for a backup disk)
+    #[serde(default)]
+    pub ignore: Vec<String>,
+}
+
+/// Network metrics and alerts (Linux only)
+#[derive(Debug, Deserialize, Clone)]
+pub struct NetworkConfig {
+    /// Count TCP sockets per process (reads every process's open descriptors)
+    #[serde(default)]
+    pub per_process_sockets: bool,
+    /// Upload spike: an interface sending at least this many MB/s...
+    #[serde(default = "default_upload_spike_min_mb_per_sec")]
+    pub upload_spike_min_mb_per_sec: f64,
+    /// ...and at least this many times its usual rate, the median of the
+    /// last `baseline_samples` ticks
+    #[serde(default = "default_upload_spike_factor")]
+    pub upload_spike_factor: f64,
+    #[serde(default = "default_upload_baseline_samples")]
+    pub baseline_samples: usize,
+    /// Sockets in CLOSE_WAIT (the process never closed them), per process
+    /// when counted, else system-wide
+    #[serde(default = "default_close_wait_warning")]
+    pub close_wait_warning: u32,
+    /// TCP sockets held by one process
+    #[serde(default = "default_process_sockets_warning")]
+    pub process_sockets_warning: u32,
+    /// Interfaces left out of upload spike detection (globs)
+    #[serde(default = "default_network_ignore_interfaces")]
+    pub ignore_interfaces: Vec<String>,
+}
+
+/// Descriptor and thread leak alerts, and the system file table (Linux only).
+/// A process leaks when it holds at least the minimum and the count has kept
+/// growing by the given amount per hour over the last 5 to 30 minutes.
+#[derive(Debug, Deserialize, Clone)]
+pub struct HandlesConfig {
+    #[serde(default = "default_fd_growth_per_hour")]
+    pub fd_growth_per_hour: f64,
+    #[serde(default = "default_fd_minimum")]
+    pub fd_minimum: u64,
+    #[serde(default = "default_thread_growth_per_hour")]
+    pub thread_growth_per_hour: f64,
+    #[serde(default = "default_thread_minimum")]
+    pub thread_minimum: u64,
+    /// Share of fs.file-max in use (/proc/sys/fs/file-nr)
+    #[serde(default = "default_file_table_warning_percent")]
+    pub file_table_warning_percent: f64,
+    #[serde(default = "default_file_table_critical_percent")]
+    pub file_table_critical_percent: f64,
+    /// Processes left out of leak detection (globs on name or executable)
+    #[serde(default)]
+    pub ignore: Vec<String>,
+}
+
+/// OOM-kill and crash detection from kernel messages
+#[derive(Debug, Deserialize, Clone)]
+pub struct KernelEventsConfig {
+    #[serde(default = "default_kernel_events_enabled")]
+    pub enabled: bool,
+    /// Tail this file (e.g. /var/log/kern.log) instead of reading /dev/kmsg
+    #[serde(default)]
+    pub log_file: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeKind {
+    Tcp,
+    Http,
+    Unix,
+}
+
+/// A local service that should answer
+#[derive(Debug, Deserialize, Clone)]
+pub struct ProbeConfig {
+    pub name: String,
+    pub kind: ProbeKind,
+    /// "host:port" (tcp), "http://host:port/path" (http) or a socket path (unix)
+    pub target: String,
+    #[serde(default = "default_probe_interval_seconds")]
+    pub interval_seconds: u64,
+    #[serde(default = "default_probe_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Written after connecting (tcp/unix)
+    #[serde(default)]
+    pub send: Option<String>,
+    /// Substring the response (the body, for http) must contain. For tcp and
+    /// unix probes the response is read until the peer closes the connection.
+    #[serde(default)]
+    pub expect: Option<String>,
+    /// HTTP status that counts as healthy; any 2xx or 3xx when unset
+    #[serde(default)]
+    pub expect_status: Option<u16>,
+    /// Slower answers count as failures
+    #[serde(default)]
+    pub max_latency_ms: Option<u64>,
+    /// Alert level while the probe fails
+    #[serde(default = "default_probe_level")]
+    pub level: AlertLevel,
+    /// Successes in a row needed before a failing probe counts as healthy again
+    #[serde(default = "default_probe_recover_after")]
+    pub recover_after: u32,
+}
+
+/// An external check following the Nagios plugin convention
+#[derive(Debug, Deserialize, Clone)]
+pub struct PluginConfig {
+    /// Anomaly type name used in alerts and history, e.g. "backup_freshness"
+    pub name: String,
+    /// Program and arguments
+    pub command: Vec<String>,
+    #[serde(default = "default_plugin_interval_seconds")]
+    pub interval_seconds: u64,
+    #[serde(default = "default_plugin_timeout_seconds")]
+    pub timeout_seconds: u64,
+    /// Alert level for UNKNOWN results (exit code 3, timeouts, failures to run)
+    #[serde(default = "default_plugin_unknown_level")]
+    pub unknown_level: AlertLevel,
+}
+
+/// A threshold rule over any metric path, e.g. `disks./.used_percent`
+#[derive(Debug, Deserialize, Clone)]
+pub struct RuleConfig {
+    pub name: String,
+    pub metric: String,
+    #[serde(default)]
+    pub warning: Option<f64>,
+    #[serde(default)]
+    pub critical: Option<f64>,
+    #[serde(default)]
+    pub direction: RuleDirection,
+    // The options below are inherited from the built-in rule being replaced
+    // while unset, and default to off/zero/none otherwise
+    /// Divide the value by the number of logical cores before comparing
+    #[serde(default)]
+    pub per_core: Option<bool>,
+    /// How long the breach must last before the rule fires
+    #[serde(default)]
+    pub sustain_seconds: Option<u64>,
+    /// Once firing, thresholds are relaxed by this much until recovery
+    #[serde(default)]
+    pub recovery_margin: Option<f64>,
+    /// Rules that suppress this one while they fire; `[]` removes inherited ones
+    #[serde(default)]
+    pub inhibited_by: Option<Vec<String>>,
+    /// Conditions on other metrics the breach must also meet; `[]` removes
+    /// inherited ones
+    #[serde(default)]
+    pub gates: Option<Vec<RuleGate>>,
+    /// Process named in the alert
+    #[serde(default)]
+    pub culprit: Option<RuleCulprit>,
+    /// Templates overriding the built-in rule text (same placeholders plus
+    /// {rule} and {metric})
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub narration: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleDirection {
+    /// Fires when the value is at or above the thresholds
+    #[default]
+    Above,
+    /// Fires when the value is at or below the thresholds
+    Below,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleCulprit {
+    #[default]
+    None,
+    /// Top memory user
+    Memory,
+    /// Top CPU user
+    Cpu,
+}
+
+/// A correlation gate: one condition, or `any` of several
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum RuleGate {
+    Any { any: Vec<RuleCondition> },
+    Condition(RuleCondition),
+}
+
+/// Holds while the metric is strictly above `above` and below `below`
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct RuleCondition {
+    pub metric: String,
+    #[serde(default)]
+    pub above: Option<f64>,
+    #[serde(default)]
+    pub below: Option<f64>,
+}
+
+/// `source` suppresses `targets` while it is raised. Names are rule names,
+/// plugin or script alert types, or built-in types ("process_missing").
+#[derive(Debug, Deserialize, Clone)]
+pub struct InhibitionConfig {
+    pub source: String,
+    pub targets: Vec<String>,
+}
+
+/// Custom detection rules in Rhai scripts
+#[derive(Debug, Deserialize, Clone)]
+pub struct ScriptingConfig {
+    /// Script files (~ expanded), run in order every tick
+    #[serde(default)]
+    pub scripts: Vec<String>,
+    /// Rhai operations a script may execute per run
+    #[serde(default = "default_script_max_operations")]
+    pub max_operations: u64,
+    /// Wall time a script may take per run
+    #[serde(default = "default_script_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Past ticks reachable through history(n)
+    #[serde(default = "default_script_history_len")]
+    pub history_len: usize,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct HistoryConfig {
+    /// Delivered alerts kept in memory for the `history` IPC command
+    #[serde(default = "default_history_max_entries")]
+    pub max_entries: usize,
+    /// Samples kept per metric series (plugin perfdata)
+    #[serde(default = "default_history_max_samples")]
+    pub max_samples: usize,
 }
 
 // Default value functions
@@ -93,8 +664,109 @@ fn default_process_watchlist() -> Vec<String> {
 fn default_process_memory_threshold_mb() -> u64 { 2000 }
 fn default_notification_cooldown_minutes() -> u64 { 20 }
 fn default_persistent_breach_threshold() -> u32 { 3 }
+fn default_restart_loop_count() -> u32 { 5 }
+fn default_restart_loop_window_minutes() -> u64 { 10 }
+fn default_cpu_pinned_percent() -> f64 { 90.0 }
+fn default_cpu_pinned_minutes() -> u64 { 15 }
+fn default_required_grace_seconds() -> u64 { 60 }
 fn default_use_hammerspoon() -> bool { true }
 fn default_fallback_to_terminal_notifier() -> bool { true }
+fn default_narration_enabled() -> bool { true }
+fn default_tts_socket_path() -> String { "/tmp/claude-tts-daemon.sock".to_string() }
+fn default_tts_voice() -> String { "lucy".to_string() }
+fn default_sound_dir() -> String { "~/.claude/hooks".to_string() }
+fn default_warning_sound() -> Option<String> { Some("sounds/subtle/alien_button.wav".to_string()) }
+fn default_critical_sound() -> Option<String> { Some("sounds/unused/Futuristic Hum 2133.wav".to_string()) }
+fn default_tts_connect_timeout_ms() -> u64 { 500 }
+fn default_tts_read_timeout_ms() -> u64 { 2000 }
+fn default_fallback_tts_command() -> Vec<String> {
+    if cfg!(target_os = "macos") {
+        vec!["say".to_string()]
+    } else {
+        vec!["espeak-ng".to_string()]
+    }
+}
+fn default_fallback_audio_player() -> Vec<String> {
+    if cfg!(target_os = "macos") {
+        vec!["afplay".to_string()]
+    } else {
+        vec!["paplay".to_string()]
+    }
+}
+fn default_narration_merge_window_ms() -> u64 { 3000 }
+fn default_narration_min_gap_seconds() -> u64 { 5 }
+fn default_narration_max_per_hour() -> u32 { 12 }
+fn default_protected_processes() -> Vec<String> {
+    [
+        "Terminal", "Ghostty", "Code", "Visual Studio Code", "Zed", "Safari", "Arc", "Claude", "Finder",
+        "launchd", "kernel_task", "WindowServer", "loginwindow", "Dock", "SystemUIServer",
+        "systemd", "sshd", "system-sentinel", "sentinel-ui",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+fn default_audit_log() -> String { "~/.local/share/system-sentinel/audit.jsonl".to_string() }
+fn default_kill_grace_seconds() -> f64 { 5.0 }
+fn default_freeze_timeout_seconds() -> u64 { 900 }
+fn default_kernel_events_enabled() -> bool { true }
+fn default_history_max_entries() -> usize { 500 }
+fn default_history_max_samples() -> usize { 1440 }
+fn default_script_max_operations() -> u64 { 200_000 }
+fn default_script_timeout_ms() -> u64 { 50 }
+fn default_script_history_len() -> usize { 20 }
+fn default_plugin_interval_seconds() -> u64 { 300 }
+fn default_plugin_timeout_seconds() -> u64 { 30 }
+fn default_plugin_unknown_level() -> AlertLevel { AlertLevel::Warning }
+fn default_probe_interval_seconds() -> u64 { 30 }
+fn default_probe_timeout_ms() -> u64 { 2000 }
+fn default_probe_level() -> AlertLevel { AlertLevel::Warning }
+fn default_probe_recover_after() -> u32 { 2 }
+fn default_cgroup_root() -> String { "/sys/fs/cgroup".to_string() }
+fn default_cgroup_hit_window_minutes() -> u64 { 5 }
+fn default_sysfs_root() -> String { "/sys".to_string() }
+fn default_io_utilization_percent() -> f64 { 90.0 }
+fn default_io_await_warning_ms() -> f64 { 50.0 }
+fn default_io_await_critical_ms() -> f64 { 500.0 }
+fn default_io_sustain_seconds() -> u64 { 60 }
+fn default_upload_spike_min_mb_per_sec() -> f64 { 5.0 }
+fn default_upload_spike_factor() -> f64 { 10.0 }
+fn default_upload_baseline_samples() -> usize { 20 }
+fn default_close_wait_warning() -> u32 { 1000 }
+fn default_process_sockets_warning() -> u32 { 10_000 }
+fn default_network_ignore_interfaces() -> Vec<String> {
+    vec!["lo".to_string(), "veth*".to_string(), "docker*".to_string(), "br-*".to_string()]
+}
+fn default_fd_growth_per_hour() -> f64 { 500.0 }
+fn default_fd_minimum() -> u64 { 1000 }
+fn default_thread_growth_per_hour() -> f64 { 200.0 }
+fn default_thread_minimum() -> u64 { 500 }
+fn default_file_table_warning_percent() -> f64 { 80.0 }
+fn default_file_table_critical_percent() -> f64 { 95.0 }
+fn default_temperature_warning() -> f64 { 85.0 }
+fn default_temperature_critical() -> f64 { 95.0 }
+fn default_thermal_sustain_seconds() -> u64 { 120 }
+fn default_throttle_ratio() -> f64 { 0.6 }
+fn default_throttle_min_cpu_percent() -> f64 { 70.0 }
+fn default_autonomous_dry_run() -> bool { true }
+fn default_autonomous_max_actions_per_hour() -> u32 { 2 }
+fn default_autonomous_consecutive_ticks() -> u32 { 3 }
+fn default_purge_command() -> Vec<String> {
+    if cfg!(target_os = "macos") {
+        vec!["sudo".to_string(), "-n".to_string(), "purge".to_string()]
+    } else {
+        ["sudo", "-n", "sh", "-c", "sync && echo 3 > /proc/sys/vm/drop_caches"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+}
+fn default_escalation_steps() -> Vec<EscalationStep> {
+    vec![
+        EscalationStep { after_minutes: 0.0, channel: EscalationChannel::Popup, target: None },
+        EscalationStep { after_minutes: 2.0, channel: EscalationChannel::Narration, target: None },
+    ]
+}
 fn default_warning_color() -> String { "#FFA500".to_string() }
 fn default_critical_color() -> String { "#FF4444".to_string() }
 
@@ -117,6 +789,8 @@ impl Default for ThresholdConfig {
             swap_critical: default_swap_critical(),
             load_warning: default_load_warning(),
             load_critical: default_load_critical(),
+            load_per_core_warning: None,
+            load_per_core_critical: None,
             memory_growth_rate_warning: default_memory_growth_rate_warning(),
             memory_growth_rate_critical: default_memory_growth_rate_critical(),
             recovery_margin: default_recovery_margin(),
@@ -131,6 +805,13 @@ impl Default for DetectionConfig {
             process_memory_threshold_mb: default_process_memory_threshold_mb(),
             notification_cooldown_minutes: default_notification_cooldown_minutes(),
             persistent_breach_threshold: default_persistent_breach_threshold(),
+            restart_loop_count: default_restart_loop_count(),
+            restart_loop_window_minutes: default_restart_loop_window_minutes(),
+            must_run: Vec::new(),
+            required_processes: Vec::new(),
+            cpu_pinned_percent: default_cpu_pinned_percent(),
+            cpu_pinned_minutes: default_cpu_pinned_minutes(),
+            cpu_pinned_ignore: Vec::new(),
         }
     }
 }
@@ -142,6 +823,161 @@ impl Default for NotificationConfig {
             fallback_to_terminal_notifier: default_fallback_to_terminal_notifier(),
             warning_color: default_warning_color(),
             critical_color: default_critical_color(),
+            quiet_hours: Vec::new(),
+            digest_interval_minutes: 0,
+        }
+    }
+}
+
+impl Default for NarrationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_narration_enabled(),
+            socket_path: default_tts_socket_path(),
+            voice: default_tts_voice(),
+            sound_dir: default_sound_dir(),
+            warning_sound: default_warning_sound(),
+            critical_sound: default_critical_sound(),
+            connect_timeout_ms: default_tts_connect_timeout_ms(),
+            read_timeout_ms: default_tts_read_timeout_ms(),
+            fallback_command: default_fallback_tts_command(),
+            fallback_player: default_fallback_audio_player(),
+            merge_window_ms: default_narration_merge_window_ms(),
+            min_gap_seconds: default_narration_min_gap_seconds(),
+            max_per_hour: default_narration_max_per_hour(),
+        }
+    }
+}
+
+impl Default for PolicyConfig {
+    fn default() -> Self {
+        Self {
+            protected_processes: default_protected_processes(),
+        }
+    }
+}
+
+impl Default for ActionsConfig {
+    fn default() -> Self {
+        Self {
+            audit_log: default_audit_log(),
+            purge_command: default_purge_command(),
+            kill_grace_seconds: default_kill_grace_seconds(),
+            freeze_timeout_seconds: default_freeze_timeout_seconds(),
+        }
+    }
+}
+
+impl Default for KernelEventsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_kernel_events_enabled(),
+            log_file: None,
+        }
+    }
+}
+
+impl Default for ScriptingConfig {
+    fn default() -> Self {
+        Self {
+            scripts: Vec::new(),
+            max_operations: default_script_max_operations(),
+            timeout_ms: default_script_timeout_ms(),
+            history_len: default_script_history_len(),
+        }
+    }
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: default_history_max_entries(),
+            max_samples: default_history_max_samples(),
+        }
+    }
+}
+
+impl Default for ThermalConfig {
+    fn default() -> Self {
+        Self {
+            sysfs_root: default_sysfs_root(),
+            temperature_warning: default_temperature_warning(),
+            temperature_critical: default_temperature_critical(),
+            sustain_seconds: default_thermal_sustain_seconds(),
+            throttle_ratio: default_throttle_ratio(),
+            throttle_min_cpu_percent: default_throttle_min_cpu_percent(),
+            ignore: Vec::new(),
+        }
+    }
+}
+
+impl Default for DiskIoConfig {
+    fn default() -> Self {
+        Self {
+            utilization_percent: default_io_utilization_percent(),
+            await_warning_ms: default_io_await_warning_ms(),
+            await_critical_ms: default_io_await_critical_ms(),
+            sustain_seconds: default_io_sustain_seconds(),
+            ignore: Vec::new(),
+        }
+    }
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            per_process_sockets: false,
+            upload_spike_min_mb_per_sec: default_upload_spike_min_mb_per_sec(),
+            upload_spike_factor: default_upload_spike_factor(),
+            baseline_samples: default_upload_baseline_samples(),
+            close_wait_warning: default_close_wait_warning(),
+            process_sockets_warning: default_process_sockets_warning(),
+            ignore_interfaces: default_network_ignore_interfaces(),
+        }
+    }
+}
+
+impl Default for HandlesConfig {
+    fn default() -> Self {
+        Self {
+            fd_growth_per_hour: default_fd_growth_per_hour(),
+            fd_minimum: default_fd_minimum(),
+            thread_growth_per_hour: default_thread_growth_per_hour(),
+            thread_minimum: default_thread_minimum(),
+            file_table_warning_percent: default_file_table_warning_percent(),
+            file_table_critical_percent: default_file_table_critical_percent(),
+            ignore: Vec::new(),
+        }
+    }
+}
+
+impl Default for CgroupConfig {
+    fn default() -> Self {
+        Self {
+            root: default_cgroup_root(),
+            watch: Vec::new(),
+            hit_window_minutes: default_cgroup_hit_window_minutes(),
+        }
+    }
+}
+
+impl Default for AutonomousConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dry_run: default_autonomous_dry_run(),
+            max_actions_per_hour: default_autonomous_max_actions_per_hour(),
+            protected_processes: Vec::new(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl Default for EscalationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            steps: default_escalation_steps(),
         }
     }
 }
@@ -172,15 +1008,13 @@ impl Config {
             .join("config.toml")
     }
 }
+fn default_ipc_socket() -> String { "/tmp/system-sentinel.soc".to_string() }
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            general: GeneralConfig::default(),
-            thresholds: ThresholdConfig::default(),
-            detection: DetectionConfig::default(),
-            notification: NotificationConfig::default(),
-        }
+/// Expand a leading `~` to the user's home directory
+pub fn expand_tilde(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ if path == "~" => dirs::home_dir().unwrap_or_else(|| PathBuf::from(path)),
+        _ => PathBuf::from(path),
     }
 }
-fn default_ipc_socket() -> String { "/tmp/system-sentinel.soc".to_string() }
@@ -1,14 +1,26 @@
 //! Anomaly detection logic
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::time::Instant;
+use serde::{Deserialize, Serialize};
 use tracing::debug;
 
-use crate::config::Config;
-use crate::metrics::SystemMetrics;
+use crate::config::{Config, RequiredProcess, RuleCulprit, RuleDirection};
+use crate::diskio::DiskIo;
+use crate::inhibition::InhibitionGraph;
+use crate::kernel_events::{KernelEvent, KernelEventKind};
+use crate::metrics::{ProcessIdentity, ProcessInfo, SystemMetrics};
+use crate::pattern::glob_match;
+use crate::plugins::PluginState;
+use crate::rules::RuleEngine;
+use crate::scripting::{ScriptAlert, ScriptEngine, ScriptStatus};
+use crate::templates::{format_duration, render, MessageTemplates, TemplateVars};
+
+/// Upload samples needed before spikes are judged against the median
+const MIN_BASELINE_SAMPLES: usize = 5;
 
 /// Severity level of detected anomaly
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum AlertLevel {
     Warning,
     Critical,
@@ -24,13 +36,93 @@ impl std::fmt::Display for AlertLevel {
 }
 
 /// Type of anomaly (used for stable cooldown keys)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AnomalyType {
     Memory,
     Swap,
     Load,
     MemoryGrowthRate,
     ProcessWatchlist,
+    CgroupLimit,
+    OomKill,
+    Crash,
+    RestartLoop,
+    ProcessExited,
+    ProcessMissing,
+    ProbeFailed,
+    /// Result of an external check plugin; `Anomaly::custom_type` names the check
+    Plugin,
+    /// Raised by a detection script; `Anomaly::custom_type` carries its type name
+    Script,
+    /// A configured `[[rules]]` entry; `Anomaly::custom_type` is the rule name
+    Rule,
+    /// A process keeping a core busy for a long time
+    CpuPinned,
+    /// Sustained high temperature or CPU throttling
+    Thermal,
+    /// A block device busy and slow for a long time
+    IoSaturation,
+    /// An interface suddenly sending far more than usual
+    UploadSpike,
+    /// A process piling up TCP sockets, typically in CLOSE_WAIT
+    SocketLeak,
+    /// A process whose open file descriptors keep growing
+    FdLeak,
+    /// A process whose thread count keeps growing
+    ThreadLeak,
+    /// The kernel's file table (file-nr) nearing fs.file-max
+    FileTable,
+}
+
+impl AnomalyType {
+    /// Name in configuration (inhibitions) and for cooldown. Types produced
+    /// by built-in rules share the rule's name.
+    pub fn key(&self) -> &'static str {
+        match self {
+            AnomalyType::Memory => "memory",
+            AnomalyType::Swap => "swap",
+            AnomalyType::Load => "load",
+            AnomalyType::MemoryGrowthRate => "memory_growth",
+            AnomalyType::ProcessWatchlist => "process_watchlist",
+            AnomalyType::CgroupLimit => "cgroup_limit",
+            AnomalyType::OomKill => "oom_kill",
+            AnomalyType::Crash => "crash",
+            AnomalyType::RestartLoop => "restart_loop",
+            AnomalyType::ProcessExited => "process_exited",
+            AnomalyType::ProcessMissing => "process_missing",
+            AnomalyType::ProbeFailed => "probe_failed",
+            AnomalyType::Plugin => "plugin",
+            AnomalyType::Script => "script",
+            AnomalyType::Rule => "rule",
+            AnomalyType::CpuPinned => "cpu_pinned",
+            AnomalyType::Thermal => "thermal",
+            AnomalyType::IoSaturation => "io_saturation",
+            AnomalyType::UploadSpike => "upload_spike",
+            AnomalyType::SocketLeak => "socket_leak",
+            AnomalyType::FdLeak => "fd_leak",
+            AnomalyType::ThreadLeak => "thread_leak",
+            AnomalyType::FileTable => "file_table",
+        }
+    }
+
+    /// Unit suffix for the anomaly's `value`, used when summarising
+    pub fn unit(&self) -> &'static str {
+        match self {
+            AnomalyType::Memory | AnomalyType::Swap | AnomalyType::CpuPinned | AnomalyType::FileTable => "%",
+            AnomalyType::Load => "",
+            AnomalyType::MemoryGrowthRate => "GB/h",
+            AnomalyType::ProcessWatchlist | AnomalyType::CgroupLimit | AnomalyType::OomKill => "MB",
+            AnomalyType::Crash | AnomalyType::ProcessExited | AnomalyType::Plugin | AnomalyType::Script | AnomalyType::Rule => "",
+            AnomalyType::ProcessMissing => " missing",
+            AnomalyType::ProbeFailed | AnomalyType::IoSaturation => "ms",
+            AnomalyType::RestartLoop => " restarts",
+            AnomalyType::Thermal => "°C",
+            AnomalyType::UploadSpike => "MB/s",
+            AnomalyType::SocketLeak => " sockets",
+            AnomalyType::FdLeak => " descriptors/h",
+            AnomalyType::ThreadLeak => " threads/h",
+        }
+    }
 }
 
 /// Result of anomaly detection
@@ -41,35 +133,95 @@ pub struct Anomaly {
     pub message: String,
     pub details: Vec<String>,
     pub narration_message: String,
-    pub sound_hint: Option<String>,
+    /// The measured value that triggered the anomaly (unit depends on type)
+    pub value: f64,
+    /// When the underlying condition was first seen (start of the breach streak)
+    pub started_at: chrono::DateTime<chrono::Local>,
+    /// Type name of a configured check (plugins), e.g. "backup_freshness"
+    pub custom_type: Option<String>,
+    /// The value is worse the lower it gets (rules with direction "below")
+    pub lower_is_worse: bool,
+}
+
+impl Anomaly {
+    /// The configured check or rule name if any, else the type's key
+    pub fn name(&self) -> String {
+        self.custom_type.clone().unwrap_or_else(|| self.anomaly_type.key().to_string())
+    }
+}
+
+/// A detected condition that was held back, and why
+#[derive(Debug, Clone, Serialize)]
+pub struct Suppression {
+    /// `Anomaly::name` or rule name
+    pub alert: String,
+    pub level: AlertLevel,
+    pub reason: String,
 }
 
 /// Anomaly detection with cooldown tracking
 pub struct AnomalyDetector {
     config: Config,
+    templates: MessageTemplates,
     /// Last notification time and level for each anomaly type (for cooldown)
     last_notification: HashMap<String, (Instant, AlertLevel)>,
-    /// How many consecutive times an anomaly has been detected
-    breach_counters: HashMap<AnomalyType, u32>,
-    /// Currently active alerts (for hysteresis)
-    active_alerts: HashMap<AnomalyType, AlertLevel>,
-    /// When the current high load started (None if load is normal)
-    load_start_time: Option<Instant>,
+    /// How many consecutive times an anomaly has been detected, by `Anomaly::name`
+    breach_counters: HashMap<String, u32>,
+    /// When the current breach streak of each anomaly began
+    breach_started: HashMap<String, chrono::DateTime<chrono::Local>>,
+    /// Currently active alerts by name (for hysteresis)
+    active_alerts: HashMap<String, AlertLevel>,
+    /// Threshold rules, built-in and configured
+    rules: RuleEngine,
+    /// Which alerts suppress which
+    inhibitions: InhibitionGraph,
+    /// Everything held back by the last check
+    suppressed: Vec<Suppression>,
+    /// Anomalies past damping in the last check, notified or not
+    breaching: Vec<Anomaly>,
+    /// must_run pattern -> the matching process that exited and has not come back
+    vanished: HashMap<String, (ProcessIdentity, chrono::DateTime<chrono::Local>)>,
+    /// Index into required_processes -> when it was first seen absent
+    missing_since: HashMap<usize, chrono::DateTime<chrono::Local>>,
+    /// (PID, name) of processes above cpu_pinned_percent -> since when
+    pinned_since: HashMap<(u32, String), chrono::DateTime<chrono::Local>>,
+    /// Start of the current overheating and throttling streaks
+    hot_since: Option<chrono::DateTime<chrono::Local>>,
+    throttled_since: Option<chrono::DateTime<chrono::Local>>,
+    /// Device -> start of its current I/O saturation
+    io_saturated_since: HashMap<String, chrono::DateTime<chrono::Local>>,
+    /// Recent upload rates (bytes/s) per interface, oldest first
+    upload_history: HashMap<String, VecDeque<f64>>,
+    /// Probes counted as failing until they succeed `recover_after` times in a row
+    failing_probes: HashSet<String>,
+    /// User detection scripts
+    scripts: ScriptEngine,
 }
 
 impl AnomalyDetector {
     pub fn new(config: &Config) -> Self {
+        let rules = RuleEngine::new(config);
+        let inhibitions = InhibitionGraph::new(&config.inhibitions, rules.inhibitions());
         Self {
-            config: Config {
-                general: config.general.clone(),
-                thresholds: config.thresholds.clone(),
-                detection: config.detection.clone(),
-                notification: config.notification.clone(),
-            },
+            config: config.clone(),
+            templates: MessageTemplates::new(&config.templates),
             last_notification: HashMap::new(),
             breach_counters: HashMap::new(),
+            breach_started: HashMap::new(),
             active_alerts: HashMap::new(),
-            load_start_time: None,
+            rules,
+            inhibitions,
+            suppressed: Vec::new(),
+            breaching: Vec::new(),
+            vanished: HashMap::new(),
+            missing_since: HashMap::new(),
+            pinned_since: HashMap::new(),
+            hot_since: None,
+            throttled_since: None,
+            io_saturated_since: HashMap::new(),
+            upload_history: HashMap::new(),
+            failing_probes: HashSet::new(),
+            scripts: ScriptEngine::new(&config.scripting),
         }
     }
 
@@ -77,31 +229,79 @@ impl AnomalyDetector {
     pub fn check(&mut self, metrics: &SystemMetrics) -> Option<Anomaly> {
         let mut anomalies_raw: Vec<Anomaly> = Vec::new();
 
-        // Check memory percentage
-        if let Some(a) = self.check_memory_percent(metrics) {
+        // Threshold rules: memory, swap, load, growth and [[rules]]
+        anomalies_raw.extend(self.check_rules(metrics));
+        self.breaching.clear();
+
+        // Check watchlist processes
+        if let Some(a) = self.check_process_watchlist(metrics) {
             anomalies_raw.push(a);
         }
 
-        // Check swap percentage
-        if let Some(a) = self.check_swap_percent(metrics) {
+        // Check cgroups that recently hit their memory limits
+        if let Some(a) = self.check_cgroup_limits(metrics) {
             anomalies_raw.push(a);
         }
 
-        // Check load average
-        if let Some(a) = self.check_load_average(metrics) {
+        // Check for processes keeping a core busy
+        if let Some(a) = self.check_cpu_pinned(metrics) {
             anomalies_raw.push(a);
         }
 
-        // Check memory growth rate
-        if let Some(a) = self.check_memory_growth_rate(metrics) {
+        // Check temperatures and CPU throttling
+        if let Some(a) = self.check_thermal(metrics) {
             anomalies_raw.push(a);
         }
 
-        // Check watchlist processes
-        if let Some(a) = self.check_process_watchlist(metrics) {
+        // Check for saturated disks
+        if let Some(a) = self.check_io_saturation(metrics) {
+            anomalies_raw.push(a);
+        }
+
+        // Check network uploads and sockets
+        if let Some(a) = self.check_upload_spike(metrics) {
+            anomalies_raw.push(a);
+        }
+        if let Some(a) = self.check_socket_leaks(metrics) {
+            anomalies_raw.push(a);
+        }
+
+        // Check for descriptor and thread leaks, and the system file table
+        let handles = &self.config.handles;
+        let fd_leak = self.check_handle_leak(AnomalyType::FdLeak, &metrics.top_fd_growers, |p| (p.open_fds, p.fd_growth_rate), handles.fd_minimum, handles.fd_growth_per_hour, metrics);
+        let thread_leak = self.check_handle_leak(AnomalyType::ThreadLeak, &metrics.top_thread_growers, |p| (p.threads, p.thread_growth_rate), handles.thread_minimum, handles.thread_growth_per_hour, metrics);
+        anomalies_raw.extend(fd_leak);
+        anomalies_raw.extend(thread_leak);
+        if let Some(a) = self.check_file_table(metrics) {
+            anomalies_raw.push(a);
+        }
+
+        // Check for programs being restarted over and over
+        if let Some(a) = self.check_restart_loops(metrics) {
+            anomalies_raw.push(a);
+        }
+
+        // Check for must-run processes that exited
+        if let Some(a) = self.check_must_run(metrics) {
+            anomalies_raw.push(a);
+        }
+
+        // Check for required processes that are not running
+        if let Some(a) = self.check_required_processes(metrics) {
             anomalies_raw.push(a);
         }
 
+        // Check local services that stopped answering
+        if let Some(a) = self.check_probes(metrics) {
+            anomalies_raw.push(a);
+        }
+
+        // Check external plugin results
+        anomalies_raw.extend(self.check_plugins(metrics));
+
+        // Run user detection scripts
+        anomalies_raw.extend(self.check_scripts(metrics));
+
         // Return the most severe anomaly that passes cooldown
         let mut anomalies = anomalies_raw.clone();
         anomalies.sort_by_key(|a| match a.level {
@@ -109,283 +309,702 @@ impl AnomalyDetector {
             AlertLevel::Warning => 1,
         });
 
-        for anomaly in anomalies {
-            // Phase 4: Alert Inhibition & Hierarchy
-            // If we have a Memory alert, suppress Growth alerts (they are redundant/noisy)
-            if anomaly.anomaly_type == AnomalyType::MemoryGrowthRate {
-                if anomalies_raw.iter().any(|a| a.anomaly_type == AnomalyType::Memory) {
-                    debug!("Inhibiting Growth alert because Memory alert is present");
-                    continue;
-                }
+        // Every anomaly goes through the whole loop so that all streaks and
+        // suppressions are kept up to date, even once one has been picked
+        let raised: HashSet<String> = anomalies_raw.iter().map(Anomaly::name).collect();
+        let mut notify: Option<Anomaly> = None;
+        for mut anomaly in anomalies {
+            // Alert inhibition: skip alerts made redundant by another raised one
+            if let Some(inhibitor) = self.inhibitions.inhibitor(&anomaly.name(), &raised) {
+                let reason = format!("inhibited by {}", inhibitor);
+                self.suppress(&anomaly, reason);
+                continue;
             }
 
-            // Apply Damping: Increment counter for this anomaly type
-            let counter = self.breach_counters.entry(anomaly.anomaly_type).or_insert(0);
+            // Apply Damping: Increment counter for this anomaly; rules, plugins
+            // and scripts each keep their own streak
+            let name = anomaly.name();
+            let counter = self.breach_counters.entry(name.clone()).or_insert(0);
             *counter += 1;
+            let started_at = *self.breach_started.entry(name).or_insert(metrics.timestamp);
+            anomaly.started_at = started_at;
 
-            if *counter < self.config.detection.persistent_breach_threshold {
-                debug!("Damping {:?}: breach_count={} < threshold={}", 
-                    anomaly.anomaly_type, *counter, self.config.detection.persistent_breach_threshold);
+            let threshold = self.config.detection.persistent_breach_threshold;
+            if *counter < threshold {
+                let reason = format!("damping: seen {} of {} times in a row", *counter, threshold);
+                self.suppress(&anomaly, reason);
                 continue;
             }
 
-            // Use stable key based on anomaly type only (ignore level for key)
-            let key = format!("{:?}", anomaly.anomaly_type);
+            self.breaching.push(anomaly.clone());
+
+            // An active alert follows its current level even while it cools
+            // down, so escalation stops when a Critical drops to Warning
+            if let Some(level) = self.active_alerts.get_mut(&anomaly.name()) {
+                *level = anomaly.level;
+            }
+
+            if let Some(sent) = &notify {
+                let reason = format!("waiting: {} is reported this tick", sent.name());
+                self.suppress(&anomaly, reason);
+                continue;
+            }
+
+            // Use stable key based on the anomaly name only (ignore level for
+            // key); rules, plugins and scripts each cool down on their own
+            let key = anomaly.name();
             
             if self.check_cooldown(&key, anomaly.level) {
                 self.last_notification.insert(key, (Instant::now(), anomaly.level));
-                self.active_alerts.insert(anomaly.anomaly_type, anomaly.level);
+                self.active_alerts.insert(anomaly.name(), anomaly.level);
                 debug!("Anomaly detected: {:?}. Details: {:?}", anomaly.message, anomaly.details);
-                return Some(anomaly);
+                notify = Some(anomaly);
+                continue;
             }
+            let reason = format!("cooldown: notified within the last {} min", self.config.detection.notification_cooldown_minutes);
+            self.suppress(&anomaly, reason);
         }
 
-        // Reset counters for anomaly types that didn't breach this time
-        // Actually, we should only reset if no severity of that type was found
-        // This is handled by a separate pass or by cleaning up after the loop
-        let detected_types: std::collections::HashSet<_> = anomalies_raw.iter().map(|a| a.anomaly_type).collect();
-        self.breach_counters.retain(|t, _| detected_types.contains(t));
-        self.active_alerts.retain(|t, _| detected_types.contains(t));
+        // Reset counters for anomalies that didn't breach this time
+        self.breach_counters.retain(|name, _| raised.contains(name));
+        self.breach_started.retain(|name, _| raised.contains(name));
+        self.active_alerts.retain(|name, _| raised.contains(name));
 
-        None
+        notify
     }
 
-    fn check_memory_percent(&self, metrics: &SystemMetrics) -> Option<Anomaly> {
-        let percent = metrics.memory_percent;
-        let thresholds = &self.config.thresholds;
-        let recovery = thresholds.recovery_margin;
+    /// Anomalies that passed damping in the last check, including those held
+    /// back by cooldown; every tick's sample, for the digest
+    pub fn breaching(&self) -> &[Anomaly] {
+        &self.breaching
+    }
 
-        // Apply Hysteresis: Use lower threshold if already in alert state
-        let active_level = self.active_alerts.get(&AnomalyType::Memory);
-        
-        let (warn_thresh, crit_thresh) = match active_level {
-            Some(AlertLevel::Critical) => (thresholds.memory_warning - recovery, thresholds.memory_critical - recovery),
-            Some(AlertLevel::Warning) => (thresholds.memory_warning - recovery, thresholds.memory_critical),
-            None => (thresholds.memory_warning, thresholds.memory_critical),
-        };
+    /// Conditions held back by the last check (gated, inhibited, damped or
+    /// cooling down)
+    pub fn suppressed(&self) -> &[Suppression] {
+        &self.suppressed
+    }
 
-        if percent >= crit_thresh {
-            let culprit = self.get_memory_culprit(metrics);
-            let msg = format!("Mem {:.0}%: {}", percent, culprit);
-            
-            Some(Anomaly {
-                anomaly_type: AnomalyType::Memory,
-                level: AlertLevel::Critical,
-                message: msg.clone(),
-                details: vec![],
-                narration_message: format!("Memory critical. {:.0} percent. {}", percent, culprit),
-                sound_hint: Some("sounds/unused/Futuristic Hum 2133.wav".to_string()),
-            })
-        } else if percent >= warn_thresh {
-            let culprit = self.get_memory_culprit(metrics);
-            let msg = format!("Mem {:.0}%: {}", percent, culprit);
-            
-            Some(Anomaly {
-                anomaly_type: AnomalyType::Memory,
-                level: AlertLevel::Warning,
-                message: msg.clone(),
-                details: vec![],
-                narration_message: format!("Memory high. {:.0} percent. {}", percent, culprit),
-                sound_hint: Some("sounds/subtle/alien_button.wav".to_string()),
-            })
-        } else {
-            None
+    fn suppress(&mut self, anomaly: &Anomaly, reason: String) {
+        debug!("Suppressing {} ({:?}): {}", anomaly.name(), anomaly.level, reason);
+        self.suppressed.push(Suppression { alert: anomaly.name(), level: anomaly.level, reason });
+    }
+
+    /// Anomaly for a one-off kernel event. Events are facts rather than
+    /// conditions, so damping and hysteresis don't apply; see `event_due`.
+    pub fn event_anomaly(&self, event: &KernelEvent) -> Anomaly {
+        let (anomaly_type, level) = event_type(event);
+        let rss_mb = event.anon_rss_kb.map_or(0.0, |kb| kb as f64 / 1024.0);
+
+        let vars = TemplateVars::new()
+            .with("value", rss_mb)
+            .with("level", level.to_string())
+            .with("type", format!("{:?}", anomaly_type))
+            .with("culprit", event.victim())
+            .with("pid", event.pid.map_or(0.0, f64::from))
+            .with("group", event.cgroup.clone().unwrap_or_default())
+            .with("detail", event.detail.clone());
+
+        let text = self.templates.render(anomaly_type, level, &vars);
+        let details = if text.details.is_empty() { vec![event.detail.clone()] } else { text.details };
+
+        Anomaly {
+            anomaly_type,
+            level,
+            message: text.message,
+            details,
+            narration_message: text.narration,
+            value: rss_mb,
+            started_at: event.timestamp,
+            custom_type: None,
+            lower_is_worse: false,
         }
     }
 
-    fn check_swap_percent(&self, metrics: &SystemMetrics) -> Option<Anomaly> {
-        if metrics.swap_total == 0 {
-            return None;
+    /// Whether a kernel event should be notified. The same kind of event for
+    /// the same victim (process name, or cgroup) notifies once per cooldown,
+    /// so a crash-looping service doesn't raise an alert per crash.
+    pub fn event_due(&mut self, event: &KernelEvent) -> bool {
+        let (anomaly_type, level) = event_type(event);
+        let victim = event.process.as_deref().or(event.cgroup.as_deref()).unwrap_or("unknown");
+        let key = format!("{}:{}", anomaly_type.key(), victim);
+        if !self.check_cooldown(&key, level) {
+            return false;
         }
+        self.last_notification.insert(key, (Instant::now(), level));
+        true
+    }
 
-        let percent = metrics.swap_percent;
-        let thresholds = &self.config.thresholds;
-        let recovery = thresholds.recovery_margin;
+    /// Run counts and errors of the detection scripts
+    pub fn script_status(&self) -> Vec<ScriptStatus> {
+        self.scripts.status()
+    }
 
-        // Apply Correlation: Suppress swap alerts if memory pressure is low (macOS opportunistic swapping)
-        if metrics.memory_percent <= 80.0 {
-            return None;
+    /// Level of the currently active alert with this `Anomaly::name`, if any (hysteresis state)
+    pub fn active_level(&self, name: &str) -> Option<AlertLevel> {
+        self.active_alerts.get(name).copied()
+    }
+
+    /// Anomalies for the threshold rules that fire. Configured rules get the
+    /// rule name as custom type and may bring their own text. A load Warning
+    /// is held back while the load trend is falling.
+    fn check_rules(&mut self, metrics: &SystemMetrics) -> Vec<Anomaly> {
+        let hits = self.rules.evaluate(metrics);
+        self.suppressed = self.rules.suppressed().to_vec();
+
+        let trend = load_trend(metrics);
+        let suppressed = &mut self.suppressed;
+        let hits: Vec<_> = hits
+            .into_iter()
+            .filter(|hit| {
+                if hit.anomaly_type != AnomalyType::Load || hit.level != AlertLevel::Warning || trend != "falling" {
+                    return true;
+                }
+                let reason = format!("load falling: {:.1} / {:.1} / {:.1} (1/5/15 min)", metrics.load_1m, metrics.load_5m, metrics.load_15m);
+                debug!("Suppressing {} ({:?}): {}", hit.rule, hit.level, reason);
+                suppressed.push(Suppression { alert: hit.rule.clone(), level: hit.level, reason });
+                false
+            })
+            .collect();
+
+        hits.into_iter()
+            .map(|hit| {
+                let culprit = match hit.culprit {
+                    RuleCulprit::Memory => self.get_memory_culprit(metrics),
+                    RuleCulprit::Cpu => self.get_cpu_culprit(metrics),
+                    RuleCulprit::None => String::new(),
+                };
+                let mut vars = self.base_vars(hit.anomaly_type, hit.level, hit.value, hit.threshold, metrics)
+                    .with("duration", format_duration(self.breach_duration(&hit.rule, metrics)))
+                    .with("culprit", culprit)
+                    .with("rule", hit.rule.clone())
+                    .with("metric", hit.metric.clone());
+                // System memory growth (GB/h) is context for the memory rules only
+                if matches!(hit.anomaly_type, AnomalyType::Memory | AnomalyType::Swap | AnomalyType::MemoryGrowthRate) {
+                    vars = vars.with_opt("rate", metrics.memory_growth_rate);
+                }
+                if hit.anomaly_type == AnomalyType::Load {
+                    vars = vars
+                        .with("load", metrics.load_1m)
+                        .with("load_5m", metrics.load_5m)
+                        .with("load_15m", metrics.load_15m)
+                        .with("cores", metrics.logical_cores as f64)
+                        .with("trend", load_trend(metrics));
+                }
+
+                let mut anomaly = self.build_anomaly(hit.anomaly_type, hit.level, hit.value, &vars, metrics);
+                if let Some(message) = &hit.message {
+                    anomaly.message = render(message, &vars);
+                }
+                if let Some(narration) = &hit.narration {
+                    anomaly.narration_message = render(narration, &vars);
+                }
+                anomaly.lower_is_worse = hit.direction == RuleDirection::Below;
+                if hit.anomaly_type == AnomalyType::Rule {
+                    anomaly.custom_type = Some(hit.rule);
+                }
+                anomaly
+            })
+            .collect()
+    }
+
+    fn check_process_watchlist(&self, metrics: &SystemMetrics) -> Option<Anomaly> {
+        let watchlist = &self.config.detection.process_watchlist;
+        let threshold_mb = self.config.detection.process_memory_threshold_mb;
+
+        // Check both raw top processes and aggregated processes
+        let mut all_to_check = metrics.top_processes.clone();
+        all_to_check.extend(metrics.aggregated_processes.clone());
+
+        for proc in all_to_check {
+            let proc_name_lower = proc.name.to_lowercase();
+
+            for watched in watchlist {
+                if proc_name_lower.contains(&watched.to_lowercase()) && proc.memory_mb >= threshold_mb as f64 {
+                    let level = AlertLevel::Warning;
+                    let vars = self.base_vars(AnomalyType::ProcessWatchlist, level, proc.memory_mb, threshold_mb as f64, metrics)
+                        .with("culprit", proc.human_name())
+                        .with("memory_gb", proc.memory_mb / 1024.0)
+                        .with_opt("rate", proc.memory_growth_rate);
+                    return Some(self.build_anomaly(AnomalyType::ProcessWatchlist, level, proc.memory_mb, &vars, metrics));
+                }
+            }
         }
 
-        // Apply Hysteresis
-        let active_level = self.active_alerts.get(&AnomalyType::Swap);
-        let (warn_thresh, crit_thresh) = match active_level {
-            Some(AlertLevel::Critical) => (thresholds.swap_warning - recovery, thresholds.swap_critical - recovery),
-            Some(AlertLevel::Warning) => (thresholds.swap_warning - recovery, thresholds.swap_critical),
-            None => (thresholds.swap_warning, thresholds.swap_critical),
-        };
+        None
+    }
 
-        let level = if percent >= crit_thresh {
-            Some(AlertLevel::Critical)
-        } else if percent >= warn_thresh {
-            Some(AlertLevel::Warning)
-        } else {
-            None
+    /// A cgroup throttled at memory.high is a Warning; hitting memory.max, failing
+    /// an allocation or an OOM kill is Critical. Both stay active for the hit window.
+    fn check_cgroup_limits(&self, metrics: &SystemMetrics) -> Option<Anomaly> {
+        let window = chrono::Duration::minutes(self.config.cgroups.hit_window_minutes as i64);
+        let recent = |t: Option<chrono::DateTime<chrono::Local>>| t.is_some_and(|t| metrics.timestamp - t <= window);
+
+        let (level, group) = metrics
+            .cgroups
+            .iter()
+            .filter_map(|g| {
+                if recent(g.last_oom) {
+                    Some((AlertLevel::Critical, g))
+                } else if recent(g.last_limit_hit) {
+                    Some((AlertLevel::Warning, g))
+                } else {
+                    None
+                }
+            })
+            .max_by_key(|(level, g)| (*level, g.current_bytes))?;
+
+        let limit = match level {
+            AlertLevel::Critical => group.max_bytes.or(group.high_bytes),
+            AlertLevel::Warning => group.high_bytes,
         };
+        let mb = |bytes: u64| bytes as f64 / 1024.0 / 1024.0;
+        let current_mb = mb(group.current_bytes);
+
+        let vars = self.base_vars(AnomalyType::CgroupLimit, level, current_mb, limit.map_or(0.0, mb), metrics)
+            .with("culprit", group.name().to_string())
+            .with("group", group.path.clone())
+            .with("oom_kills", group.events.oom_kill as f64);
+        Some(self.build_anomaly(AnomalyType::CgroupLimit, level, current_mb, &vars, metrics))
+    }
 
-        if let Some(level) = level {
-            let culprit = self.get_memory_culprit(metrics);
-            let msg = format!("Swap {:.0}%: {}", percent, culprit);
-            
-            Some(Anomaly {
-                anomaly_type: AnomalyType::Swap,
-                level,
-                message: msg.clone(),
-                details: vec![],
-                narration_message: format!("Swap high. {:.0} percent. {}", percent, culprit),
-                sound_hint: match level {
-                    AlertLevel::Critical => Some("sounds/unused/Futuristic Hum 2133.wav".to_string()),
-                    AlertLevel::Warning => Some("sounds/subtle/alien_button.wav".to_string()),
-                },
-            })
-        } else {
-            None
+    /// A process near 100% of a core for `cpu_pinned_minutes`. On a many-core
+    /// machine a runaway process barely moves the load average, but it still
+    /// drains the battery and heats the machine.
+    fn check_cpu_pinned(&mut self, metrics: &SystemMetrics) -> Option<Anomaly> {
+        let detection = &self.config.detection;
+        let busy: Vec<&ProcessInfo> = metrics
+            .top_cpu_processes
+            .iter()
+            .filter(|p| f64::from(p.cpu_usage) >= detection.cpu_pinned_percent)
+            .filter(|p| !detection.cpu_pinned_ignore.iter().any(|pattern| name_or_exe_matches(pattern, &p.name, p.exe.as_deref())))
+            .collect();
+        self.pinned_since.retain(|(pid, name), _| busy.iter().any(|p| p.pid == *pid && p.name == *name));
+        for p in &busy {
+            self.pinned_since.entry((p.pid, p.name.clone())).or_insert(metrics.timestamp);
         }
-    }
 
-    fn check_load_average(&mut self, metrics: &SystemMetrics) -> Option<Anomaly> {
-        let load = metrics.load_1m;
-        let thresholds = &self.config.thresholds;
-        let recovery = 1.0; // Fixed absolute recovery for load
+        let min_pinned = chrono::Duration::minutes(detection.cpu_pinned_minutes as i64);
+        let mut pinned: Vec<(&ProcessInfo, chrono::Duration)> = busy
+            .into_iter()
+            .filter_map(|p| self.pinned_since.get(&(p.pid, p.name.clone())).map(|since| (p, metrics.timestamp - *since)))
+            .filter(|(_, pinned_for)| *pinned_for >= min_pinned)
+            .collect();
+        pinned.sort_by_key(|(_, pinned_for)| std::cmp::Reverse(*pinned_for));
+        let (top, pinned_for) = *pinned.first()?;
+
+        let level = AlertLevel::Warning;
+        let value = f64::from(top.cpu_usage);
+        let vars = self.base_vars(AnomalyType::CpuPinned, level, value, detection.cpu_pinned_percent, metrics)
+            .with("culprit", top.human_name())
+            .with("pid", top.pid as f64)
+            .with("pinned_for", format_duration(pinned_for));
+        let mut anomaly = self.build_anomaly(AnomalyType::CpuPinned, level, value, &vars, metrics);
+        if anomaly.details.is_empty() {
+            anomaly.details = pinned
+                .iter()
+                .map(|(p, d)| format!("{} (PID {}): {:.0}% for {}", p.human_name(), p.pid, p.cpu_usage, format_duration(*d)))
+                .collect();
+        }
+        Some(anomaly)
+    }
 
-        if load >= thresholds.load_warning {
-            // High load detected, check if it's sustained
-            if self.load_start_time.is_none() {
-                self.load_start_time = Some(Instant::now());
-                debug!("High load detected ({:.1}), starting timer", load);
+    /// The hottest sensor above temperature_warning, or the CPU running well
+    /// below its maximum frequency while busy, for sustain_seconds
+    fn check_thermal(&mut self, metrics: &SystemMetrics) -> Option<Anomaly> {
+        let t = &self.config.thermal;
+        let hottest = metrics
+            .thermal
+            .temperatures
+            .iter()
+            .filter(|s| !t.ignore.iter().any(|pattern| glob_match(pattern, &s.name())))
+            .max_by(|a, b| a.celsius.total_cmp(&b.celsius));
+        let ratio = metrics.thermal.frequency_ratio();
+        let hot = hottest.is_some_and(|s| s.celsius >= t.temperature_warning);
+        let throttled = ratio.is_some_and(|r| r < t.throttle_ratio) && f64::from(metrics.cpu_usage) >= t.throttle_min_cpu_percent;
+
+        let sustain = chrono::Duration::seconds(t.sustain_seconds as i64);
+        let sustained = |condition: bool, since: &mut Option<chrono::DateTime<chrono::Local>>| {
+            if !condition {
+                *since = None;
+                return false;
             }
+            metrics.timestamp - *since.get_or_insert(metrics.timestamp) >= sustain
+        };
+        let hot = sustained(hot, &mut self.hot_since);
+        let throttled = sustained(throttled, &mut self.throttled_since);
+        if !hot && !throttled {
+            return None;
+        }
 
-            let sustained_secs = self.load_start_time.unwrap().elapsed().as_secs();
-            let alert_threshold_secs = 120; // 2 minutes
+        let celsius = hottest.map_or(0.0, |s| s.celsius);
+        let level = if hot && celsius >= t.temperature_critical { AlertLevel::Critical } else { AlertLevel::Warning };
+        let cause = match (hot, throttled) {
+            (true, true) => "Hot and throttled",
+            (true, false) => "Hot",
+            _ => "Throttled",
+        };
+        let vars = self.base_vars(AnomalyType::Thermal, level, celsius, t.temperature_warning, metrics)
+            .with("cause", cause)
+            .with("sensor", hottest.map_or_else(|| "no sensor".to_string(), |s| s.name()))
+            .with("speed", ratio.map_or_else(|| "speed unknown".to_string(), |r| format!("at {:.0}% speed", r * 100.0)));
+        Some(self.build_anomaly(AnomalyType::Thermal, level, celsius, &vars, metrics))
+    }
 
-            if sustained_secs >= alert_threshold_secs {
-                // Apply Hysteresis
-                let active_level = self.active_alerts.get(&AnomalyType::Load);
-                let (warn_thresh, crit_thresh) = match active_level {
-                    Some(AlertLevel::Critical) => (thresholds.load_warning - recovery, thresholds.load_critical - recovery),
-                    Some(AlertLevel::Warning) => (thresholds.load_warning - recovery, thresholds.load_critical),
-                    None => (thresholds.load_warning, thresholds.load_critical),
-                };
+    /// The slowest block device that has been busy and slow for sustain_seconds.
+    /// Swap storms and image pulls stall everything while memory and load
+    /// look fine.
+    fn check_io_saturation(&mut self, metrics: &SystemMetrics) -> Option<Anomaly> {
+        let c = &self.config.disk_io;
+        let saturated: Vec<&DiskIo> = metrics
+            .disk_io
+            .iter()
+            .filter(|d| d.utilization_percent >= c.utilization_percent && d.await_ms >= c.await_warning_ms)
+            .filter(|d| !c.ignore.iter().any(|pattern| glob_match(pattern, &d.device)))
+            .collect();
+        self.io_saturated_since.retain(|device, _| saturated.iter().any(|d| d.device == *device));
+        for d in &saturated {
+            self.io_saturated_since.entry(d.device.clone()).or_insert(metrics.timestamp);
+        }
 
-                let level = if load >= crit_thresh {
-                    AlertLevel::Critical
-                } else if load >= warn_thresh {
-                    AlertLevel::Warning
-                } else {
-                    return None;
-                };
+        let sustain = chrono::Duration::seconds(c.sustain_seconds as i64);
+        let disk = saturated
+            .into_iter()
+            .filter(|d| self.io_saturated_since.get(&d.device).is_some_and(|since| metrics.timestamp - *since >= sustain))
+            .max_by(|a, b| a.await_ms.total_cmp(&b.await_ms))?;
+
+        let level = if disk.await_ms >= c.await_critical_ms { AlertLevel::Critical } else { AlertLevel::Warning };
+        let culprit = metrics.top_io_processes.first().map_or_else(
+            || "Unknown".to_string(),
+            |p| format!("{} ({:.0}MB/s)", p.name, p.total_bytes_per_sec() / 1024.0 / 1024.0),
+        );
+        let vars = self.base_vars(AnomalyType::IoSaturation, level, disk.await_ms, c.await_warning_ms, metrics)
+            .with("culprit", culprit)
+            .with("device", disk.device.clone())
+            .with("utilization", disk.utilization_percent);
+        Some(self.build_anomaly(AnomalyType::IoSaturation, level, disk.await_ms, &vars, metrics))
+    }
 
-                let culprit = self.get_cpu_culprit(metrics);
-                let msg = format!("Load {:.1}: {}", load, culprit);
-
-                return Some(Anomaly {
-                    anomaly_type: AnomalyType::Load,
-                    level,
-                    message: msg.clone(),
-                    details: vec![],
-                    narration_message: format!("Load high. {:.1}. {}", load, culprit),
-                    sound_hint: match level {
-                        AlertLevel::Critical => Some("sounds/unused/Futuristic Hum 2133.wav".to_string()),
-                        AlertLevel::Warning => Some("sounds/subtle/alien_button.wav".to_string()),
-                    },
-                });
+    /// An interface sending far more than its recent median: something is
+    /// exfiltrating or a sync client went wild. A sustained upload becomes
+    /// the new normal once it fills half the baseline window.
+    fn check_upload_spike(&mut self, metrics: &SystemMetrics) -> Option<Anomaly> {
+        let n = &self.config.network;
+        let interfaces: Vec<_> = metrics
+            .network
+            .interfaces
+            .iter()
+            .filter(|i| !n.ignore_interfaces.iter().any(|pattern| glob_match(pattern, &i.name)))
+            .collect();
+        self.upload_history.retain(|name, _| interfaces.iter().any(|i| i.name == *name));
+
+        let min_bytes = n.upload_spike_min_mb_per_sec * 1024.0 * 1024.0;
+        let mut spike: Option<(&str, f64, f64)> = None;
+        for interface in interfaces {
+            let history = self.upload_history.entry(interface.name.clone()).or_default();
+            let tx = interface.tx_bytes_per_sec;
+            if history.len() >= MIN_BASELINE_SAMPLES {
+                let usual = median(history.iter().copied());
+                if tx >= min_bytes && tx >= usual * n.upload_spike_factor && spike.is_none_or(|(_, top, _)| tx > top) {
+                    spike = Some((&interface.name, tx, usual));
+                }
             }
-        } else {
-            // Load is normal, reset timer
-            if self.load_start_time.is_some() {
-                debug!("Load returned to normal, resetting timer");
-                self.load_start_time = None;
+            history.push_back(tx);
+            while history.len() > n.baseline_samples.max(MIN_BASELINE_SAMPLES) {
+                history.pop_front();
             }
         }
 
-        None
+        let (interface, tx, usual) = spike?;
+        let level = AlertLevel::Warning;
+        let mb_per_sec = tx / 1024.0 / 1024.0;
+        let vars = self.base_vars(AnomalyType::UploadSpike, level, mb_per_sec, n.upload_spike_min_mb_per_sec, metrics)
+            .with("interface", interface.to_string())
+            .with("usual", usual / 1024.0 / 1024.0);
+        Some(self.build_anomaly(AnomalyType::UploadSpike, level, mb_per_sec, &vars, metrics))
+    }
+
+    /// A process holding close_wait_warning sockets its peer already closed,
+    /// or process_sockets_warning sockets in all. Without per-process counts
+    /// only the system-wide CLOSE_WAIT total is checked.
+    fn check_socket_leaks(&self, metrics: &SystemMetrics) -> Option<Anomaly> {
+        let n = &self.config.network;
+        let leaking = metrics
+            .network
+            .process_sockets
+            .iter()
+            .filter(|p| p.close_wait >= n.close_wait_warning || p.tcp >= n.process_sockets_warning)
+            .max_by_key(|p| (p.close_wait, p.tcp));
+
+        let total_close_wait = metrics.network.tcp_state("CLOSE_WAIT");
+        let (culprit, close_wait, sockets) = match leaking {
+            Some(p) => (format!("{} (PID {})", p.name, p.pid), p.close_wait, p.tcp),
+            None if metrics.network.process_sockets.is_empty() && total_close_wait >= n.close_wait_warning => {
+                let sockets = metrics.network.tcp_states.values().sum();
+                ("Unknown".to_string(), total_close_wait, sockets)
+            }
+            None => return None,
+        };
+
+        let level = AlertLevel::Warning;
+        let vars = self.base_vars(AnomalyType::SocketLeak, level, f64::from(sockets), f64::from(n.close_wait_warning), metrics)
+            .with("culprit", culprit)
+            .with("close_wait", f64::from(close_wait))
+            .with("sockets", f64::from(sockets));
+        Some(self.build_anomaly(AnomalyType::SocketLeak, level, f64::from(sockets), &vars, metrics))
     }
 
-    fn check_memory_growth_rate(&self, metrics: &SystemMetrics) -> Option<Anomaly> {
-        let rate = metrics.memory_growth_rate?;
-        let thresholds = &self.config.thresholds;
+    /// The fastest of `growers` holding at least `minimum` descriptors or
+    /// threads and gaining `per_hour` more. `count_and_rate` picks which.
+    fn check_handle_leak(
+        &self,
+        anomaly_type: AnomalyType,
+        growers: &[ProcessInfo],
+        count_and_rate: impl Fn(&ProcessInfo) -> (Option<u64>, Option<f64>),
+        minimum: u64,
+        per_hour: f64,
+        metrics: &SystemMetrics,
+    ) -> Option<Anomaly> {
+        let ignore = &self.config.handles.ignore;
+        let (top, count, rate) = growers
+            .iter()
+            .filter(|p| !ignore.iter().any(|pattern| name_or_exe_matches(pattern, &p.name, p.exe.as_deref())))
+            .filter_map(|p| match count_and_rate(p) {
+                (Some(count), Some(rate)) if count >= minimum && rate >= per_hour => Some((p, count, rate)),
+                _ => None,
+            })
+            .max_by(|a, b| a.2.total_cmp(&b.2))?;
+
+        let level = AlertLevel::Warning;
+        let vars = self.base_vars(anomaly_type, level, rate, per_hour, metrics)
+            .with("rate", rate)
+            .with("culprit", top.human_name())
+            .with("pid", top.pid as f64)
+            .with("count", count as f64);
+        Some(self.build_anomaly(anomaly_type, level, rate, &vars, metrics))
+    }
 
-        // Condition 1: Only alert on positive growth (memory increasing)
-        if rate <= 0.0 {
+    /// Allocated file handles close to fs.file-max: soon every open() and
+    /// socket() on the system fails. The culprit is the process holding the
+    /// most descriptors among those collected.
+    fn check_file_table(&self, metrics: &SystemMetrics) -> Option<Anomaly> {
+        let h = &self.config.handles;
+        let table = metrics.file_table?;
+        let used = table.used_percent();
+        let (level, threshold) = if used >= h.file_table_critical_percent {
+            (AlertLevel::Critical, h.file_table_critical_percent)
+        } else if used >= h.file_table_warning_percent {
+            (AlertLevel::Warning, h.file_table_warning_percent)
+        } else {
             return None;
+        };
+
+        let culprit = metrics
+            .top_fd_growers
+            .iter()
+            .chain(&metrics.top_processes)
+            .filter(|p| p.open_fds.is_some())
+            .max_by_key(|p| p.open_fds)
+            .map_or_else(|| "Unknown".to_string(), |p| format!("{} ({} open)", p.human_name(), p.open_fds.unwrap_or(0)));
+        let vars = self.base_vars(AnomalyType::FileTable, level, used, threshold, metrics)
+            .with("culprit", culprit)
+            .with("allocated", table.allocated as f64)
+            .with("max", table.max as f64);
+        Some(self.build_anomaly(AnomalyType::FileTable, level, used, &vars, metrics))
+    }
+
+    /// A program restarted `restart_loop_count` times within the window is
+    /// flapping: each instance is short-lived, so it never shows up as heavy.
+    fn check_restart_loops(&self, metrics: &SystemMetrics) -> Option<Anomaly> {
+        let detection = &self.config.detection;
+        let window = chrono::Duration::minutes(detection.restart_loop_window_minutes as i64);
+
+        let (count, program) = metrics
+            .process_restarts
+            .iter()
+            .map(|r| (r.restarts.iter().filter(|t| metrics.timestamp - **t <= window).count(), r))
+            .filter(|(count, _)| *count as u32 >= detection.restart_loop_count.max(1))
+            .max_by_key(|(count, _)| *count)?;
+
+        let level = AlertLevel::Warning;
+        let vars = self.base_vars(AnomalyType::RestartLoop, level, count as f64, detection.restart_loop_count as f64, metrics)
+            .with("culprit", program.name.clone())
+            .with("pid", program.pid as f64)
+            .with("window", format_duration(window));
+        Some(self.build_anomaly(AnomalyType::RestartLoop, level, count as f64, &vars, metrics))
+    }
+
+    /// A must-run process that exited is Critical until a matching process runs again
+    fn check_must_run(&mut self, metrics: &SystemMetrics) -> Option<Anomaly> {
+        let patterns = &self.config.detection.must_run;
+        let running = |pattern: &str| metrics.processes.iter().any(|p| identity_matches(pattern, p));
+
+        self.vanished.retain(|pattern, _| patterns.contains(pattern) && !running(pattern));
+        for process in &metrics.exited_processes {
+            for pattern in patterns {
+                if identity_matches(pattern, process) && !running(pattern) {
+                    self.vanished.entry(pattern.clone()).or_insert_with(|| (process.clone(), metrics.timestamp));
+                }
+            }
         }
 
-        // Phase 3: Cross-Metric Correlation
-        // Only alert on growth if the system is actually under some pressure,
-        // unless growth is so high it's clearly an emergency.
-        let high_pressure = metrics.memory_percent > 80.0 || metrics.swap_percent > 80.0;
-        let critical_growth = rate >= 10.0;
+        let (process, since) = self.vanished.values().min_by_key(|(_, since)| *since)?;
+        let level = AlertLevel::Critical;
+        let vars = self.base_vars(AnomalyType::ProcessExited, level, 0.0, 0.0, metrics)
+            .with("culprit", process.name.clone())
+            .with("pid", process.pid as f64)
+            .with("missing", self.vanished.len() as f64)
+            .with("gone_for", format_duration(metrics.timestamp - *since));
+        Some(self.build_anomaly(AnomalyType::ProcessExited, level, 0.0, &vars, metrics))
+    }
 
-        if !high_pressure && !critical_growth {
-             // System is healthy enough to handle some growth without bothering the user.
-             return None;
+    /// Required processes absent for longer than their grace period. The alert
+    /// resolves on the first sample where all of them are running again.
+    fn check_required_processes(&mut self, metrics: &SystemMetrics) -> Option<Anomaly> {
+        let required = &self.config.detection.required_processes;
+        for (i, process) in required.iter().enumerate() {
+            if metrics.processes.iter().any(|p| required_matches(process, p)) {
+                self.missing_since.remove(&i);
+            } else {
+                self.missing_since.entry(i).or_insert(metrics.timestamp);
+            }
         }
 
-        // Condition 2: Only alert if system memory usage is significant (> 60%)
-        // This avoids noise when memory is fluctuates at low usage levels.
-        if metrics.memory_percent < 60.0 {
-            return None;
+        let mut overdue: Vec<(&RequiredProcess, chrono::DateTime<chrono::Local>)> = self
+            .missing_since
+            .iter()
+            .filter_map(|(&i, &since)| required.get(i).map(|p| (p, since)))
+            .filter(|(p, since)| metrics.timestamp - *since >= chrono::Duration::seconds(p.grace_seconds as i64))
+            .collect();
+        overdue.sort_by_key(|(_, since)| *since);
+        let (first, since) = overdue.first()?;
+
+        let level = AlertLevel::Warning;
+        let names: Vec<String> = overdue.iter().map(|(p, _)| p.label()).collect();
+        let vars = self.base_vars(AnomalyType::ProcessMissing, level, overdue.len() as f64, 0.0, metrics)
+            .with("culprit", first.label())
+            .with("missing", names.join(", "))
+            .with("gone_for", format_duration(metrics.timestamp - *since));
+        Some(self.build_anomaly(AnomalyType::ProcessMissing, level, overdue.len() as f64, &vars, metrics))
+    }
+
+    /// Failing probes alert at their configured level. Hysteresis: once failing,
+    /// a probe must succeed `recover_after` times in a row to count as healthy.
+    fn check_probes(&mut self, metrics: &SystemMetrics) -> Option<Anomaly> {
+        let mut failing = Vec::new();
+        for result in &metrics.probes {
+            let Some(probe) = self.config.probes.iter().find(|p| p.name == result.name) else { continue };
+            if !result.ok {
+                self.failing_probes.insert(result.name.clone());
+            } else if result.consecutive_successes >= probe.recover_after {
+                self.failing_probes.remove(&result.name);
+            }
+            if self.failing_probes.contains(&result.name) {
+                failing.push((probe.level, result));
+            }
         }
+        self.failing_probes.retain(|name| metrics.probes.iter().any(|r| &r.name == name));
+
+        let &(level, result) = failing.iter().max_by_key(|(level, r)| (*level, r.consecutive_failures))?;
+        let latency = result.latency_ms.unwrap_or(0.0);
+        let names: Vec<&str> = failing.iter().map(|(_, r)| r.name.as_str()).collect();
+
+        let vars = self.base_vars(AnomalyType::ProbeFailed, level, latency, 0.0, metrics)
+            .with("culprit", result.name.clone())
+            .with("target", result.target.clone())
+            .with("error", result.error.clone().unwrap_or_else(|| "recovering".to_string()))
+            .with("failing", names.join(", "));
+        Some(self.build_anomaly(AnomalyType::ProbeFailed, level, latency, &vars, metrics))
+    }
 
-        if rate >= thresholds.memory_growth_rate_critical {
-            let culprit = self.get_memory_culprit(metrics);
-            let msg = format!("Growth {:.0}GB/h: {}", rate, culprit);
-            
-            Some(Anomaly {
-                anomaly_type: AnomalyType::MemoryGrowthRate,
-                level: AlertLevel::Critical,
-                message: msg.clone(),
-                details: vec![],
-                narration_message: format!("Memory growth critical. {:.1} gigabytes per hour. {}", rate, culprit),
-                sound_hint: Some("sounds/unused/Futuristic Hum 2133.wav".to_string()),
-            })
-        } else if rate >= thresholds.memory_growth_rate_warning {
-            let culprit = self.get_memory_culprit(metrics);
-            let msg = format!("Growth {:.0}GB/h: {}", rate, culprit);
-            
-            Some(Anomaly {
-                anomaly_type: AnomalyType::MemoryGrowthRate,
-                level: AlertLevel::Warning,
-                message: msg.clone(),
-                details: vec![],
-                narration_message: format!("Memory growth high. {:.1} gigabytes per hour. {}", rate, culprit),
-                sound_hint: Some("sounds/subtle/alien_button.wav".to_string()),
+    /// Plugins reporting WARNING or CRITICAL, or UNKNOWN at the plugin's
+    /// configured level, each under its own type name
+    fn check_plugins(&self, metrics: &SystemMetrics) -> Vec<Anomaly> {
+        let failing = metrics.plugins.iter().filter(|r| r.state != PluginState::Ok).count();
+        metrics
+            .plugins
+            .iter()
+            .filter_map(|r| {
+                let level = match r.state {
+                    PluginState::Ok => return None,
+                    PluginState::Warning => AlertLevel::Warning,
+                    PluginState::Critical => AlertLevel::Critical,
+                    PluginState::Unknown => self.config.plugins.iter().find(|p| p.name == r.name)?.unknown_level,
+                };
+                let vars = self.base_vars(AnomalyType::Plugin, level, 0.0, 0.0, metrics)
+                    .with("type", r.name.clone())
+                    .with("duration", format_duration(self.breach_duration(&r.name, metrics)))
+                    .with("culprit", r.name.clone())
+                    .with("output", r.output.clone())
+                    .with("failing", failing as f64);
+                let mut anomaly = self.build_anomaly(AnomalyType::Plugin, level, 0.0, &vars, metrics);
+                anomaly.custom_type = Some(r.name.clone());
+                Some(anomaly)
             })
-        } else {
-            None
-        }
+            .collect()
     }
 
-    fn check_process_watchlist(&self, metrics: &SystemMetrics) -> Option<Anomaly> {
-        let watchlist = &self.config.detection.process_watchlist;
-        let threshold_mb = self.config.detection.process_memory_threshold_mb;
+    /// One anomaly per alert type returned by the detection scripts, the
+    /// most severe when a type comes back more than once. Scripts write their
+    /// own text, so templates don't apply.
+    fn check_scripts(&mut self, metrics: &SystemMetrics) -> Vec<Anomaly> {
+        let mut worst: Vec<ScriptAlert> = Vec::new();
+        for alert in self.scripts.run(metrics) {
+            match worst.iter_mut().find(|a| a.name == alert.name) {
+                Some(seen) if seen.level < alert.level => *seen = alert,
+                Some(_) => {}
+                None => worst.push(alert),
+            }
+        }
 
-        // Check both raw top processes and aggregated processes
-        let mut all_to_check = metrics.top_processes.clone();
-        all_to_check.extend(metrics.aggregated_processes.clone());
+        worst
+            .into_iter()
+            .map(|alert| Anomaly {
+                anomaly_type: AnomalyType::Script,
+                level: alert.level,
+                message: alert.message,
+                details: alert.details,
+                narration_message: alert.narration,
+                value: alert.value,
+                started_at: metrics.timestamp,
+                custom_type: Some(alert.name),
+                lower_is_worse: false,
+            })
+            .collect()
+    }
 
-        for proc in all_to_check {
-            let proc_name_lower = proc.name.to_lowercase();
+    /// Template variables shared by every anomaly type
+    fn base_vars(&self, anomaly_type: AnomalyType, level: AlertLevel, value: f64, threshold: f64, metrics: &SystemMetrics) -> TemplateVars {
+        TemplateVars::new()
+            .with("value", value)
+            .with("threshold", threshold)
+            .with("duration", format_duration(self.breach_duration(anomaly_type.key(), metrics)))
+            .with("level", level.to_string())
+            .with("type", format!("{:?}", anomaly_type))
+    }
 
-            for watched in watchlist {
-                if proc_name_lower.contains(&watched.to_lowercase()) {
-                    if proc.memory_mb >= threshold_mb as f64 {
-                        let name = proc.human_name();
-
-                        return Some(Anomaly {
-                            anomaly_type: AnomalyType::ProcessWatchlist,
-                            level: AlertLevel::Warning,
-                            message: format!("Heavy App: {} ({:.0}GB)", name, proc.memory_mb / 1024.0),
-                            details: vec![],
-                            narration_message: format!("Process {} memory high.", name),
-                            sound_hint: Some("sounds/subtle/alien_button.wav".to_string()),
-                        });
-                    }
-                }
-            }
-        }
+    /// How long the anomaly named `name` has been breaching, up to the previous check
+    fn breach_duration(&self, name: &str, metrics: &SystemMetrics) -> chrono::Duration {
+        self.breach_started.get(name)
+            .map(|start| metrics.timestamp - *start)
+            .unwrap_or_else(chrono::Duration::zero)
+    }
 
-        None
+    /// Render the configured templates into an `Anomaly`
+    fn build_anomaly(&self, anomaly_type: AnomalyType, level: AlertLevel, value: f64, vars: &TemplateVars, metrics: &SystemMetrics) -> Anomaly {
+        let text = self.templates.render(anomaly_type, level, vars);
+
+        Anomaly {
+            anomaly_type,
+            level,
+            message: text.message,
+            details: text.details,
+            narration_message: text.narration,
+            value,
+            started_at: metrics.timestamp,
+            custom_type: None,
+            lower_is_worse: false,
+        }
     }
 
     /// Check if enough time has passed since last notification for this key
@@ -426,7 +1045,7 @@ impl AnomalyDetector {
         all_procs.extend(metrics.aggregated_processes.clone());
         
         // Sort by memory descending
-        all_procs.sort_by(|a, b| b.memory_bytes.cmp(&a.memory_bytes));
+        all_procs.sort_by_key(|p| std::cmp::Reverse(p.memory_bytes));
 
         if let Some(top) = all_procs.first() {
             let name = top.human_name();
@@ -440,7 +1059,8 @@ impl AnomalyDetector {
 
     /// Get string describing the top CPU user: "ffmpeg (120%)"
     fn get_cpu_culprit(&self, metrics: &SystemMetrics) -> String {
-        let mut all_procs = metrics.top_processes.clone();
+        let mut all_procs = metrics.top_cpu_processes.clone();
+        all_procs.extend(metrics.top_processes.clone());
         all_procs.extend(metrics.aggregated_processes.clone());
         
         // Sort by CPU descending
@@ -456,6 +1076,61 @@ impl AnomalyDetector {
     }
 }
 
+/// Anomaly type and level of a kernel event
+fn event_type(event: &KernelEvent) -> (AnomalyType, AlertLevel) {
+    match event.kind {
+        KernelEventKind::OomKill => (AnomalyType::OomKill, AlertLevel::Critical),
+        KernelEventKind::Crash => (AnomalyType::Crash, AlertLevel::Warning),
+    }
+}
+
+/// Middle value (mean of the middle two for an even count); 0 when empty
+fn median(values: impl Iterator<Item = f64>) -> f64 {
+    let mut values: Vec<f64> = values.collect();
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) { (values[mid - 1] + values[mid]) / 2.0 } else { values[mid] }
+}
+
+/// "rising", "falling" or "steady": whether the 1, 5 and 15 minute load
+/// averages move in one direction, by more than a fifth overall. Shown in the
+/// load alert; a falling trend also holds back load Warnings.
+fn load_trend(metrics: &SystemMetrics) -> &'static str {
+    let (now, mid, old) = (metrics.load_1m, metrics.load_5m, metrics.load_15m);
+    if now >= mid && mid >= old && now > old * 1.2 {
+        "rising"
+    } else if now <= mid && mid <= old && now < old * 0.8 {
+        "falling"
+    } else {
+        "steady"
+    }
+}
+
+/// Whether every matcher set on `required` matches the process
+fn required_matches(required: &RequiredProcess, process: &ProcessIdentity) -> bool {
+    let exe = process.exe.as_deref().unwrap_or("");
+    required.name.as_ref().is_none_or(|n| n.eq_ignore_ascii_case(&process.name))
+        && required.exe.as_ref().is_none_or(|g| glob_match(g, exe))
+        && required.regex.as_ref().is_none_or(|r| r.is_match(&process.name) || r.is_match(exe))
+        && (required.name.is_some() || required.exe.is_some() || required.regex.is_some())
+}
+
+/// Whether a glob matches the process name, executable path or file name
+fn identity_matches(pattern: &str, process: &ProcessIdentity) -> bool {
+    name_or_exe_matches(pattern, &process.name, process.exe.as_deref())
+}
+
+fn name_or_exe_matches(pattern: &str, name: &str, exe: Option<&str>) -> bool {
+    glob_match(pattern, name)
+        || exe.is_some_and(|exe| {
+            glob_match(pattern, exe)
+                || std::path::Path::new(exe).file_name().is_some_and(|f| glob_match(pattern, &f.to_string_lossy()))
+        })
+}
+
 
 
 
@@ -478,12 +1153,93 @@ mod tests {
             load_1m: 1.0,
             load_5m: 1.0,
             load_15m: 1.0,
+            logical_cores: 8,
+            physical_cores: Some(8),
+            cpu_usage: 10.0,
+            cpu_per_core: vec![10.0; 8],
+            disks: vec![],
+            disk_io: vec![],
             top_processes: vec![],
+            top_cpu_processes: vec![],
+            top_io_processes: vec![],
             aggregated_processes: vec![],
             memory_growth_rate: growth,
+            top_growers: vec![],
+            top_fd_growers: vec![],
+            top_thread_growers: vec![],
+            file_table: None,
+            parent_pids: Default::default(),
+            frozen_processes: vec![],
+            cgroups: vec![],
+            network: Default::default(),
+            thermal: Default::default(),
+            processes: vec![],
+            exited_processes: vec![],
+            process_restarts: vec![],
+            probes: vec![],
+            plugins: vec![],
         }
     }
 
+    #[test]
+    fn test_scripts_alert_separately() {
+        let dir = std::env::temp_dir().join(format!("sentinel-detector-scripts-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let noisy = dir.join("noisy.rhai");
+        let disks = dir.join("disks.rhai");
+        std::fs::write(&noisy, r#"#{ level: "critical", message: "noisy" }"#).unwrap();
+        std::fs::write(
+            &disks,
+            r#"[#{ level: "warning", message: "sdb", type: "disk" }, #{ level: "critical", message: "sda", type: "disk" }]"#,
+        )
+        .unwrap();
+
+        let mut config = Config::default();
+        config.detection.persistent_breach_threshold = 1;
+        config.scripting.scripts = vec![noisy.to_string_lossy().to_string(), disks.to_string_lossy().to_string()];
+        let mut detector = AnomalyDetector::new(&config);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        // The noisy script cools down without hiding the other one, whose
+        // alerts of one type are reported once, at the worst level
+        let metrics = mock_metrics(50.0, 0.0, None);
+        assert_eq!(detector.check(&metrics).expect("noisy").name(), "noisy");
+        let disk = detector.check(&metrics).expect("disk");
+        assert_eq!((disk.name(), disk.message.as_str()), ("disk".to_string(), "sda"));
+        assert!(detector.check(&metrics).is_none());
+        assert_eq!(detector.suppressed().iter().filter(|s| s.alert == "disk").count(), 1);
+    }
+
+    #[test]
+    fn test_plugins_alert_separately() {
+        use crate::plugins::PluginResult;
+
+        let mut config = Config::default();
+        config.detection.persistent_breach_threshold = 1;
+        let mut detector = AnomalyDetector::new(&config);
+
+        let result = |name: &str, state| PluginResult {
+            name: name.to_string(),
+            state,
+            output: format!("{} output", name),
+            perfdata: vec![],
+            checked_at: chrono::Local::now(),
+            duration_ms: 1.0,
+        };
+        let mut metrics = mock_metrics(50.0, 0.0, None);
+        metrics.plugins = vec![
+            result("raid", PluginState::Critical),
+            result("backup_freshness", PluginState::Warning),
+            result("ntp", PluginState::Ok),
+        ];
+
+        // The Critical goes first and then cools down; the Warning is not hidden behind it
+        assert_eq!(detector.check(&metrics).expect("raid").name(), "raid");
+        assert_eq!(detector.check(&metrics).expect("backup").name(), "backup_freshness");
+        assert!(detector.check(&metrics).is_none());
+        assert!(detector.suppressed().iter().any(|s| s.alert == "raid" && s.reason.starts_with("cooldown")));
+    }
+
     #[test]
     fn test_damping() {
         let mut config = Config::default();
@@ -495,11 +1251,11 @@ mod tests {
 
         // Breach 1
         assert!(detector.check(&metrics).is_none());
-        assert_eq!(*detector.breach_counters.get(&AnomalyType::Memory).unwrap(), 1);
+        assert_eq!(*detector.breach_counters.get("memory").unwrap(), 1);
 
         // Breach 2
         assert!(detector.check(&metrics).is_none());
-        assert_eq!(*detector.breach_counters.get(&AnomalyType::Memory).unwrap(), 2);
+        assert_eq!(*detector.breach_counters.get("memory").unwrap(), 2);
 
         // Breach 3
         let a = detector.check(&metrics).expect("Should alert now");
@@ -528,7 +1284,7 @@ mod tests {
         // 3. Drop below recovery (75.0 -> 74.0)
         let m_low = mock_metrics(74.0, 0.0, None);
         assert!(detector.check(&m_low).is_none());
-        assert!(detector.active_alerts.get(&AnomalyType::Memory).is_none());
+        assert!(!detector.active_alerts.contains_key("memory"));
     }
 
     #[test]
@@ -568,4 +1324,437 @@ mod tests {
         let a = detector.check(&m_high_mem).expect("Should alert when memory is high");
         assert_eq!(a.anomaly_type, AnomalyType::Swap);
     }
+
+    #[test]
+    fn test_configured_inhibition_and_gates() {
+        let mut config: Config = toml::from_str(
+            r#"
+            [[rules]]
+            name = "swap"
+            metric = "swap_percent"
+            warning = 80
+            critical = 95
+            gates = [{ metric = "memory_percent", above = 60 }]
+
+            [[inhibitions]]
+            source = "swap"
+            targets = ["memory"]
+            "#,
+        )
+        .unwrap();
+        config.detection.persistent_breach_threshold = 1;
+        config.detection.notification_cooldown_minutes = 0;
+        config.thresholds.memory_warning = 60.0;
+        let mut detector = AnomalyDetector::new(&config);
+
+        // The replaced swap gate lets swap through at 70% memory, and swap
+        // then inhibits the memory alert
+        let a = detector.check(&mock_metrics(70.0, 90.0, None)).expect("swap should alert");
+        assert_eq!(a.anomaly_type, AnomalyType::Swap);
+        let memory = detector.suppressed().iter().find(|s| s.alert == "memory").expect("memory suppressed");
+        assert_eq!(memory.reason, "inhibited by swap");
+
+        assert!(detector.check(&mock_metrics(50.0, 90.0, None)).is_none());
+        let swap = &detector.suppressed()[0];
+        assert_eq!(swap.alert, "swap");
+        assert_eq!(swap.reason, "needs memory_percent > 60 (is 50.0)");
+    }
+
+    #[test]
+    fn test_rules_damped_separately() {
+        let mut config: Config = toml::from_str(
+            r#"
+            [[rules]]
+            name = "busy"
+            metric = "cpu_usage"
+            warning = 5
+
+            [[rules]]
+            name = "loaded"
+            metric = "load_1m"
+            warning = 0.5
+
+            [[rules]]
+            name = "loaded_5m"
+            metric = "load_5m"
+            warning = 0.5
+            "#,
+        )
+        .unwrap();
+        config.detection.persistent_breach_threshold = 3;
+        config.detection.notification_cooldown_minutes = 0;
+        let mut detector = AnomalyDetector::new(&config);
+
+        // Three rules breaching together still need three ticks each
+        let mut metrics = mock_metrics(50.0, 0.0, None);
+        assert!(detector.check(&metrics).is_none());
+        assert!(detector.check(&metrics).is_none());
+        assert!(detector.check(&metrics).is_some());
+
+        // One rule recovering leaves the others' streaks alone
+        metrics.cpu_usage = 1.0;
+        assert!(detector.check(&metrics).is_some());
+        assert!(!detector.breach_counters.contains_key("busy"));
+        assert_eq!(detector.breach_counters.get("loaded"), Some(&4));
+        assert_eq!(detector.breach_counters.get("loaded_5m"), Some(&4));
+    }
+
+    #[test]
+    fn test_load_per_core() {
+        let mut config = Config::default();
+        config.detection.persistent_breach_threshold = 1;
+        config.detection.notification_cooldown_minutes = 0;
+        config.thresholds.load_per_core_warning = Some(1.25);
+        config.thresholds.load_per_core_critical = Some(4.0);
+        let mut detector = AnomalyDetector::new(&config);
+
+        // Load 6 is fine on 8 cores but oversubscribes a 4-core laptop
+        let mut metrics = mock_metrics(50.0, 0.0, None);
+        (metrics.load_1m, metrics.load_5m, metrics.load_15m) = (6.0, 4.0, 2.0);
+        assert!(detector.check(&metrics).is_none());
+        metrics.timestamp += chrono::Duration::seconds(180);
+        assert!(detector.check(&metrics).is_none());
+
+        metrics.logical_cores = 4;
+        assert!(detector.check(&metrics).is_none(), "load must be sustained");
+        metrics.timestamp += chrono::Duration::seconds(180);
+        let a = detector.check(&metrics).expect("1.5 per core should alert");
+        assert_eq!(a.anomaly_type, AnomalyType::Load);
+        assert_eq!(a.level, AlertLevel::Warning);
+        assert_eq!(a.value, 1.5);
+        assert!(a.message.starts_with("Load 6.0 on 4 cores, rising"), "{}", a.message);
+
+        // Still above the threshold but easing off: a Warning waits, a Critical doesn't
+        (metrics.load_1m, metrics.load_5m, metrics.load_15m) = (6.0, 9.0, 12.0);
+        assert!(detector.check(&metrics).is_none());
+        assert!(detector.suppressed().iter().any(|s| s.alert == "load" && s.reason.starts_with("load falling")));
+        (metrics.load_1m, metrics.load_5m, metrics.load_15m) = (20.0, 30.0, 40.0);
+        assert_eq!(detector.check(&metrics).expect("critical load").level, AlertLevel::Critical);
+    }
+
+    #[test]
+    fn test_cpu_pinned() {
+        let mut config = Config::default();
+        config.detection.persistent_breach_threshold = 1;
+        config.detection.notification_cooldown_minutes = 0;
+        config.detection.cpu_pinned_minutes = 10;
+        config.detection.cpu_pinned_ignore = vec!["ffmpeg".to_string()];
+        let mut detector = AnomalyDetector::new(&config);
+
+        let process = |pid, name: &str, cpu_usage| ProcessInfo {
+            pid,
+            parent_pid: None,
+            name: name.to_string(),
+            memory_bytes: 0,
+            memory_mb: 200.0,
+            cpu_usage,
+            exe: None,
+            memory_growth_rate: None,
+            open_fds: None,
+            threads: None,
+            fd_growth_rate: None,
+            thread_growth_rate: None,
+        };
+        let mut metrics = mock_metrics(50.0, 0.0, None);
+        metrics.top_cpu_processes = vec![process(10, "ffmpeg", 400.0), process(20, "mds_stores", 99.0)];
+        assert!(detector.check(&metrics).is_none());
+
+        metrics.timestamp += chrono::Duration::minutes(12);
+        let a = detector.check(&metrics).expect("pinned for 12 minutes");
+        assert_eq!(a.anomaly_type, AnomalyType::CpuPinned);
+        assert!(a.message.contains("mds_stores"), "{}", a.message);
+
+        // A dip below the threshold restarts the clock
+        metrics.top_cpu_processes[1].cpu_usage = 40.0;
+        assert!(detector.check(&metrics).is_none());
+        metrics.top_cpu_processes[1].cpu_usage = 99.0;
+        metrics.timestamp += chrono::Duration::minutes(5);
+        assert!(detector.check(&metrics).is_none());
+    }
+
+    #[test]
+    fn test_thermal() {
+        use crate::thermal::{CpuFrequency, Temperature};
+
+        let mut config = Config::default();
+        config.detection.persistent_breach_threshold = 1;
+        config.detection.notification_cooldown_minutes = 0;
+        config.thermal.ignore = vec!["thermal_zone*".to_string()];
+        let mut detector = AnomalyDetector::new(&config);
+
+        let sensor = |chip: &str, celsius| Temperature {
+            chip: chip.to_string(),
+            label: "Package id 0".to_string(),
+            celsius,
+            max_celsius: None,
+            critical_celsius: None,
+        };
+        let mut metrics = mock_metrics(50.0, 0.0, None);
+        metrics.thermal.temperatures = vec![sensor("coretemp", 80.0), sensor("thermal_zone0", 120.0)];
+        metrics.thermal.cpu_frequencies = vec![CpuFrequency { cpu: 0, current_mhz: 1600.0, max_mhz: 4000.0 }];
+        assert!(detector.check(&metrics).is_none(), "ignored zone, and not busy enough to count as throttled");
+
+        // Busy at 40% speed
+        metrics.cpu_usage = 95.0;
+        assert!(detector.check(&metrics).is_none(), "throttling must be sustained");
+        metrics.timestamp += chrono::Duration::seconds(150);
+        let a = detector.check(&metrics).expect("sustained throttling");
+        assert_eq!((a.anomaly_type, a.level), (AnomalyType::Thermal, AlertLevel::Warning));
+        assert_eq!(a.message, "Throttled: coretemp/Package id 0 80°C, CPU at 40% speed");
+
+        metrics.thermal.temperatures[0].celsius = 97.0;
+        metrics.timestamp += chrono::Duration::seconds(150);
+        assert!(detector.check(&metrics).is_some_and(|a| !a.message.starts_with("Hot")), "heat not sustained yet");
+        metrics.timestamp += chrono::Duration::seconds(150);
+        let a = detector.check(&metrics).expect("sustained heat");
+        assert_eq!(a.level, AlertLevel::Critical);
+        assert!(a.message.starts_with("Hot and throttled"), "{}", a.message);
+    }
+
+    #[test]
+    fn test_io_saturation() {
+        use crate::metrics::ProcessIo;
+
+        let mut config = Config::default();
+        config.detection.persistent_breach_threshold = 1;
+        config.detection.notification_cooldown_minutes = 0;
+        let mut detector = AnomalyDetector::new(&config);
+
+        let disk = |device: &str, utilization_percent, await_ms| DiskIo {
+            device: device.to_string(),
+            read_bytes_per_sec: 0.0,
+            write_bytes_per_sec: 200.0 * 1024.0 * 1024.0,
+            read_iops: 0.0,
+            write_iops: 1500.0,
+            utilization_percent,
+            await_ms,
+        };
+        let mut metrics = mock_metrics(50.0, 0.0, None);
+        // Busy but fast (an SSD at full parallelism), and slow but mostly idle
+        metrics.disk_io = vec![disk("nvme0n1", 100.0, 2.0), disk("sda", 20.0, 300.0)];
+        metrics.top_io_processes = vec![ProcessIo {
+            pid: 42,
+            name: "dockerd".to_string(),
+            read_bytes_per_sec: 0.0,
+            write_bytes_per_sec: 180.0 * 1024.0 * 1024.0,
+        }];
+        assert!(detector.check(&metrics).is_none());
+
+        metrics.disk_io[1].utilization_percent = 98.0;
+        assert!(detector.check(&metrics).is_none(), "saturation must be sustained");
+        metrics.timestamp += chrono::Duration::seconds(90);
+        let a = detector.check(&metrics).expect("sda saturated");
+        assert_eq!((a.anomaly_type, a.level), (AnomalyType::IoSaturation, AlertLevel::Warning));
+        assert_eq!(a.message, "I/O saturated: sda 98% busy, 300ms: dockerd (180MB/s)");
+    }
+
+    #[test]
+    fn test_upload_spike_and_socket_leak() {
+        use crate::network::{InterfaceStats, ProcessSockets};
+
+        let mut config = Config::default();
+        config.detection.persistent_breach_threshold = 1;
+        config.detection.notification_cooldown_minutes = 0;
+        let mut detector = AnomalyDetector::new(&config);
+
+        let interface = |name: &str, mb: f64| InterfaceStats {
+            name: name.to_string(),
+            rx_bytes_per_sec: 0.0,
+            tx_bytes_per_sec: mb * 1024.0 * 1024.0,
+            rx_errors: 0,
+            tx_errors: 0,
+            rx_dropped: 0,
+            tx_dropped: 0,
+        };
+        let mut metrics = mock_metrics(50.0, 0.0, None);
+        for _ in 0..MIN_BASELINE_SAMPLES {
+            metrics.network.interfaces = vec![interface("eth0", 0.5), interface("docker0", 0.0)];
+            assert!(detector.check(&metrics).is_none());
+        }
+        // docker0 is ignored by default
+        metrics.network.interfaces = vec![interface("eth0", 0.5), interface("docker0", 80.0)];
+        assert!(detector.check(&metrics).is_none());
+        metrics.network.interfaces = vec![interface("eth0", 40.0), interface("docker0", 0.0)];
+        let a = detector.check(&metrics).expect("eth0 upload spike");
+        assert_eq!(a.anomaly_type, AnomalyType::UploadSpike);
+        assert_eq!(a.message, "Upload spike: eth0 40.0MB/s, usually 0.5MB/s");
+
+        metrics.network.interfaces.clear();
+        metrics.network.tcp_states.insert("CLOSE_WAIT".to_string(), 10_000);
+        let a = detector.check(&metrics).expect("system-wide CLOSE_WAIT pile-up");
+        assert!(a.message.contains("Unknown"), "{}", a.message);
+
+        metrics.network.process_sockets = vec![ProcessSockets { pid: 7, name: "node".to_string(), tcp: 10_200, close_wait: 9_800 }];
+        let a = detector.check(&metrics).expect("node leaks sockets");
+        assert_eq!(a.anomaly_type, AnomalyType::SocketLeak);
+        assert_eq!(a.message, "Socket leak: node (PID 7), 9800 CLOSE_WAIT of 10200");
+    }
+
+    #[test]
+    fn test_handle_leaks_and_file_table() {
+        use crate::handles::FileTable;
+
+        let mut config = Config::default();
+        config.detection.persistent_breach_threshold = 1;
+        config.detection.notification_cooldown_minutes = 0;
+        config.handles.ignore = vec!["chrome".to_string()];
+        let mut detector = AnomalyDetector::new(&config);
+
+        let process = |pid, name: &str, open_fds, fd_growth_rate| ProcessInfo {
+            pid,
+            parent_pid: None,
+            name: name.to_string(),
+            memory_bytes: 0,
+            memory_mb: 200.0,
+            cpu_usage: 1.0,
+            exe: None,
+            memory_growth_rate: None,
+            open_fds: Some(open_fds),
+            threads: Some(40),
+            fd_growth_rate: Some(fd_growth_rate),
+            thread_growth_rate: Some(0.0),
+        };
+        let mut metrics = mock_metrics(50.0, 0.0, None);
+        // Growing fast but still small, and ignored
+        metrics.top_fd_growers = vec![process(10, "postgres", 300, 5000.0), process(20, "chrome", 20_000, 9000.0)];
+        assert!(detector.check(&metrics).is_none());
+
+        metrics.top_fd_growers.push(process(30, "node", 4200, 1800.0));
+        let a = detector.check(&metrics).expect("node leaks descriptors");
+        assert_eq!(a.anomaly_type, AnomalyType::FdLeak);
+        assert_eq!(a.message, "FD leak: node holds 4200 descriptors, +1800/h");
+
+        let mut worker = process(40, "worker", 10, 0.0);
+        (worker.threads, worker.thread_growth_rate) = (Some(900), Some(600.0));
+        metrics.top_fd_growers.clear();
+        metrics.top_thread_growers = vec![worker];
+        let a = detector.check(&metrics).expect("worker leaks threads");
+        assert_eq!(a.anomaly_type, AnomalyType::ThreadLeak);
+        assert_eq!(a.message, "Thread leak: worker runs 900 threads, +600/h");
+
+        metrics.top_thread_growers.clear();
+        metrics.file_table = Some(FileTable { allocated: 90_000, max: 100_000 });
+        metrics.top_processes = vec![process(50, "java", 60_000, 0.0)];
+        let a = detector.check(&metrics).expect("file table filling up");
+        assert_eq!((a.anomaly_type, a.level), (AnomalyType::FileTable, AlertLevel::Warning));
+        assert_eq!(a.message, "File handles: 90% of 100000 in use, top: java (60000 open)");
+        metrics.file_table = Some(FileTable { allocated: 96_000, max: 100_000 });
+        assert_eq!(detector.check(&metrics).expect("nearly full").level, AlertLevel::Critical);
+    }
+
+    #[test]
+    fn test_rate_only_where_it_applies() {
+        use crate::config::TemplateConfig;
+        use crate::handles::FileTable;
+
+        let mut config = Config::default();
+        config.detection.persistent_breach_threshold = 1;
+        config.detection.notification_cooldown_minutes = 0;
+        let template = |anomaly_type| TemplateConfig {
+            anomaly_type: Some(anomaly_type),
+            level: None,
+            message: Some("{type} {rate:.1}".to_string()),
+            details: None,
+            narration: None,
+        };
+        config.templates = vec![template(AnomalyType::Memory), template(AnomalyType::FileTable)];
+        let mut detector = AnomalyDetector::new(&config);
+
+        let a = detector.check(&mock_metrics(90.0, 0.0, Some(2.5))).expect("memory");
+        assert_eq!(a.message, "Memory 2.5");
+
+        let mut metrics = mock_metrics(50.0, 0.0, Some(2.5));
+        metrics.file_table = Some(FileTable { allocated: 90_000, max: 100_000 });
+        let a = detector.check(&metrics).expect("file table");
+        assert_eq!(a.message, "FileTable {rate:.1}", "no rate for a file table alert");
+    }
+
+    #[test]
+    fn test_event_cooldown_per_victim() {
+        let mut detector = AnomalyDetector::new(&Config::default());
+        let crash = |process: &str, pid: u32| KernelEvent {
+            kind: KernelEventKind::Crash,
+            timestamp: chrono::Local::now(),
+            process: Some(process.to_string()),
+            pid: Some(pid),
+            anon_rss_kb: None,
+            cgroup: None,
+            detail: "segfault at 0".to_string(),
+        };
+
+        assert!(detector.event_due(&crash("worker", 100)));
+        assert!(!detector.event_due(&crash("worker", 101)), "same victim, new PID");
+        assert!(detector.event_due(&crash("node", 102)));
+        let oom = KernelEvent { kind: KernelEventKind::OomKill, ..crash("worker", 103) };
+        assert!(detector.event_due(&oom), "a different kind of event");
+        assert!(detector.event_due(&KernelEvent::cgroup_oom_kill("app.slice/a.scope", 1)));
+        assert!(!detector.event_due(&KernelEvent::cgroup_oom_kill("app.slice/a.scope", 2)));
+    }
+
+    #[test]
+    fn test_must_run_exit() {
+        let mut config = Config::default();
+        config.detection.persistent_breach_threshold = 1;
+        config.detection.notification_cooldown_minutes = 0;
+        config.detection.must_run = vec!["com.docker.*".to_string()];
+        let mut detector = AnomalyDetector::new(&config);
+
+        let docker = ProcessIdentity {
+            pid: 500,
+            name: "com.docker.backend".to_string(),
+            exe: Some("/Applications/Docker.app/Contents/MacOS/com.docker.backend".to_string()),
+            start_time: 1,
+            parent_pid: Some(1),
+            cmd: String::new(),
+        };
+        let mut metrics = mock_metrics(50.0, 0.0, None);
+        metrics.processes = vec![docker.clone()];
+        assert!(detector.check(&metrics).is_none());
+
+        metrics.processes.clear();
+        metrics.exited_processes = vec![docker.clone()];
+        let a = detector.check(&metrics).expect("exit of a must-run process should alert");
+        assert_eq!(a.anomaly_type, AnomalyType::ProcessExited);
+        assert_eq!(a.level, AlertLevel::Critical);
+
+        // Still gone on the next tick, then back
+        metrics.exited_processes.clear();
+        assert!(detector.active_level("process_exited").is_some());
+        detector.check(&metrics);
+        assert!(detector.active_level("process_exited").is_some());
+        metrics.processes = vec![ProcessIdentity { pid: 501, start_time: 2, ..docker }];
+        assert!(detector.check(&metrics).is_none());
+        assert!(detector.active_level("process_exited").is_none());
+    }
+
+    #[test]
+    fn test_required_process_grace() {
+        let mut config = Config::default();
+        config.detection.persistent_breach_threshold = 1;
+        config.detection.notification_cooldown_minutes = 0;
+        config.detection.required_processes = toml::from_str::<crate::config::DetectionConfig>(
+            r#"
+            [[required_processes]]
+            label = "TTS daemon"
+            regex = "^tts-?daemon$"
+            grace_seconds = 60
+            "#,
+        )
+        .unwrap()
+        .required_processes;
+        let mut detector = AnomalyDetector::new(&config);
+
+        let mut metrics = mock_metrics(50.0, 0.0, None);
+        assert!(detector.check(&metrics).is_none(), "absent but within grace");
+
+        metrics.timestamp += chrono::Duration::seconds(90);
+        let a = detector.check(&metrics).expect("absent past grace should alert");
+        assert_eq!(a.anomaly_type, AnomalyType::ProcessMissing);
+        assert!(a.message.contains("TTS daemon"));
+
+        metrics.timestamp += chrono::Duration::seconds(30);
+        metrics.processes = vec![ProcessIdentity { pid: 77, name: "ttsdaemon".to_string(), exe: None, start_time: 1, parent_pid: None, cmd: String::new() }];
+        assert!(detector.check(&metrics).is_none());
+        assert!(detector.active_level("process_missing").is_none());
+    }
 }
@@ -0,0 +1,306 @@
+//! Quiet hours and Warning digests
+//!
+//! While a quiet-hours window is active (or when a digest interval is set),
+//! Warnings are buffered here instead of being shown or narrated. When the
+//! window ends they are delivered as one summary listing each anomaly with
+//! its peak value and how long it lasted. Buffered entries keep taking breach
+//! samples while the alert cools down, so the peak and duration cover the
+//! whole breach. Criticals never pass through here.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveTime};
+use tracing::warn;
+
+use crate::config::{NotificationConfig, QuietHoursConfig};
+use crate::detector::{AlertLevel, Anomaly, AnomalyType};
+use crate::templates::format_duration;
+
+/// A daily time window, possibly wrapping past midnight
+#[derive(Debug, Clone, Copy)]
+pub struct QuietHours {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl QuietHours {
+    pub fn parse(config: &QuietHoursConfig) -> Result<Self> {
+        let start = NaiveTime::parse_from_str(&config.start, "%H:%M")
+            .with_context(|| format!("Invalid quiet hours start: {:?}", config.start))?;
+        let end = NaiveTime::parse_from_str(&config.end, "%H:%M")
+            .with_context(|| format!("Invalid quiet hours end: {:?}", config.end))?;
+        Ok(Self { start, end })
+    }
+
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            time >= self.start && time < self.end
+        } else {
+            // Wraps midnight, e.g. 22:00 -> 07:00
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// One buffered alert with its worst observation
+#[derive(Debug, Clone)]
+struct DigestEntry {
+    /// `Anomaly::name`, so that rules, plugins and scripts stay apart
+    name: String,
+    anomaly_type: AnomalyType,
+    level: AlertLevel,
+    peak_value: f64,
+    started_at: DateTime<Local>,
+    last_seen: DateTime<Local>,
+    count: u32,
+}
+
+/// A summary ready to be delivered
+#[derive(Debug, Clone)]
+pub struct DigestSummary {
+    pub message: String,
+    pub details: Vec<String>,
+}
+
+/// Buffers Warnings during quiet hours and decides when to flush them
+pub struct Digest {
+    windows: Vec<QuietHours>,
+    interval: Option<chrono::Duration>,
+    entries: Vec<DigestEntry>,
+    opened_at: Option<DateTime<Local>>,
+    was_quiet: bool,
+}
+
+impl Digest {
+    pub fn new(config: &NotificationConfig) -> Self {
+        let windows = config
+            .quiet_hours
+            .iter()
+            .filter_map(|w| match QuietHours::parse(w) {
+                Ok(q) => Some(q),
+                Err(e) => {
+                    warn!("Ignoring quiet hours window: {:#}", e);
+                    None
+                }
+            })
+            .collect();
+
+        let interval = (config.digest_interval_minutes > 0)
+            .then(|| chrono::Duration::minutes(config.digest_interval_minutes as i64));
+
+        Self {
+            windows,
+            interval,
+            entries: Vec::new(),
+            opened_at: None,
+            was_quiet: false,
+        }
+    }
+
+    /// Whether `now` falls inside any configured quiet-hours window
+    pub fn is_quiet(&self, now: DateTime<Local>) -> bool {
+        let time = now.time();
+        self.windows.iter().any(|w| w.contains(time))
+    }
+
+    /// Whether this anomaly should be held for the digest instead of sent now
+    pub fn should_buffer(&self, anomaly: &Anomaly, now: DateTime<Local>) -> bool {
+        anomaly.level == AlertLevel::Warning && (self.is_quiet(now) || self.interval.is_some())
+    }
+
+    /// Add a held-back notification to the digest, merging with an earlier
+    /// entry for the same alert
+    pub fn record(&mut self, anomaly: &Anomaly, now: DateTime<Local>) {
+        self.opened_at.get_or_insert(now);
+
+        match self.entry(anomaly) {
+            Some(entry) => {
+                entry.update(anomaly, now);
+                entry.count += 1;
+            }
+            None => self.entries.push(DigestEntry {
+                name: anomaly.name(),
+                anomaly_type: anomaly.anomaly_type,
+                level: anomaly.level,
+                peak_value: anomaly.value,
+                started_at: anomaly.started_at,
+                last_seen: now,
+                count: 1,
+            }),
+        }
+    }
+
+    /// A breach sample of an alert that was not notified this tick (e.g.
+    /// cooling down). Only extends an alert already in the digest.
+    pub fn sample(&mut self, anomaly: &Anomaly, now: DateTime<Local>) {
+        if let Some(entry) = self.entry(anomaly) {
+            entry.update(anomaly, now);
+        }
+    }
+
+    fn entry(&mut self, anomaly: &Anomaly) -> Option<&mut DigestEntry> {
+        let name = anomaly.name();
+        self.entries.iter_mut().find(|e| e.name == name)
+    }
+
+    /// Called once per tick. Returns a summary when the buffer is due:
+    /// at the end of a quiet-hours window, or when the digest interval elapses.
+    pub fn poll(&mut self, now: DateTime<Local>) -> Option<DigestSummary> {
+        let quiet = self.is_quiet(now);
+        let window_ended = self.was_quiet && !quiet;
+        self.was_quiet = quiet;
+
+        if quiet || self.entries.is_empty() {
+            return None;
+        }
+
+        let interval_elapsed = match (self.interval, self.opened_at) {
+            (Some(interval), Some(opened)) => now - opened >= interval,
+            (None, _) => true,
+            _ => false,
+        };
+
+        if window_ended || interval_elapsed {
+            self.take_summary()
+        } else {
+            None
+        }
+    }
+
+    fn take_summary(&mut self) -> Option<DigestSummary> {
+        if self.entries.is_empty() {
+            return None;
+        }
+
+        let entries = std::mem::take(&mut self.entries);
+        let since = self.opened_at.take().map(|t| t.format("%H:%M").to_string());
+
+        let message = match since {
+            Some(since) => format!("Digest: {} issue(s) since {}", entries.len(), since),
+            None => format!("Digest: {} issue(s)", entries.len()),
+        };
+
+        let details = entries
+            .iter()
+            .map(|e| {
+                format!(
+                    "{} ({}): peak {:.1}{} for {} ({}x)",
+                    e.name,
+                    e.level,
+                    e.peak_value,
+                    e.anomaly_type.unit(),
+                    format_duration(e.last_seen - e.started_at),
+                    e.count
+                )
+            })
+            .collect();
+
+        Some(DigestSummary { message, details })
+    }
+}
+
+impl DigestEntry {
+    fn update(&mut self, anomaly: &Anomaly, now: DateTime<Local>) {
+        self.level = self.level.max(anomaly.level);
+        self.peak_value = if anomaly.lower_is_worse {
+            self.peak_value.min(anomaly.value)
+        } else {
+            self.peak_value.max(anomaly.value)
+        };
+        self.started_at = self.started_at.min(anomaly.started_at);
+        self.last_seen = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, h: u32, m: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 1, day, h, m, 0).unwrap()
+    }
+
+    fn warning(anomaly_type: AnomalyType, value: f64, started_at: DateTime<Local>) -> Anomaly {
+        Anomaly {
+            anomaly_type,
+            level: AlertLevel::Warning,
+            message: String::new(),
+            details: vec![],
+            narration_message: String::new(),
+            value,
+            started_at,
+            custom_type: None,
+            lower_is_worse: false,
+        }
+    }
+
+    fn night_config() -> NotificationConfig {
+        NotificationConfig {
+            quiet_hours: vec![QuietHoursConfig { start: "22:00".into(), end: "07:00".into() }],
+            ..NotificationConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_quiet_hours_wrap_midnight() {
+        let q = QuietHours::parse(&QuietHoursConfig { start: "22:00".into(), end: "07:00".into() }).unwrap();
+        assert!(q.contains(NaiveTime::from_hms_opt(23, 30, 0).unwrap()));
+        assert!(q.contains(NaiveTime::from_hms_opt(3, 0, 0).unwrap()));
+        assert!(!q.contains(NaiveTime::from_hms_opt(7, 0, 0).unwrap()));
+        assert!(!q.contains(NaiveTime::from_hms_opt(12, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_digest_flushes_when_window_ends() {
+        let mut digest = Digest::new(&night_config());
+
+        let a = warning(AnomalyType::Memory, 82.0, at(15, 23, 0));
+        assert!(digest.should_buffer(&a, at(15, 23, 10)));
+        digest.record(&a, at(15, 23, 10));
+        digest.record(&warning(AnomalyType::Memory, 87.0, at(15, 23, 0)), at(16, 0, 30));
+        assert!(digest.poll(at(16, 0, 30)).is_none());
+
+        let summary = digest.poll(at(16, 7, 0)).expect("Digest should flush after quiet hours");
+        assert_eq!(summary.details.len(), 1);
+        assert!(summary.details[0].contains("peak 87.0%"));
+        assert!(digest.poll(at(16, 7, 1)).is_none());
+    }
+
+    #[test]
+    fn test_samples_extend_peak_and_duration() {
+        let mut digest = Digest::new(&night_config());
+        digest.record(&warning(AnomalyType::Memory, 82.0, at(15, 23, 0)), at(15, 23, 10));
+
+        // Cooling down: not notified again, but still breaching
+        digest.sample(&warning(AnomalyType::Memory, 91.0, at(15, 23, 0)), at(15, 23, 40));
+        digest.sample(&warning(AnomalyType::Memory, 85.0, at(15, 23, 0)), at(16, 0, 30));
+        digest.sample(&warning(AnomalyType::Swap, 70.0, at(15, 23, 0)), at(16, 0, 30));
+
+        let summary = digest.poll(at(16, 7, 0)).unwrap();
+        assert_eq!(summary.details, vec!["memory (WARNING): peak 91.0% for 1h 30m (1x)"]);
+    }
+
+    #[test]
+    fn test_peak_of_below_rule_is_lowest() {
+        let mut digest = Digest::new(&night_config());
+        let disk_free = |value| Anomaly {
+            custom_type: Some("disk_free".into()),
+            lower_is_worse: true,
+            ..warning(AnomalyType::Rule, value, at(15, 23, 0))
+        };
+        digest.record(&disk_free(8.0), at(15, 23, 10));
+        digest.sample(&disk_free(3.0), at(15, 23, 40));
+        digest.sample(&disk_free(6.0), at(16, 0, 30));
+
+        let summary = digest.poll(at(16, 7, 0)).unwrap();
+        assert_eq!(summary.details, vec!["disk_free (WARNING): peak 3.0 for 1h 30m (1x)"]);
+    }
+
+    #[test]
+    fn test_critical_is_never_buffered() {
+        let digest = Digest::new(&night_config());
+        let mut a = warning(AnomalyType::Swap, 90.0, at(15, 23, 0));
+        a.level = AlertLevel::Critical;
+        assert!(!digest.should_buffer(&a, at(15, 23, 5)));
+    }
+}
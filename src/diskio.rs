@@ -0,0 +1,172 @@
+//! Block device I/O throughput, latency and utilization (Linux)
+//!
+//! `DiskIoMonitor` reads the cumulative counters in /proc/diskstats on every
+//! tick and turns the difference to the previous tick into rates. Partitions
+//! (devices with a `partition` file in /sys/class/block/<name>), loop and ram
+//! devices are left out, so traffic is not counted twice. Elsewhere the file
+//! is missing and no devices are reported.
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// diskstats counts sectors of 512 bytes regardless of the device
+const SECTOR_BYTES: f64 = 512.0;
+
+/// I/O of one block device over the last tick
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiskIo {
+    pub device: String,
+    pub read_bytes_per_sec: f64,
+    pub write_bytes_per_sec: f64,
+    pub read_iops: f64,
+    pub write_iops: f64,
+    /// Share of the time the device had I/O in flight
+    pub utilization_percent: f64,
+    /// Average time per completed request, queueing included
+    pub await_ms: f64,
+}
+
+/// Cumulative counters of one /proc/diskstats line
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct DiskCounters {
+    reads: u64,
+    sectors_read: u64,
+    read_ms: u64,
+    writes: u64,
+    sectors_written: u64,
+    write_ms: u64,
+    io_ms: u64,
+}
+
+pub struct DiskIoMonitor {
+    proc_root: PathBuf,
+    sys_root: PathBuf,
+    previous: HashMap<String, DiskCounters>,
+    previous_at: Option<DateTime<Local>>,
+}
+
+impl DiskIoMonitor {
+    pub fn new() -> Self {
+        Self::with_roots("/proc", "/sys")
+    }
+
+    pub fn with_roots(proc_root: impl Into<PathBuf>, sys_root: impl Into<PathBuf>) -> Self {
+        Self { proc_root: proc_root.into(), sys_root: sys_root.into(), previous: HashMap::new(), previous_at: None }
+    }
+
+    /// Rates since the previous call; empty on the first call
+    pub fn collect(&mut self, now: DateTime<Local>) -> Vec<DiskIo> {
+        let Ok(text) = std::fs::read_to_string(self.proc_root.join("diskstats")) else { return Vec::new() };
+        let current: HashMap<String, DiskCounters> = parse_diskstats(&text)
+            .into_iter()
+            .filter(|(device, _)| self.is_whole_disk(device))
+            .collect();
+
+        let elapsed = self.previous_at.map(|at| (now - at).num_milliseconds() as f64 / 1000.0);
+        let mut devices: Vec<DiskIo> = match elapsed {
+            Some(secs) if secs > 0.0 => current
+                .iter()
+                .filter_map(|(device, now)| Some(rates(device, self.previous.get(device)?, now, secs)))
+                .collect(),
+            _ => Vec::new(),
+        };
+        devices.sort_by(|a, b| a.device.cmp(&b.device));
+
+        self.previous = current;
+        self.previous_at = Some(now);
+        devices
+    }
+
+    fn is_whole_disk(&self, device: &str) -> bool {
+        !device.starts_with("loop")
+            && !device.starts_with("ram")
+            && !self.sys_root.join("class/block").join(device).join("partition").exists()
+    }
+}
+
+impl Default for DiskIoMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn rates(device: &str, before: &DiskCounters, now: &DiskCounters, secs: f64) -> DiskIo {
+    // Counters wrap or reset when a device is re-attached; treat that as idle
+    let delta = |a: u64, b: u64| b.saturating_sub(a) as f64;
+    let ios = delta(before.reads, now.reads) + delta(before.writes, now.writes);
+    let busy_ms = delta(before.read_ms, now.read_ms) + delta(before.write_ms, now.write_ms);
+
+    DiskIo {
+        device: device.to_string(),
+        read_bytes_per_sec: delta(before.sectors_read, now.sectors_read) * SECTOR_BYTES / secs,
+        write_bytes_per_sec: delta(before.sectors_written, now.sectors_written) * SECTOR_BYTES / secs,
+        read_iops: delta(before.reads, now.reads) / secs,
+        write_iops: delta(before.writes, now.writes) / secs,
+        utilization_percent: (delta(before.io_ms, now.io_ms) / (secs * 1000.0) * 100.0).min(100.0),
+        await_ms: if ios > 0.0 { busy_ms / ios } else { 0.0 },
+    }
+}
+
+/// "major minor name reads merged sectors ms writes merged sectors ms in_flight io_ms ..."
+fn parse_diskstats(text: &str) -> Vec<(String, DiskCounters)> {
+    text.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let number = |i: usize| fields.get(i).and_then(|f| f.parse::<u64>().ok());
+            let counters = DiskCounters {
+                reads: number(3)?,
+                sectors_read: number(5)?,
+                read_ms: number(6)?,
+                writes: number(7)?,
+                sectors_written: number(9)?,
+                write_ms: number(10)?,
+                io_ms: number(12)?,
+            };
+            Some((fields[2].to_string(), counters))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_rates() {
+        let root = std::env::temp_dir().join(format!("sentinel-diskio-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("proc")).unwrap();
+        std::fs::create_dir_all(root.join("sys/class/block/nvme0n1p1")).unwrap();
+        std::fs::write(root.join("sys/class/block/nvme0n1p1/partition"), "1\n").unwrap();
+
+        let stats = |reads: u64, sectors: u64, read_ms: u64, io_ms: u64| {
+            format!(
+                "   7       0 loop0 10 0 80 1 0 0 0 0 0 1 1\n\
+                 259       0 nvme0n1 {r} 0 {s} {rm} 100 0 800 50 0 {io} 0 0 0 0 0\n\
+                 259       1 nvme0n1p1 {r} 0 {s} {rm} 100 0 800 50 0 {io} 0\n",
+                r = reads, s = sectors, rm = read_ms, io = io_ms
+            )
+        };
+        let mut monitor = DiskIoMonitor::with_roots(root.join("proc"), root.join("sys"));
+        let start = Local::now();
+
+        std::fs::write(root.join("proc/diskstats"), stats(1000, 8000, 500, 1000)).unwrap();
+        assert!(monitor.collect(start).is_empty(), "no rates without a previous sample");
+
+        // 10s later: 2000 reads of 8 sectors taking 6000ms, busy 9.5s of 10s
+        std::fs::write(root.join("proc/diskstats"), stats(3000, 24000, 6500, 10500)).unwrap();
+        let devices = monitor.collect(start + chrono::Duration::seconds(10));
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert_eq!(devices.len(), 1);
+        let disk = &devices[0];
+        assert_eq!(disk.device, "nvme0n1");
+        assert_eq!(disk.read_iops, 200.0);
+        assert_eq!(disk.read_bytes_per_sec, 16000.0 * 512.0 / 10.0);
+        assert_eq!(disk.write_bytes_per_sec, 0.0);
+        assert_eq!(disk.utilization_percent, 95.0);
+        assert_eq!(disk.await_ms, 3.0);
+    }
+}
@@ -0,0 +1,271 @@
+//! Escalation chains for unacknowledged Critical alerts
+//!
+//! Instead of re-sending a Critical every cooldown period, each Critical starts
+//! a chain of steps (popup, then narration, then webhook/email, ...). Steps fire
+//! at their configured offsets until the alert is acknowledged over IPC or the
+//! condition resolves.
+
+use std::collections::HashMap;
+use std::time::Instant;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::config::{EscalationChannel, EscalationConfig, EscalationStep};
+use crate::detector::{AlertLevel, Anomaly, AnomalyType};
+use crate::notifier::Notifier;
+
+/// An escalation in progress for one alert (`Anomaly::name`)
+struct Escalation {
+    anomaly: Anomaly,
+    started: Instant,
+    started_at: chrono::DateTime<chrono::Local>,
+    /// Index of the next step to fire
+    next_step: usize,
+    acknowledged: bool,
+}
+
+/// Escalation state as reported over IPC
+#[derive(Debug, Clone, Serialize)]
+pub struct EscalationStatus {
+    /// `Anomaly::name`: the type key, or the rule, plugin or script name
+    pub name: String,
+    pub anomaly_type: AnomalyType,
+    pub level: AlertLevel,
+    pub message: String,
+    pub started_at: chrono::DateTime<chrono::Local>,
+    pub steps_fired: usize,
+    pub steps_total: usize,
+    /// Seconds until the next step fires (None when acknowledged or exhausted)
+    pub next_step_in_seconds: Option<u64>,
+    pub acknowledged: bool,
+}
+
+pub struct EscalationManager {
+    enabled: bool,
+    steps: Vec<EscalationStep>,
+    active: HashMap<String, Escalation>,
+}
+
+impl EscalationManager {
+    pub fn new(config: &EscalationConfig) -> Self {
+        let mut steps = config.steps.clone();
+        steps.sort_by(|a, b| a.after_minutes.total_cmp(&b.after_minutes));
+
+        for step in &steps {
+            if matches!(step.channel, EscalationChannel::Webhook | EscalationChannel::Email) && step.target.is_none() {
+                warn!("Escalation step {:?} at {} min has no target and will be skipped", step.channel, step.after_minutes);
+            }
+        }
+
+        Self {
+            enabled: config.enabled && !steps.is_empty(),
+            steps,
+            active: HashMap::new(),
+        }
+    }
+
+    /// Whether Criticals should be routed through escalation instead of a plain send
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Start escalating a Critical anomaly. If an escalation for this alert is
+    /// already running (acknowledged or not), only its details are refreshed.
+    pub fn start(&mut self, anomaly: &Anomaly, notifier: &Notifier) {
+        let name = anomaly.name();
+        if let Some(existing) = self.active.get_mut(&name) {
+            existing.anomaly = anomaly.clone();
+            return;
+        }
+
+        info!("Starting escalation for {}: {}", name, anomaly.message);
+        self.active.insert(
+            name,
+            Escalation {
+                anomaly: anomaly.clone(),
+                started: Instant::now(),
+                started_at: chrono::Local::now(),
+                next_step: 0,
+                acknowledged: false,
+            },
+        );
+
+        // Fire any immediate steps right away
+        self.tick(notifier, |_| Some(AlertLevel::Critical));
+    }
+
+    /// Fire due steps and drop escalations whose condition has resolved.
+    /// `active_level` reports the detector's current level for an alert name.
+    pub fn tick(&mut self, notifier: &Notifier, active_level: impl Fn(&str) -> Option<AlertLevel>) {
+        self.active.retain(|name, _| {
+            let still_critical = active_level(name) == Some(AlertLevel::Critical);
+            if !still_critical {
+                info!("Escalation for {} ended: condition resolved", name);
+            }
+            still_critical
+        });
+
+        for escalation in self.active.values_mut() {
+            if escalation.acknowledged {
+                continue;
+            }
+
+            let elapsed_minutes = escalation.started.elapsed().as_secs_f64() / 60.0;
+            while let Some(step) = self.steps.get(escalation.next_step) {
+                if step.after_minutes > elapsed_minutes {
+                    break;
+                }
+                fire_step(step, &escalation.anomaly, notifier);
+                escalation.next_step += 1;
+            }
+        }
+    }
+
+    /// Acknowledge the escalations of one alert name, of one anomaly type
+    /// (every rule, say), or all of them when both are `None`.
+    /// Returns the names that were newly acknowledged.
+    pub fn acknowledge(&mut self, anomaly_type: Option<AnomalyType>, name: Option<&str>) -> Vec<String> {
+        let mut acknowledged = Vec::new();
+        for (n, escalation) in self.active.iter_mut() {
+            let unwanted = anomaly_type.is_some_and(|wanted| wanted != escalation.anomaly.anomaly_type)
+                || name.is_some_and(|wanted| wanted != n);
+            if unwanted || escalation.acknowledged {
+                continue;
+            }
+            escalation.acknowledged = true;
+            acknowledged.push(n.clone());
+            info!("Escalation for {} acknowledged after {} step(s)", n, escalation.next_step);
+        }
+        acknowledged
+    }
+
+    pub fn status(&self) -> Vec<EscalationStatus> {
+        self.active
+            .iter()
+            .map(|(name, e)| {
+                let next_step_in_seconds = if e.acknowledged {
+                    None
+                } else {
+                    self.steps.get(e.next_step).map(|step| {
+                        let due = step.after_minutes * 60.0 - e.started.elapsed().as_secs_f64();
+                        due.max(0.0) as u64
+                    })
+                };
+
+                EscalationStatus {
+                    name: name.clone(),
+                    anomaly_type: e.anomaly.anomaly_type,
+                    level: e.anomaly.level,
+                    message: e.anomaly.message.clone(),
+                    started_at: e.started_at,
+                    steps_fired: e.next_step,
+                    steps_total: self.steps.len(),
+                    next_step_in_seconds,
+                    acknowledged: e.acknowledged,
+                }
+            })
+            .collect()
+    }
+}
+
+fn fire_step(step: &EscalationStep, anomaly: &Anomaly, notifier: &Notifier) {
+    info!("Escalation step for {}: {:?} (+{} min)", anomaly.name(), step.channel, step.after_minutes);
+
+    let result = match (step.channel, step.target.as_deref()) {
+        (EscalationChannel::Popup, _) => notifier.show(anomaly.level, &anomaly.message, &anomaly.details),
+        (EscalationChannel::Narration, _) => {
+            notifier.narrate(anomaly);
+            Ok(())
+        }
+        (EscalationChannel::Webhook, Some(url)) => notifier.send_webhook(url, anomaly),
+        (EscalationChannel::Email, Some(to)) => notifier.send_email(to, anomaly),
+        (_, None) => Ok(()),
+    };
+
+    if let Err(e) = result {
+        warn!("Escalation step {:?} failed: {}", step.channel, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    /// Steps without a target fire nothing, so no popup, mail or speech in tests
+    fn manager(offsets: &[f64]) -> EscalationManager {
+        let steps = offsets
+            .iter()
+            .map(|&after_minutes| EscalationStep { after_minutes, channel: EscalationChannel::Email, target: None })
+            .collect();
+        EscalationManager::new(&EscalationConfig { enabled: true, steps })
+    }
+
+    fn critical(anomaly_type: AnomalyType, custom_type: Option<&str>) -> Anomaly {
+        Anomaly {
+            anomaly_type,
+            level: AlertLevel::Critical,
+            message: "critical".to_string(),
+            details: vec![],
+            narration_message: String::new(),
+            value: 0.0,
+            started_at: chrono::Local::now(),
+            custom_type: custom_type.map(str::to_string),
+            lower_is_worse: false,
+        }
+    }
+
+    fn steps_fired(manager: &EscalationManager, name: &str) -> usize {
+        manager.status().iter().find(|s| s.name == name).map_or(0, |s| s.steps_fired)
+    }
+
+    fn age(manager: &mut EscalationManager, name: &str, minutes: u64) {
+        let escalation = manager.active.get_mut(name).unwrap();
+        escalation.started = Instant::now() - std::time::Duration::from_secs(minutes * 60);
+    }
+
+    #[test]
+    fn test_chain_walk_per_alert() {
+        let notifier = Notifier::new(&Config::default());
+        let mut manager = manager(&[0.0, 5.0, 15.0]);
+        let critical_everywhere = |_: &str| Some(AlertLevel::Critical);
+
+        manager.start(&critical(AnomalyType::Rule, Some("disk_full")), &notifier);
+        manager.start(&critical(AnomalyType::Rule, Some("queue_depth")), &notifier);
+        assert_eq!(manager.status().len(), 2, "two rules escalate separately");
+        assert_eq!(steps_fired(&manager, "disk_full"), 1, "immediate step fires on start");
+
+        age(&mut manager, "disk_full", 6);
+        manager.tick(&notifier, critical_everywhere);
+        assert_eq!(steps_fired(&manager, "disk_full"), 2);
+        assert_eq!(steps_fired(&manager, "queue_depth"), 1);
+
+        age(&mut manager, "disk_full", 20);
+        manager.tick(&notifier, critical_everywhere);
+        let status = manager.status();
+        let disk_full = status.iter().find(|s| s.name == "disk_full").unwrap();
+        assert_eq!((disk_full.steps_fired, disk_full.next_step_in_seconds), (3, None));
+    }
+
+    #[test]
+    fn test_acknowledge_and_recovery() {
+        let notifier = Notifier::new(&Config::default());
+        let mut manager = manager(&[0.0, 5.0]);
+        manager.start(&critical(AnomalyType::Memory, None), &notifier);
+        manager.start(&critical(AnomalyType::Plugin, Some("backup_freshness")), &notifier);
+
+        // An acknowledged chain stops walking but stays until the condition clears
+        assert_eq!(manager.acknowledge(None, Some("memory")), vec!["memory".to_string()]);
+        assert!(manager.acknowledge(Some(AnomalyType::Memory), None).is_empty(), "already acknowledged");
+        age(&mut manager, "memory", 10);
+        manager.tick(&notifier, |_| Some(AlertLevel::Critical));
+        assert_eq!(steps_fired(&manager, "memory"), 1);
+        assert_eq!(manager.status().len(), 2);
+
+        // Dropping to Warning or clearing ends the chain
+        manager.tick(&notifier, |name| (name == "memory").then_some(AlertLevel::Critical).or(Some(AlertLevel::Warning)));
+        assert_eq!(manager.status().iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), vec!["memory"]);
+        manager.tick(&notifier, |_| None);
+        assert!(manager.status().is_empty());
+    }
+}
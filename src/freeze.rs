@@ -0,0 +1,305 @@
+//! Freezing processes as a non-destructive alternative to killing them
+//!
+//! On Linux, when the target's cgroup v2 contains nothing but the processes
+//! being frozen, the whole cgroup is frozen through `cgroup.freeze`, which the
+//! processes cannot observe or resist. Otherwise every PID gets SIGSTOP, and
+//! SIGCONT on thaw. Each freeze carries an auto-thaw deadline, and the daemon
+//! reports frozen processes in its metrics so the UI keeps them visible.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use crate::actions::{send_signal, still_running};
+use crate::cgroup::CgroupFs;
+
+/// How a process was frozen, and therefore how to thaw it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FreezeMethod {
+    /// The process' cgroup v2 directory; `cgroup.freeze` was set to 1
+    Cgroup { path: String },
+    /// SIGSTOP was sent to every PID
+    Signal,
+}
+
+/// A process (and possibly its descendants) the daemon has frozen
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrozenProcess {
+    pub pid: u32,
+    pub name: String,
+    /// Every frozen PID, `pid` first
+    pub pids: Vec<u32>,
+    pub memory_bytes: u64,
+    pub method: FreezeMethod,
+    pub requested_by: String,
+    pub frozen_at: DateTime<Local>,
+    pub thaw_at: DateTime<Local>,
+}
+
+/// Sends a signal by name to a PID
+type Signaller = fn(u32, &str) -> Result<()>;
+
+/// Registry of frozen processes; clones share the same registry
+#[derive(Debug, Clone)]
+pub struct Freezer {
+    cgroups: CgroupFs,
+    frozen: Arc<Mutex<HashMap<u32, FrozenProcess>>>,
+    signal: Signaller,
+}
+
+impl Freezer {
+    pub fn new(cgroups: CgroupFs) -> Self {
+        Self {
+            cgroups,
+            frozen: Arc::new(Mutex::new(HashMap::new())),
+            signal: send_signal,
+        }
+    }
+
+    /// Freeze `pids` (the root process first) until thawed or `thaw_after` passes
+    pub fn freeze(
+        &self,
+        name: &str,
+        pids: &[u32],
+        memory_bytes: u64,
+        thaw_after: Duration,
+        requested_by: &str,
+    ) -> Result<FrozenProcess> {
+        let &pid = pids.first().context("Nothing to freeze")?;
+        if self.is_frozen(pid) {
+            anyhow::bail!("PID {} is already frozen", pid);
+        }
+
+        let (method, pids) = match self.exclusive_cgroup(pids) {
+            Some(dir) => {
+                write_freeze(&dir, true)?;
+                (FreezeMethod::Cgroup { path: dir.to_string_lossy().to_string() }, pids.to_vec())
+            }
+            None => (FreezeMethod::Signal, self.stop_all(pids)?),
+        };
+
+        let now = Local::now();
+        let frozen = FrozenProcess {
+            pid,
+            name: name.to_string(),
+            pids,
+            memory_bytes,
+            method,
+            requested_by: requested_by.to_string(),
+            frozen_at: now,
+            thaw_at: now + chrono::Duration::from_std(thaw_after).unwrap_or(chrono::Duration::MAX),
+        };
+        info!("Froze {} (PID {}) via {:?} until {}", name, pid, frozen.method, frozen.thaw_at.format("%H:%M:%S"));
+
+        self.frozen.lock().unwrap().insert(pid, frozen.clone());
+        Ok(frozen)
+    }
+
+    /// SIGSTOP every PID, skipping those that have exited meanwhile. On any
+    /// other failure the PIDs stopped so far are resumed. Returns the stopped PIDs.
+    fn stop_all(&self, pids: &[u32]) -> Result<Vec<u32>> {
+        let mut stopped = Vec::new();
+        for &p in pids {
+            match (self.signal)(p, "STOP") {
+                Ok(()) => stopped.push(p),
+                Err(_) if still_running(&[p]).is_empty() => debug!("PID {} exited before it could be stopped", p),
+                Err(e) => {
+                    for &s in &stopped {
+                        if let Err(e) = (self.signal)(s, "CONT") {
+                            warn!("Failed to resume PID {} after a failed freeze: {}", s, e);
+                        }
+                    }
+                    return Err(e.context(format!("Failed to stop PID {}", p)));
+                }
+            }
+        }
+        if !stopped.contains(&pids[0]) {
+            anyhow::bail!("PID {} has exited", pids[0]);
+        }
+        Ok(stopped)
+    }
+
+    /// Thaw a process this daemon froze. Returns `None` if `pid` is not in the
+    /// registry. The process stays registered when thawing fails.
+    pub fn thaw(&self, pid: u32) -> Result<Option<FrozenProcess>> {
+        let Some(frozen) = self.frozen.lock().unwrap().get(&pid).cloned() else {
+            return Ok(None);
+        };
+
+        match &frozen.method {
+            FreezeMethod::Cgroup { path } => {
+                let dir = Path::new(path);
+                // The cgroup disappears when its last process exits
+                if dir.exists() {
+                    write_freeze(dir, false)?;
+                }
+            }
+            FreezeMethod::Signal => {
+                for &p in &still_running(&frozen.pids) {
+                    if let Err(e) = (self.signal)(p, "CONT") {
+                        if !still_running(&[p]).is_empty() {
+                            return Err(e.context(format!("Failed to resume PID {}", p)));
+                        }
+                    }
+                }
+            }
+        }
+
+        self.frozen.lock().unwrap().remove(&pid);
+        info!("Thawed {} (PID {})", frozen.name, frozen.pid);
+        Ok(Some(frozen))
+    }
+
+    /// Move the auto-thaw deadline of `pid` to `delay` from now
+    pub fn postpone(&self, pid: u32, delay: Duration) {
+        if let Some(frozen) = self.frozen.lock().unwrap().get_mut(&pid) {
+            frozen.thaw_at = Local::now() + chrono::Duration::from_std(delay).unwrap_or(chrono::Duration::MAX);
+        }
+    }
+
+    pub fn is_frozen(&self, pid: u32) -> bool {
+        self.frozen.lock().unwrap().contains_key(&pid)
+    }
+
+    /// Currently frozen processes, oldest first
+    pub fn frozen(&self) -> Vec<FrozenProcess> {
+        let mut list: Vec<FrozenProcess> = self.frozen.lock().unwrap().values().cloned().collect();
+        list.sort_by_key(|f| f.frozen_at);
+        list
+    }
+
+    /// The cgroup v2 directory of `pids[0]`, if it supports freezing and
+    /// contains no process outside `pids`; freezing it then affects only them
+    fn exclusive_cgroup(&self, pids: &[u32]) -> Option<PathBuf> {
+        let path = self.cgroups.cgroup_of(pids[0])?;
+        if path.is_empty() {
+            // Never freeze the root cgroup
+            return None;
+        }
+
+        let dir = self.cgroups.dir(&path);
+        if !dir.join("cgroup.freeze").exists() {
+            return None;
+        }
+
+        let members = self.cgroups.procs(&path)?;
+        let wanted: HashSet<u32> = pids.iter().copied().collect();
+
+        if !members.is_empty() && members.is_subset(&wanted) {
+            Some(dir)
+        } else {
+            debug!("cgroup {:?} is shared with other processes; using SIGSTOP", dir);
+            None
+        }
+    }
+}
+
+fn write_freeze(dir: &Path, frozen: bool) -> Result<()> {
+    let file = dir.join("cgroup.freeze");
+    std::fs::write(&file, if frozen { "1" } else { "0" })
+        .with_context(|| format!("Failed to write {:?}", file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exclusive_cgroup() {
+        let root = std::env::temp_dir().join(format!("sentinel-freeze-{}", std::process::id()));
+        let proc_root = root.join("proc");
+        let cgroup_root = root.join("cgroup");
+        let app = cgroup_root.join("user.slice/app-ghostty.scope");
+        std::fs::create_dir_all(proc_root.join("100")).unwrap();
+        std::fs::create_dir_all(proc_root.join("200")).unwrap();
+        std::fs::create_dir_all(&app).unwrap();
+        std::fs::write(proc_root.join("100/cgroup"), "0::/user.slice/app-ghostty.scope\n").unwrap();
+        std::fs::write(proc_root.join("200/cgroup"), "0::/\n").unwrap();
+        std::fs::write(app.join("cgroup.freeze"), "0").unwrap();
+        std::fs::write(app.join("cgroup.procs"), "100\n101\n").unwrap();
+
+        let freezer = Freezer::new(CgroupFs::with_roots(&proc_root, &cgroup_root));
+        assert_eq!(freezer.exclusive_cgroup(&[100, 101]), Some(app.clone()));
+        // PID 101 would be frozen too without being asked for
+        assert_eq!(freezer.exclusive_cgroup(&[100]), None);
+        assert_eq!(freezer.exclusive_cgroup(&[200]), None);
+
+        freezer.freeze("ghostty", &[100, 101], 0, Duration::from_secs(60), "test").unwrap();
+        assert_eq!(std::fs::read_to_string(app.join("cgroup.freeze")).unwrap(), "1");
+        assert!(freezer.is_frozen(100));
+
+        let thawed = freezer.thaw(100).unwrap().unwrap();
+        assert_eq!(thawed.method, FreezeMethod::Cgroup { path: app.to_string_lossy().to_string() });
+        assert_eq!(std::fs::read_to_string(app.join("cgroup.freeze")).unwrap(), "0");
+        assert!(freezer.frozen().is_empty());
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    thread_local! {
+        static SIGNALS: std::cell::RefCell<Vec<(u32, String)>> = const { std::cell::RefCell::new(Vec::new()) };
+    }
+
+    /// Records signals instead of sending them; signals to this test process
+    /// fail as a live process refusing them would. PIDs above the kernel's
+    /// pid_max limit never exist, so signals to them fail as for exited ones.
+    fn fake_signal(pid: u32, signal: &str) -> Result<()> {
+        SIGNALS.with(|s| s.borrow_mut().push((pid, signal.to_string())));
+        if pid == std::process::id() || pid > 4_194_304 {
+            anyhow::bail!("kill: ({}) - failed", pid);
+        }
+        Ok(())
+    }
+
+    fn signals() -> Vec<(u32, String)> {
+        SIGNALS.with(|s| s.borrow_mut().drain(..).collect())
+    }
+
+    fn signal_freezer() -> Freezer {
+        Freezer { signal: fake_signal, ..Freezer::new(CgroupFs::with_roots("/nonexistent/proc", "/nonexistent/cgroup")) }
+    }
+
+    #[test]
+    fn test_partial_freeze() {
+        let freezer = signal_freezer();
+        let (exited, refusing) = (5_000_001, std::process::id());
+
+        // A descendant that exited is skipped and left out of the registry
+        let frozen = freezer.freeze("app", &[10, exited, 11], 0, Duration::from_secs(60), "test").unwrap();
+        assert_eq!(frozen.pids, vec![10, 11]);
+        assert_eq!(signals().len(), 3);
+
+        // Any other failure resumes what was already stopped
+        assert!(freezer.freeze("other", &[20, 21, refusing, 22], 0, Duration::from_secs(60), "test").is_err());
+        let sent = signals();
+        assert_eq!(sent[3..], [(20, "CONT".to_string()), (21, "CONT".to_string())]);
+        assert!(!freezer.is_frozen(20));
+    }
+
+    #[test]
+    fn test_failed_thaw_keeps_registry() {
+        let freezer = signal_freezer();
+        let refusing = std::process::id();
+        let fake = FrozenProcess {
+            pid: refusing,
+            name: "sentinel-test".to_string(),
+            pids: vec![refusing],
+            memory_bytes: 0,
+            method: FreezeMethod::Signal,
+            requested_by: "test".to_string(),
+            frozen_at: Local::now(),
+            thaw_at: Local::now(),
+        };
+        freezer.frozen.lock().unwrap().insert(refusing, fake);
+
+        assert!(freezer.thaw(refusing).is_err());
+        assert!(freezer.is_frozen(refusing), "a failed thaw must stay tracked for retry");
+        assert_eq!(signals(), vec![(refusing, "CONT".to_string())]);
+    }
+}
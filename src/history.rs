@@ -0,0 +1,127 @@
+//! Recent alert history
+//!
+//! A bounded, in-memory record of every anomaly the daemon delivered
+//! (threshold alerts and kernel events alike), newest last, plus bounded
+//! numeric series such as plugin performance data. Clients read both with the
+//! `history` IPC command.
+
+use std::collections::{BTreeMap, VecDeque};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::detector::{AlertLevel, Anomaly, AnomalyType};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryEntry {
+    pub timestamp: DateTime<Local>,
+    pub anomaly_type: AnomalyType,
+    pub level: AlertLevel,
+    pub message: String,
+    pub details: Vec<String>,
+    pub value: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_type: Option<String>,
+}
+
+/// One point of a metric series
+#[derive(Debug, Clone, Serialize)]
+pub struct Sample {
+    pub timestamp: DateTime<Local>,
+    pub value: f64,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub unit: String,
+}
+
+pub struct History {
+    entries: VecDeque<HistoryEntry>,
+    max_entries: usize,
+    series: BTreeMap<String, VecDeque<Sample>>,
+    max_samples: usize,
+}
+
+impl History {
+    pub fn new(max_entries: usize, max_samples: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            max_entries,
+            series: BTreeMap::new(),
+            max_samples,
+        }
+    }
+
+    pub fn record(&mut self, anomaly: &Anomaly) {
+        self.entries.push_back(HistoryEntry {
+            timestamp: Local::now(),
+            anomaly_type: anomaly.anomaly_type,
+            level: anomaly.level,
+            message: anomaly.message.clone(),
+            details: anomaly.details.clone(),
+            value: anomaly.value,
+            custom_type: anomaly.custom_type.clone(),
+        });
+        while self.entries.len() > self.max_entries {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Up to `limit` most recent entries, newest first
+    pub fn recent(&self, limit: usize) -> Vec<HistoryEntry> {
+        self.entries.iter().rev().take(limit).cloned().collect()
+    }
+
+    /// Append a sample to a named series, e.g. "backup_freshness.age"
+    pub fn record_sample(&mut self, series: &str, sample: Sample) {
+        let samples = self.series.entry(series.to_string()).or_default();
+        samples.push_back(sample);
+        while samples.len() > self.max_samples {
+            samples.pop_front();
+        }
+    }
+
+    pub fn series_names(&self) -> Vec<String> {
+        self.series.keys().cloned().collect()
+    }
+
+    /// Up to `limit` most recent samples of a series, oldest first
+    pub fn samples(&self, series: &str, limit: usize) -> Vec<Sample> {
+        let Some(samples) = self.series.get(series) else { return Vec::new() };
+        samples.iter().skip(samples.len().saturating_sub(limit)).cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anomaly(message: &str) -> Anomaly {
+        Anomaly {
+            anomaly_type: AnomalyType::Crash,
+            level: AlertLevel::Warning,
+            message: message.to_string(),
+            details: vec![],
+            narration_message: String::new(),
+            value: 0.0,
+            started_at: Local::now(),
+            custom_type: None,
+            lower_is_worse: false,
+        }
+    }
+
+    #[test]
+    fn test_bounded_newest_first() {
+        let mut history = History::new(2, 2);
+        for m in ["a", "b", "c"] {
+            history.record(&anomaly(m));
+        }
+        let recent: Vec<String> = history.recent(10).into_iter().map(|e| e.message).collect();
+        assert_eq!(recent, vec!["c", "b"]);
+        assert_eq!(history.recent(1).len(), 1);
+
+        for value in [1.0, 2.0, 3.0] {
+            history.record_sample("backup.age", Sample { timestamp: Local::now(), value, unit: "s".to_string() });
+        }
+        let values: Vec<f64> = history.samples("backup.age", 10).iter().map(|s| s.value).collect();
+        assert_eq!(values, vec![2.0, 3.0]);
+        assert!(history.samples("missing", 10).is_empty());
+    }
+}
@@ -0,0 +1,59 @@
+//! Alert inhibition: "A suppresses B while A is raised"
+//!
+//! The graph is built from `[[inhibitions]]` and the rules' `inhibited_by`
+//! lists (the built-in memory_growth rule is inhibited by memory). Names are
+//! those of `Anomaly::name`: rule names, plugin and script alert types, or
+//! built-in type names such as "process_missing". A source counts as raised
+//! when it is detected this tick, even while it is damped, cooling down or
+//! inhibited itself.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::config::InhibitionConfig;
+
+#[derive(Debug, Default)]
+pub struct InhibitionGraph {
+    /// Target -> the sources that suppress it
+    sources: HashMap<String, Vec<String>>,
+}
+
+impl InhibitionGraph {
+    pub fn new<'a>(configured: &'a [InhibitionConfig], edges: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let mut graph = Self::default();
+        let configured = configured
+            .iter()
+            .flat_map(|i| i.targets.iter().map(move |target| (i.source.as_str(), target.as_str())));
+        for (source, target) in configured.chain(edges) {
+            let sources = graph.sources.entry(target.to_string()).or_default();
+            if source != target && !sources.iter().any(|s| s == source) {
+                sources.push(source.to_string());
+            }
+        }
+        graph
+    }
+
+    /// The first raised source that suppresses `target`
+    pub fn inhibitor(&self, target: &str, raised: &HashSet<String>) -> Option<&str> {
+        self.sources.get(target)?.iter().find(|s| raised.contains(*s)).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inhibitor() {
+        let configured = vec![InhibitionConfig {
+            source: "process_missing".to_string(),
+            targets: vec!["tts_health".to_string(), "process_missing".to_string()],
+        }];
+        let graph = InhibitionGraph::new(&configured, [("memory", "memory_growth")]);
+        let raised: HashSet<String> = ["memory", "process_missing"].iter().map(|s| s.to_string()).collect();
+
+        assert_eq!(graph.inhibitor("memory_growth", &raised), Some("memory"));
+        assert_eq!(graph.inhibitor("tts_health", &raised), Some("process_missing"));
+        assert_eq!(graph.inhibitor("process_missing", &raised), None, "an alert never inhibits itself");
+        assert_eq!(graph.inhibitor("swap", &raised), None);
+    }
+}
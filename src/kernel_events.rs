@@ -0,0 +1,357 @@
+//! Kernel OOM-kill and crash events
+//!
+//! A background thread follows `/dev/kmsg` (Linux) or tails a configurable log
+//! file, such as /var/log/kern.log, and turns OOM-killer and segfault/trap lines
+//! into `KernelEvent`s for the main loop. Group-level OOM kills seen through
+//! cgroup `memory.events` are reported by `CgroupMonitor` and merged in main.
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use crate::config::{expand_tilde, KernelEventsConfig};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KernelEventKind {
+    /// The OOM killer (global or cgroup) killed a process
+    OomKill,
+    /// A process died from a segfault, general protection fault or other trap
+    Crash,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KernelEvent {
+    pub kind: KernelEventKind,
+    pub timestamp: DateTime<Local>,
+    /// Victim process name (kernel comm), if known
+    pub process: Option<String>,
+    pub pid: Option<u32>,
+    /// Resident anonymous memory of the victim at the time of an OOM kill
+    pub anon_rss_kb: Option<u64>,
+    /// cgroup the OOM kill happened in, if it was a cgroup OOM
+    pub cgroup: Option<String>,
+    /// What happened, e.g. "segfault at 0 ip 00007f... error 4 in libc.so.6"
+    pub detail: String,
+}
+
+impl KernelEvent {
+    fn new(kind: KernelEventKind, process: Option<String>, pid: Option<u32>, detail: &str) -> Self {
+        Self {
+            kind,
+            timestamp: Local::now(),
+            process,
+            pid,
+            anon_rss_kb: None,
+            cgroup: None,
+            detail: detail.trim().to_string(),
+        }
+    }
+
+    /// An OOM kill known only from a cgroup's memory.events counter
+    pub fn cgroup_oom_kill(cgroup: &str, kills: u64) -> Self {
+        let mut event = Self::new(KernelEventKind::OomKill, None, None, &format!("{} OOM kill(s) in cgroup {}", kills, cgroup));
+        event.cgroup = Some(cgroup.to_string());
+        event
+    }
+
+    /// Victim name for messages: "chrome (PID 1234)", or the cgroup when unknown
+    pub fn victim(&self) -> String {
+        match (&self.process, self.pid, &self.cgroup) {
+            (Some(name), Some(pid), _) => format!("{} (PID {})", name, pid),
+            (Some(name), None, _) => name.clone(),
+            (None, _, Some(cgroup)) => cgroup.rsplit('/').next().unwrap_or(cgroup).to_string(),
+            _ => "unknown process".to_string(),
+        }
+    }
+}
+
+/// Turns kernel log lines into events. Stateful because the OOM killer prints
+/// the victim's cgroup on the line before the "Killed process" line.
+#[derive(Debug, Default)]
+pub struct KernelLogParser {
+    /// (pid, memcg) from the last "oom-kill:" summary line
+    pending_memcg: Option<(u32, String)>,
+}
+
+impl KernelLogParser {
+    pub fn feed(&mut self, line: &str) -> Option<KernelEvent> {
+        let line = strip_log_prefix(line);
+
+        if let Some(idx) = line.find("oom-kill:") {
+            let fields = &line[idx + "oom-kill:".len()..];
+            let field = |key: &str| {
+                fields
+                    .split(',')
+                    .find_map(|f| f.trim().strip_prefix(key).map(|v| v.to_string()))
+            };
+            if let (Some(pid), Some(memcg)) = (field("pid=").and_then(|p| p.parse().ok()), field("task_memcg=")) {
+                self.pending_memcg = Some((pid, memcg));
+            }
+            return None;
+        }
+
+        if let Some(idx) = line.find("Killed process ") {
+            let rest = &line[idx + "Killed process ".len()..];
+            let pid: Option<u32> = rest.split_whitespace().next().and_then(|p| p.parse().ok());
+            let process = between(rest, '(', ')');
+            let mut event = KernelEvent::new(KernelEventKind::OomKill, process, pid, &line[idx..]);
+            event.anon_rss_kb = rest
+                .split(',')
+                .find_map(|f| f.trim().strip_prefix("anon-rss:"))
+                .and_then(|v| v.trim_end_matches("kB").parse().ok());
+            if let Some((memcg_pid, memcg)) = self.pending_memcg.take() {
+                if Some(memcg_pid) == pid && memcg != "/" {
+                    event.cgroup = Some(memcg.trim_start_matches('/').to_string());
+                }
+            }
+            return Some(event);
+        }
+
+        // "chrome[1234]: segfault at 0 ip 00007f12 sp 00007ffd error 4 in libc.so.6[7f00+1000]"
+        if let Some(idx) = line.find(": segfault at ") {
+            let (process, pid) = comm_and_pid(&line[..idx])?;
+            return Some(KernelEvent::new(KernelEventKind::Crash, Some(process), Some(pid), &line[idx + 2..]));
+        }
+
+        // "traps: foo[123] general protection fault ip:4011d6 sp:7ffd error:0 in foo[401000+1000]"
+        if let Some(rest) = line.strip_prefix("traps: ") {
+            let end = rest.find("] ")? + 1;
+            let (process, pid) = comm_and_pid(&rest[..end])?;
+            return Some(KernelEvent::new(KernelEventKind::Crash, Some(process), Some(pid), &rest[end..]));
+        }
+
+        None
+    }
+}
+
+/// Drop a syslog "... kernel: " prefix and a "[12345.678] " kernel timestamp
+fn strip_log_prefix(line: &str) -> &str {
+    let line = line.find("kernel: ").map_or(line, |i| &line[i + "kernel: ".len()..]);
+    let trimmed = line.trim_start();
+    match trimmed.strip_prefix('[').and_then(|rest| rest.split_once("] ")) {
+        Some((stamp, rest)) if stamp.trim().parse::<f64>().is_ok() => rest,
+        _ => trimmed,
+    }
+}
+
+/// "name[pid]" or "name[pid]:" -> (name, pid); comm may contain spaces
+fn comm_and_pid(token: &str) -> Option<(String, u32)> {
+    let token = token.trim_end_matches(':');
+    let (name, rest) = token.rsplit_once('[')?;
+    let pid = rest.strip_suffix(']')?.parse().ok()?;
+    Some((name.to_string(), pid))
+}
+
+fn between(text: &str, open: char, close: char) -> Option<String> {
+    let start = text.find(open)? + 1;
+    let end = start + text[start..].find(close)?;
+    Some(text[start..end].to_string())
+}
+
+/// How long the cgroups of logged OOM victims are remembered
+const OOM_MEMORY: Duration = Duration::from_secs(600);
+
+/// Handle to the reader thread
+pub struct KernelEventSource {
+    /// When and in which cgroup ("" if unknown) the log reported OOM kills
+    recent_ooms: Arc<Mutex<Vec<(Instant, String)>>>,
+}
+
+impl KernelEventSource {
+    /// Start following the configured source. Returns None when disabled or
+    /// when no source is available (e.g. /dev/kmsg on macOS without a log file).
+    pub fn spawn(config: &KernelEventsConfig) -> Option<(Self, mpsc::Receiver<KernelEvent>)> {
+        if !config.enabled {
+            return None;
+        }
+
+        let source = match &config.log_file {
+            Some(path) => Source::LogFile(expand_tilde(path)),
+            None if Path::new("/dev/kmsg").exists() => Source::Kmsg,
+            None => {
+                info!("Kernel event detection disabled: no /dev/kmsg and no [kernel_events] log_file");
+                return None;
+            }
+        };
+
+        let (tx, rx) = mpsc::channel(64);
+        let recent_ooms: Arc<Mutex<Vec<(Instant, String)>>> = Arc::new(Mutex::new(Vec::new()));
+        let seen = recent_ooms.clone();
+
+        std::thread::spawn(move || {
+            let mut parser = KernelLogParser::default();
+            let mut emit = |line: &str| {
+                let Some(event) = parser.feed(line) else { return true };
+                debug!("Kernel event: {:?}", event);
+                if event.kind == KernelEventKind::OomKill {
+                    let mut seen = seen.lock().unwrap();
+                    seen.retain(|(t, _)| t.elapsed() <= OOM_MEMORY);
+                    seen.push((Instant::now(), event.cgroup.clone().unwrap_or_default()));
+                }
+                tx.blocking_send(event).is_ok()
+            };
+
+            let result = match &source {
+                Source::Kmsg => follow_kmsg(&mut emit),
+                Source::LogFile(path) => tail_file(path, &mut emit),
+            };
+            if let Err(e) = result {
+                warn!("Kernel event source {:?} stopped: {}", source, e);
+            }
+        });
+
+        Some((Self { recent_ooms }, rx))
+    }
+
+    /// Whether the log source recently reported an OOM kill of a process in
+    /// `cgroup` or below it; used to avoid announcing the same kill again
+    /// from that group's memory.events
+    pub fn saw_oom_in(&self, cgroup: &str, window: Duration) -> bool {
+        self.recent_ooms
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(t, victim)| t.elapsed() <= window && in_cgroup(victim, cgroup))
+    }
+}
+
+/// Whether `path` is `group` or one of its descendants (memory.events is
+/// hierarchical)
+fn in_cgroup(path: &str, group: &str) -> bool {
+    let group = group.trim_matches('/');
+    group.is_empty() || path.strip_prefix(group).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+#[derive(Debug)]
+enum Source {
+    Kmsg,
+    LogFile(PathBuf),
+}
+
+/// Read /dev/kmsg from the current end; every read() returns one record,
+/// "prio,seq,usec,flags;message" followed by optional " KEY=value" lines
+fn follow_kmsg(emit: &mut impl FnMut(&str) -> bool) -> std::io::Result<()> {
+    let mut file = File::open("/dev/kmsg")?;
+    file.seek(SeekFrom::End(0))?;
+    info!("Following kernel messages in /dev/kmsg");
+
+    let mut buf = vec![0u8; 8192];
+    loop {
+        match file.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => {
+                let record = String::from_utf8_lossy(&buf[..n]);
+                let message = record.split_once(';').map_or(&*record, |(_, m)| m);
+                let first_line = message.lines().next().unwrap_or("");
+                if !emit(first_line) {
+                    return Ok(());
+                }
+            }
+            // Records were overwritten before we read them; carry on with the next one
+            Err(e) if e.raw_os_error() == Some(32) => continue,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Follow a log file like `tail -F`, reopening it after rotation or truncation
+fn tail_file(path: &Path, emit: &mut impl FnMut(&str) -> bool) -> std::io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut position = reader.seek(SeekFrom::End(0))?;
+    let mut inode = reader.get_ref().metadata()?.ino();
+    info!("Following kernel messages in {:?}", path);
+
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let n = reader.read_line(&mut line)?;
+        if n > 0 && line.ends_with('\n') {
+            position += n as u64;
+            if !emit(line.trim_end()) {
+                return Ok(());
+            }
+            continue;
+        }
+        if n > 0 {
+            // Partial line; re-read it once the writer finishes it
+            reader.seek(SeekFrom::Start(position))?;
+        }
+
+        std::thread::sleep(Duration::from_secs(1));
+        match std::fs::metadata(path) {
+            Ok(meta) if meta.ino() != inode || meta.len() < position => {
+                debug!("{:?} was rotated or truncated; reopening", path);
+                reader = BufReader::new(File::open(path)?);
+                position = 0;
+                inode = meta.ino();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cgroup_oom_kill() {
+        let mut parser = KernelLogParser::default();
+        let summary = "oom-kill:constraint=CONSTRAINT_MEMCG,nodemask=(null),cpuset=/,mems_allowed=0,oom_memcg=/user.slice/app-ghostty.scope,task_memcg=/user.slice/app-ghostty.scope,task=ghostty,pid=4242,uid=1000";
+        assert!(parser.feed(summary).is_none());
+
+        let event = parser
+            .feed("Memory cgroup out of memory: Killed process 4242 (ghostty) total-vm:71234560kB, anon-rss:69800000kB, file-rss:1024kB, shmem-rss:0kB, UID:1000 pgtables:140000kB oom_score_adj:0")
+            .unwrap();
+        assert_eq!(event.kind, KernelEventKind::OomKill);
+        assert_eq!(event.process.as_deref(), Some("ghostty"));
+        assert_eq!(event.pid, Some(4242));
+        assert_eq!(event.anon_rss_kb, Some(69800000));
+        assert_eq!(event.cgroup.as_deref(), Some("user.slice/app-ghostty.scope"));
+        assert_eq!(event.victim(), "ghostty (PID 4242)");
+    }
+
+    #[test]
+    fn test_parse_crashes() {
+        let mut parser = KernelLogParser::default();
+
+        // Syslog prefix as in /var/log/kern.log
+        let event = parser
+            .feed("Jan 15 10:25:01 host kernel: [12345.678] Web Content[987]: segfault at 0 ip 00007f12 sp 00007ffd error 4 in libxul.so[7f00+1000]")
+            .unwrap();
+        assert_eq!(event.kind, KernelEventKind::Crash);
+        assert_eq!(event.process.as_deref(), Some("Web Content"));
+        assert_eq!(event.pid, Some(987));
+        assert!(event.detail.starts_with("segfault at 0"));
+
+        let event = parser
+            .feed("traps: node[5555] general protection fault ip:4011d6 sp:7ffd error:0 in node[401000+1000]")
+            .unwrap();
+        assert_eq!(event.process.as_deref(), Some("node"));
+        assert!(event.detail.starts_with("general protection fault"));
+
+        assert!(parser.feed("usb 1-1: new high-speed USB device number 2").is_none());
+    }
+
+    #[test]
+    fn test_log_oom_matches_victim_cgroup() {
+        let source = KernelEventSource {
+            recent_ooms: Arc::new(Mutex::new(vec![(Instant::now(), "user.slice/user-1000.slice/app-ghostty.scope".to_string())])),
+        };
+        let window = Duration::from_secs(60);
+        assert!(source.saw_oom_in("user.slice/user-1000.slice/app-ghostty.scope", window));
+        assert!(source.saw_oom_in("user.slice", window), "memory.events counts descendants");
+        assert!(!source.saw_oom_in("system.slice/postgresql.service", window));
+        assert!(!source.saw_oom_in("user.slice/user-1000.slice/app-ghost", window));
+    }
+}
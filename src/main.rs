@@ -3,12 +3,33 @@
 //! Monitors memory, swap, load average, and per-process memory usage.
 //! Sends notifications via Hammerspoon when anomalies are detected.
 
+mod actions;
+mod autonomous;
+mod cgroup;
 mod config;
 mod detector;
+mod digest;
+mod diskio;
+mod escalation;
+mod freeze;
+mod handles;
+mod history;
+mod inhibition;
+mod kernel_events;
 mod metrics;
 mod narration;
+mod narration_queue;
+mod network;
 mod notifier;
+mod pattern;
+mod plugins;
+mod policy;
+mod probes;
+mod rules;
+mod scripting;
 mod server;
+mod templates;
+mod thermal;
 
 use anyhow::Result;
 use std::time::Duration;
@@ -16,11 +37,22 @@ use tokio::signal;
 use tokio::time::interval;
 use tracing::{error, info, warn};
 
+use crate::actions::{ActionExecutor, ActionStatus};
+use crate::autonomous::AutonomousGuard;
+use crate::cgroup::CgroupMonitor;
 use crate::config::Config;
-use crate::detector::AnomalyDetector;
-use crate::metrics::MetricsCollector;
+use crate::detector::{AlertLevel, AnomalyDetector};
+use crate::diskio::DiskIoMonitor;
+use crate::escalation::EscalationManager;
+use crate::history::{History, Sample};
+use crate::kernel_events::{KernelEvent, KernelEventSource};
+use crate::metrics::{MetricsCollector, SystemMetrics};
+use crate::network::NetworkMonitor;
 use crate::notifier::Notifier;
-use crate::server::IpcServer;
+use crate::plugins::PluginRunner;
+use crate::probes::ProbeRunner;
+use crate::server::{IpcRequest, IpcResponse, IpcServer};
+use crate::thermal::SensorFs;
 use std::sync::Arc;
 
 #[tokio::main]
@@ -43,11 +75,31 @@ async fn main() -> Result<()> {
     // Initialize components
     let mut metrics_collector = MetricsCollector::new();
     let mut detector = AnomalyDetector::new(&config);
-    let notifier = Notifier::new(&config);
+    let mut notifier = Notifier::new(&config);
+    let mut escalation = EscalationManager::new(&config.escalation);
+    let executor = ActionExecutor::new(&config);
+    let mut autonomous = AutonomousGuard::new(&config.autonomous);
+    let mut cgroup_monitor = CgroupMonitor::new(&config.cgroups);
+    let sensors = SensorFs::new(&config.thermal.sysfs_root);
+    let mut disk_io = DiskIoMonitor::new();
+    let mut network = NetworkMonitor::new(config.network.per_process_sockets);
+    let mut history = History::new(config.history.max_entries, config.history.max_samples);
+    let probes = ProbeRunner::spawn(&config.probes);
+    let plugins = PluginRunner::spawn(&config.plugins);
+    let mut latest_metrics: Option<Arc<SystemMetrics>> = None;
+
+    // OOM kills and crashes from the kernel log
+    let (kernel_source, mut kernel_rx) = match KernelEventSource::spawn(&config.kernel_events) {
+        Some((source, rx)) => (Some(source), Some(rx)),
+        None => (None, None),
+    };
+
+    // Outcomes of autonomous actions, which run on blocking threads
+    let (auto_tx, mut auto_rx) = tokio::sync::mpsc::channel(4);
 
     // Initialise IPC Server
     let socket_path = "/tmp/system-sentinel.soc";
-    let (server, tx) = IpcServer::new(socket_path);
+    let (server, tx, mut cmd_rx) = IpcServer::new(socket_path);
     
     // Start IPC server in background
     tokio::spawn(async move {
@@ -66,20 +118,152 @@ async fn main() -> Result<()> {
             _ = check_interval.tick() => {
                 // Collect metrics (with aggregation for watchlist)
                 // Collect metrics (with auto-aggregation)
-                let metrics = metrics_collector.collect_aggregated();
+                let mut metrics = metrics_collector.collect_aggregated();
+
+                // cgroup memory of watched groups and the ones we capped
+                executor.limiter().prune();
+                metrics.cgroups = cgroup_monitor.collect(&executor.limiter().limited());
+                metrics.thermal = sensors.read();
+                metrics.disk_io = disk_io.collect(metrics.timestamp);
+                metrics.network = network.collect(metrics.timestamp);
+                metrics.probes = probes.results();
+                metrics.plugins = plugins.results();
+
+                // Plugin perfdata goes into the metric history, one sample per run
+                for result in plugins.take_completed() {
+                    for perf in &result.perfdata {
+                        let sample = Sample { timestamp: result.checked_at, value: perf.value, unit: perf.unit.clone() };
+                        history.record_sample(&format!("{}.{}", result.name, perf.label), sample);
+                    }
+                }
+
+                // The kernel log names OOM victims; memory.events only counts them
+                for (group, kills) in cgroup_monitor.take_oom_kills() {
+                    let logged = kernel_source.as_ref().is_some_and(|s| s.saw_oom_in(&group, Duration::from_secs(60)));
+                    if !logged {
+                        report_event(&KernelEvent::cgroup_oom_kill(&group, kills), &mut detector, &mut history, &mut notifier);
+                    }
+                }
 
                 // Detect anomalies
                 if let Some(anomaly) = detector.check(&metrics) {
                     warn!("Anomaly detected: {} - {}", anomaly.level, anomaly.message);
+                    history.record(&anomaly);
 
-                    // Send notification
-                    if let Err(e) = notifier.send(&anomaly) {
+                    if anomaly.level == AlertLevel::Critical && escalation.is_enabled() {
+                        // Criticals walk the escalation chain until acknowledged
+                        escalation.start(&anomaly, &notifier);
+                    } else if let Err(e) = notifier.send(&anomaly) {
                         error!("Failed to send notification: {}", e);
                     }
                 }
 
+                // Every breach sample counts towards the digest's peak and duration
+                for anomaly in detector.breaching() {
+                    notifier.observe(anomaly);
+                }
+
+                // Advance escalation chains for unacknowledged Criticals
+                escalation.tick(&notifier, |name| detector.active_level(name));
+
+                // Deliver any quiet-hours digest that has come due
+                if let Err(e) = notifier.tick() {
+                    error!("Failed to send digest: {}", e);
+                }
+
+                // Thaw processes whose freeze has timed out; report the rest so the UI shows them
+                executor.auto_thaw(false, Some(&metrics));
+                metrics.frozen_processes = executor.freezer().frozen();
+
                 // Broadcast metrics to UI
-                let _ = tx.send(Arc::new(metrics));
+                let metrics = Arc::new(metrics);
+                latest_metrics = Some(metrics.clone());
+                let _ = tx.send(metrics.clone());
+
+                // Tier 3: protective action without asking, if a rule demands it
+                if let Some(decision) = autonomous.evaluate(&metrics, std::time::Instant::now()) {
+                    if decision.dry_run {
+                        info!(
+                            "[dry run] Autonomous rule '{}' would {:?}: {}",
+                            decision.rule, decision.request.action, decision.request.reason
+                        );
+                    } else {
+                        warn!("Autonomous rule '{}' acting: {:?}", decision.rule, decision.request.action);
+                        let executor = executor.clone();
+                        let auto_tx = auto_tx.clone();
+                        tokio::task::spawn_blocking(move || {
+                            let outcome = executor.execute(&decision.request, Some(&metrics));
+                            let _ = auto_tx.blocking_send((decision, outcome));
+                        });
+                    }
+                }
+            }
+            Some(event) = recv_optional(&mut kernel_rx) => {
+                report_event(&event, &mut detector, &mut history, &mut notifier);
+            }
+            Some((decision, outcome)) = auto_rx.recv() => {
+                // Every autonomous action is announced, whether it worked or not
+                let (title, details) = decision.notification(&outcome);
+                if let Err(e) = notifier.show(AlertLevel::Critical, &title, &details) {
+                    error!("Failed to send autonomous action notification: {}", e);
+                }
+            }
+            Some((request, reply)) = cmd_rx.recv() => {
+                let response = match request {
+                    IpcRequest::Ack { anomaly_type, name } => {
+                        let acked = escalation.acknowledge(anomaly_type, name.as_deref());
+                        IpcResponse::ok(
+                            format!("Acknowledged {} alert(s)", acked.len()),
+                            serde_json::json!({ "acknowledged": acked }),
+                        )
+                    }
+                    IpcRequest::Status => IpcResponse::ok(
+                        "ok",
+                        serde_json::json!({
+                            "escalations": escalation.status(),
+                            "frozen": executor.freezer().frozen(),
+                            "limits": executor.limiter().limited(),
+                            "scripts": detector.script_status(),
+                            "suppressed": detector.suppressed(),
+                        }),
+                    ),
+                    IpcRequest::History { limit, series } => {
+                        let limit = limit.unwrap_or(50);
+                        let mut data = serde_json::json!({
+                            "history": history.recent(limit),
+                            "series": history.series_names(),
+                        });
+                        if let Some(name) = series {
+                            data["samples"] = serde_json::json!(history.samples(&name, limit));
+                        }
+                        IpcResponse::ok("ok", data)
+                    }
+                    IpcRequest::CheckProcess { pid, requester_pid } => match executor.policy().check(pid, requester_pid) {
+                        Ok(target) => IpcResponse::ok(
+                            format!("{} (PID {}) may be targeted", target.name, target.pid),
+                            serde_json::json!({ "target": target }),
+                        ),
+                        Err(violation) => {
+                            info!("Policy refused action on PID {}: {}", pid, violation);
+                            IpcResponse::error(format!("Refused: {}", violation))
+                        }
+                    },
+                    IpcRequest::Action(request) => {
+                        // Actions may block (external commands), so run them off the main loop
+                        let executor = executor.clone();
+                        let snapshot = latest_metrics.clone();
+                        tokio::task::spawn_blocking(move || {
+                            let outcome = executor.execute(&request, snapshot.as_deref());
+                            let response = match outcome.status {
+                                ActionStatus::Succeeded => IpcResponse::ok(outcome.message.clone(), serde_json::json!({ "outcome": outcome })),
+                                _ => IpcResponse::error(outcome.message.clone()),
+                            };
+                            let _ = reply.send(response);
+                        });
+                        continue;
+                    }
+                };
+                let _ = reply.send(response);
             }
             _ = signal::ctrl_c() => {
                 info!("Shutdown signal received, exiting...");
@@ -88,6 +272,36 @@ async fn main() -> Result<()> {
         }
     }
 
+    // Never leave processes stopped behind us
+    executor.auto_thaw(true, latest_metrics.as_deref());
+
     info!("System Sentinel stopped");
     Ok(())
 }
+
+/// Alert on a kernel event right away; events bypass damping and escalation.
+/// Every event is recorded, but repeats for the same victim only notify once
+/// per cooldown.
+fn report_event(event: &KernelEvent, detector: &mut AnomalyDetector, history: &mut History, notifier: &mut Notifier) {
+    let anomaly = detector.event_anomaly(event);
+    warn!("Kernel event: {} - {}", anomaly.message, event.detail);
+    history.record(&anomaly);
+    if !detector.event_due(event) {
+        return;
+    }
+    if let Err(e) = notifier.send(&anomaly) {
+        error!("Failed to send notification: {}", e);
+    }
+}
+
+/// Receive from an optional channel; never resolves when there is none, or
+/// once its sender is gone
+async fn recv_optional<T>(rx: &mut Option<tokio::sync::mpsc::Receiver<T>>) -> Option<T> {
+    if let Some(receiver) = rx {
+        if let Some(value) = receiver.recv().await {
+            return Some(value);
+        }
+        *rx = None;
+    }
+    std::future::pending().await
+}
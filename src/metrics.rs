@@ -1,12 +1,35 @@
 //! System metrics collection using sysinfo crate
 
 use std::collections::{VecDeque, HashMap};
-use sysinfo::{System, ProcessesToUpdate, MemoryRefreshKind, ProcessRefreshKind};
+use sysinfo::{Disks, System, ProcessesToUpdate, MemoryRefreshKind, ProcessRefreshKind};
 use serde::{Serialize, Deserialize};
 use tracing::debug;
 
+use crate::cgroup::CgroupMemory;
+use crate::diskio::DiskIo;
+use crate::freeze::FrozenProcess;
+use crate::handles::{FileTable, HandleCounter};
+use crate::network::NetworkMetrics;
+use crate::plugins::PluginResult;
+use crate::probes::ProbeResult;
+use crate::thermal::ThermalMetrics;
+
+/// How long exits and restarts are remembered for restart-loop detection
+const RESTART_RETENTION_MINUTES: i64 = 60;
+
+/// Descriptor and thread samples needed before a growth rate is reported
+/// (5 minutes at 30s intervals), so a burst of connections is not a leak
+const MIN_HANDLE_SAMPLES: usize = 10;
+
+/// Memory samples needed before a process's growth rate is reported, so a
+/// process is not ranked (or acted on) from a couple of allocations
+const MIN_PROCESS_GROWTH_SAMPLES: usize = 10;
+
+/// (time, value) samples, oldest first
+type History = VecDeque<(chrono::DateTime<chrono::Local>, u64)>;
+
 /// Snapshot of system metrics at a point in time
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SystemMetrics {
     pub timestamp: chrono::DateTime<chrono::Local>,
 
@@ -26,14 +49,119 @@ pub struct SystemMetrics {
     pub load_5m: f64,
     pub load_15m: f64,
 
+    // CPU cores, for reading load averages
+    #[serde(default)]
+    pub logical_cores: usize,
+    #[serde(default)]
+    pub physical_cores: Option<usize>,
+
+    // CPU utilization, overall and per logical core (percent)
+    #[serde(default)]
+    pub cpu_usage: f32,
+    #[serde(default)]
+    pub cpu_per_core: Vec<f32>,
+
+    // Mounted filesystems
+    #[serde(default)]
+    pub disks: Vec<DiskInfo>,
+
+    /// Block device I/O (Linux), filled in by the main loop
+    #[serde(default)]
+    pub disk_io: Vec<DiskIo>,
+
     // Top memory-consuming processes
     pub top_processes: Vec<ProcessInfo>,
 
+    /// Top CPU-consuming processes (percent of one core, so up to 100 per thread)
+    #[serde(default)]
+    pub top_cpu_processes: Vec<ProcessInfo>,
+
+    /// Processes reading and writing the most, busiest first
+    #[serde(default)]
+    pub top_io_processes: Vec<ProcessIo>,
+
     // Aggregated memory usage for watched processes (e.g., app families)
     pub aggregated_processes: Vec<ProcessInfo>,
 
     // Memory growth rate (GB/hour, calculated from history)
     pub memory_growth_rate: Option<f64>,
+
+    /// Processes whose memory is growing fastest (GB/hour), fastest first
+    #[serde(default)]
+    pub top_growers: Vec<ProcessInfo>,
+
+    /// Processes whose open descriptors and threads grow fastest (per hour), fastest first
+    #[serde(default)]
+    pub top_fd_growers: Vec<ProcessInfo>,
+    #[serde(default)]
+    pub top_thread_growers: Vec<ProcessInfo>,
+
+    /// Kernel file handles in use and fs.file-max (Linux)
+    #[serde(default)]
+    pub file_table: Option<FileTable>,
+
+    /// PID -> parent PID for every process, filled by `collect_aggregated`.
+    /// Daemon-internal (used to kill process trees), never sent to clients.
+    #[serde(skip)]
+    pub parent_pids: HashMap<u32, u32>,
+
+    /// Processes currently frozen by the daemon, filled in by the main loop
+    #[serde(default)]
+    pub frozen_processes: Vec<FrozenProcess>,
+
+    /// Memory of watched and daemon-limited cgroups (Linux), filled in by the main loop
+    #[serde(default)]
+    pub cgroups: Vec<CgroupMemory>,
+
+    /// Interfaces and TCP connections (Linux), filled in by the main loop
+    #[serde(default)]
+    pub network: NetworkMetrics,
+
+    /// Temperatures, fans and CPU frequencies (Linux), filled in by the main loop
+    #[serde(default)]
+    pub thermal: ThermalMetrics,
+
+    /// Every running process. Daemon-internal, never sent to clients.
+    #[serde(skip)]
+    pub processes: Vec<ProcessIdentity>,
+
+    /// Processes that exited since the previous sample
+    #[serde(default)]
+    pub exited_processes: Vec<ProcessIdentity>,
+
+    /// Programs restarted within the last hour, most restarts first
+    #[serde(default)]
+    pub process_restarts: Vec<ProcessRestarts>,
+
+    /// Latest result of each service probe, filled in by the main loop
+    #[serde(default)]
+    pub probes: Vec<ProbeResult>,
+
+    /// Latest result of each check plugin, filled in by the main loop
+    #[serde(default)]
+    pub plugins: Vec<PluginResult>,
+}
+
+impl SystemMetrics {
+    /// All descendants of `root` according to `parent_pids`, children before grandchildren
+    pub fn descendants(&self, root: u32) -> Vec<u32> {
+        let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+        for (&pid, &ppid) in &self.parent_pids {
+            children.entry(ppid).or_default().push(pid);
+        }
+
+        let mut result = Vec::new();
+        let mut queue = VecDeque::from([root]);
+        while let Some(pid) = queue.pop_front() {
+            for &child in children.get(&pid).into_iter().flatten() {
+                if child != root && !result.contains(&child) {
+                    result.push(child);
+                    queue.push_back(child);
+                }
+            }
+        }
+        result
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,6 +173,19 @@ pub struct ProcessInfo {
     pub memory_mb: f64,
     pub cpu_usage: f32,
     pub exe: Option<String>,
+    /// Resident memory growth rate of this process in GB/hour, once enough history exists
+    #[serde(default)]
+    pub memory_growth_rate: Option<f64>,
+    /// Open file descriptors and threads (Linux; None when not readable)
+    #[serde(default)]
+    pub open_fds: Option<u64>,
+    #[serde(default)]
+    pub threads: Option<u64>,
+    /// Growth of open descriptors and threads per hour, once enough history exists
+    #[serde(default)]
+    pub fd_growth_rate: Option<f64>,
+    #[serde(default)]
+    pub thread_growth_rate: Option<f64>,
 }
 
 impl ProcessInfo {
@@ -72,9 +213,76 @@ impl ProcessInfo {
     }
 }
 
+/// Space on one mounted filesystem
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskInfo {
+    pub name: String,
+    pub mount_point: String,
+    pub file_system: String,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+    pub used_percent: f64,
+}
+
+/// A process instance. A PID seen again with a different start time is a new
+/// process (the PID was reused).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessIdentity {
+    pub pid: u32,
+    pub name: String,
+    pub exe: Option<String>,
+    /// Seconds since the epoch
+    pub start_time: u64,
+    #[serde(default)]
+    pub parent_pid: Option<u32>,
+    /// Command line, arguments separated by spaces
+    #[serde(default)]
+    pub cmd: String,
+}
+
+impl ProcessIdentity {
+    /// What makes two instances "the same program": the executable, or the name
+    pub fn program(&self) -> &str {
+        self.exe.as_deref().filter(|e| !e.is_empty()).unwrap_or(&self.name)
+    }
+
+    /// What makes a new instance a restart of an exited one: the same program
+    /// started by the same parent with the same command line. Helpers of one
+    /// app or compiler runs in a build differ in their parent or arguments.
+    fn instance_key(&self) -> String {
+        format!("{}\0{}\0{}", self.program(), self.parent_pid.unwrap_or(0), self.cmd)
+    }
+}
+
+/// A program that keeps dying and being started again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessRestarts {
+    pub name: String,
+    pub exe: Option<String>,
+    /// PID of the latest instance
+    pub pid: u32,
+    /// When a new instance appeared after a previous one exited, oldest first
+    pub restarts: Vec<chrono::DateTime<chrono::Local>>,
+}
+
+/// Disk I/O of one process over the last sample
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessIo {
+    pub pid: u32,
+    pub name: String,
+    pub read_bytes_per_sec: f64,
+    pub write_bytes_per_sec: f64,
+}
+
+impl ProcessIo {
+    pub fn total_bytes_per_sec(&self) -> f64 {
+        self.read_bytes_per_sec + self.write_bytes_per_sec
+    }
+}
+
 /// Helper to extract \"App Name\" from a path containing .app
 /// e.g. \"/Applications/Visual Studio Code.app/Contents/MacOS/Electron\" -> \"Visual Studio Code\"
-fn extract_app_name(path: &str) -> Option<String> {
+pub fn extract_app_name(path: &str) -> Option<String> {
     if let Some(idx) = path.find(".app") {
         // Find the last slash before the .app
         let prefix = &path[..idx];
@@ -88,18 +296,40 @@ fn extract_app_name(path: &str) -> Option<String> {
 /// Collects system metrics with historical tracking for rate calculations
 pub struct MetricsCollector {
     system: System,
+    disks: Disks,
     /// Rolling history for growth rate calculation (last 10 minutes)
     memory_history: VecDeque<(chrono::DateTime<chrono::Local>, u64)>,
     /// Maximum history entries (at 30s intervals, 20 entries = 10 minutes)
     max_history: usize,
+    /// Per-process resident memory history, for per-process growth rates
+    process_history: HashMap<u32, VecDeque<(chrono::DateTime<chrono::Local>, u64)>>,
+    /// Per-process open descriptor and thread counts, for leak detection
+    fd_history: HashMap<u32, History>,
+    thread_history: HashMap<u32, History>,
+    handles: HandleCounter,
+    /// Processes seen in the previous sample, by PID
+    lifetimes: HashMap<u32, ProcessIdentity>,
+    /// Last exit time of each program instance (`ProcessIdentity::instance_key`),
+    /// for pairing exits with later starts
+    last_exit: HashMap<String, chrono::DateTime<chrono::Local>>,
+    /// Recent restarts by program instance
+    restarts: HashMap<String, ProcessRestarts>,
 }
 
 impl MetricsCollector {
     pub fn new() -> Self {
         Self {
             system: System::new_all(),
+            disks: Disks::new_with_refreshed_list(),
             memory_history: VecDeque::new(),
             max_history: 60, // 30 minutes at 30s intervals
+            process_history: HashMap::new(),
+            fd_history: HashMap::new(),
+            thread_history: HashMap::new(),
+            handles: HandleCounter::new(),
+            lifetimes: HashMap::new(),
+            last_exit: HashMap::new(),
+            restarts: HashMap::new(),
         }
     }
 
@@ -109,6 +339,7 @@ impl MetricsCollector {
 
         // Refresh system information
         self.system.refresh_memory_specifics(MemoryRefreshKind::new().with_ram().with_swap());
+        self.system.refresh_cpu_usage();
         self.system.refresh_processes_specifics(
             ProcessesToUpdate::All,
             true,  // refresh_user
@@ -137,23 +368,108 @@ impl MetricsCollector {
         // Load averages
         let load_avg = System::load_average();
 
-        // Top memory-consuming processes
-        let mut processes: Vec<ProcessInfo> = self.system
+        // Disk space; the mount list is refreshed too, volumes come and go
+        self.disks.refresh_list();
+        let disks = self.disks
+            .list()
+            .iter()
+            .filter(|d| d.total_space() > 0)
+            .map(|d| DiskInfo {
+                name: d.name().to_string_lossy().to_string(),
+                mount_point: d.mount_point().to_string_lossy().to_string(),
+                file_system: d.file_system().to_string_lossy().to_string(),
+                total_bytes: d.total_space(),
+                available_bytes: d.available_space(),
+                used_percent: (d.total_space() - d.available_space().min(d.total_space())) as f64 / d.total_space() as f64 * 100.0,
+            })
+            .collect();
+
+        // Per-process memory history; forget processes that have exited
+        self.process_history.retain(|pid, _| self.system.process(sysinfo::Pid::from_u32(*pid)).is_some());
+        for (pid, p) in self.system.processes() {
+            let history = self.process_history.entry(pid.as_u32()).or_default();
+            history.push_back((now, p.memory()));
+            while history.len() > self.max_history {
+                history.pop_front();
+            }
+        }
+
+        // Per-process descriptors and threads. Threads listed as processes
+        // share their process's descriptor table and are skipped.
+        let alive = |pid: &u32| self.system.process(sysinfo::Pid::from_u32(*pid)).is_some_and(|p| p.thread_kind().is_none());
+        self.fd_history.retain(|pid, _| alive(pid));
+        self.thread_history.retain(|pid, _| alive(pid));
+        for p in self.system.processes().values().filter(|p| p.thread_kind().is_none()) {
+            let pid = p.pid().as_u32();
+            record(&mut self.fd_history, pid, now, self.handles.open_fds(pid), self.max_history);
+            record(&mut self.thread_history, pid, now, self.handles.threads(pid), self.max_history);
+        }
+        let file_table = self.handles.file_table();
+
+        // Process lifetimes: exits, and restarts of the same program. Threads
+        // come and go with their process and are left out.
+        let identities: Vec<ProcessIdentity> = self.system
             .processes()
             .values()
-            .map(|p| ProcessInfo {
+            .filter(|p| p.thread_kind().is_none())
+            .map(|p| ProcessIdentity {
                 pid: p.pid().as_u32(),
-                parent_pid: p.parent().map(|ppid| ppid.as_u32()),
                 name: p.name().to_string_lossy().to_string(),
-                memory_bytes: p.memory(),
-                memory_mb: p.memory() as f64 / 1024.0 / 1024.0,
-                cpu_usage: p.cpu_usage(),
                 exe: p.exe().map(|path| path.to_string_lossy().to_string()),
+                start_time: p.start_time(),
+                parent_pid: p.parent().map(|ppid| ppid.as_u32()),
+                cmd: p.cmd().iter().map(|arg| arg.to_string_lossy()).collect::<Vec<_>>().join(" "),
             })
             .collect();
+        let exited_processes = self.track_lifetimes(now, &identities);
+        let mut process_restarts: Vec<ProcessRestarts> = self.restarts.values().cloned().collect();
+        process_restarts.sort_by_key(|r| std::cmp::Reverse(r.restarts.len()));
+
+        // Top memory-consuming processes
+        let mut processes: Vec<ProcessInfo> = self.system
+            .processes()
+            .values()
+            .map(|p| self.process_info(p, p.parent().map(|ppid| ppid.as_u32())))
+            .collect();
+
+        // Fastest-growing processes
+        let mut growers: Vec<ProcessInfo> = processes
+            .iter()
+            .filter(|p| p.memory_growth_rate.is_some_and(|r| r > 0.0))
+            .cloned()
+            .collect();
+        growers.sort_by(|a, b| b.memory_growth_rate.partial_cmp(&a.memory_growth_rate).unwrap_or(std::cmp::Ordering::Equal));
+        growers.truncate(5);
+        let top_fd_growers = fastest_growing(&processes, |p| p.fd_growth_rate);
+        let top_thread_growers = fastest_growing(&processes, |p| p.thread_growth_rate);
+
+        // Heaviest I/O since the previous sample
+        let elapsed = self.memory_history.back().map(|(t, _)| (now - *t).num_milliseconds() as f64 / 1000.0);
+        let mut top_io_processes: Vec<ProcessIo> = match elapsed {
+            Some(secs) if secs > 0.0 => self.system
+                .processes()
+                .values()
+                .map(|p| (p, p.disk_usage()))
+                .filter(|(_, io)| io.read_bytes + io.written_bytes > 0)
+                .map(|(p, io)| ProcessIo {
+                    pid: p.pid().as_u32(),
+                    name: p.name().to_string_lossy().to_string(),
+                    read_bytes_per_sec: io.read_bytes as f64 / secs,
+                    write_bytes_per_sec: io.written_bytes as f64 / secs,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        top_io_processes.sort_by(|a, b| b.total_bytes_per_sec().total_cmp(&a.total_bytes_per_sec()));
+        top_io_processes.truncate(10);
+
+        // Busiest processes
+        let mut top_cpu_processes = processes.clone();
+        top_cpu_processes.sort_by(|a, b| b.cpu_usage.partial_cmp(&a.cpu_usage).unwrap_or(std::cmp::Ordering::Equal));
+        top_cpu_processes.truncate(10);
 
         // Sort by memory usage descending
-        processes.sort_by(|a, b| b.memory_bytes.cmp(&a.memory_bytes));
+        processes.sort_by_key(|p| std::cmp::Reverse(p.memory_bytes));
         let top_processes: Vec<ProcessInfo> = processes.into_iter().take(10).collect();
 
         // Update history and calculate growth rate
@@ -181,12 +497,83 @@ impl MetricsCollector {
             load_1m: load_avg.one,
             load_5m: load_avg.five,
             load_15m: load_avg.fifteen,
+            logical_cores: self.system.cpus().len(),
+            physical_cores: self.system.physical_core_count(),
+            cpu_usage: self.system.global_cpu_usage(),
+            cpu_per_core: self.system.cpus().iter().map(|cpu| cpu.cpu_usage()).collect(),
+            disks,
+            disk_io: Vec::new(),
             top_processes,
+            top_cpu_processes,
+            top_io_processes,
             aggregated_processes: Vec::new(), // Initialized as empty, can be populated if needed
             memory_growth_rate,
+            top_growers: growers,
+            top_fd_growers,
+            top_thread_growers,
+            file_table,
+            parent_pids: HashMap::new(),
+            frozen_processes: Vec::new(),
+            cgroups: Vec::new(),
+            network: NetworkMetrics::default(),
+            thermal: ThermalMetrics::default(),
+            processes: identities,
+            exited_processes,
+            process_restarts,
+            probes: Vec::new(),
+            plugins: Vec::new(),
         }
     }
 
+    /// Compare this sample's processes with the previous one. Returns the
+    /// processes that exited; a program that exited and then appears again with
+    /// the same parent and command line counts as restarted, once per exit.
+    /// Instances that start and die between two samples are not seen, so fast
+    /// loops are undercounted, never overcounted.
+    fn track_lifetimes(&mut self, now: chrono::DateTime<chrono::Local>, current: &[ProcessIdentity]) -> Vec<ProcessIdentity> {
+        let current: HashMap<u32, &ProcessIdentity> = current.iter().map(|p| (p.pid, p)).collect();
+        let first_sample = self.lifetimes.is_empty();
+
+        let exited: Vec<ProcessIdentity> = self
+            .lifetimes
+            .values()
+            .filter(|old| current.get(&old.pid).is_none_or(|p| p.start_time != old.start_time))
+            .cloned()
+            .collect();
+        for process in &exited {
+            self.last_exit.insert(process.instance_key(), now);
+        }
+
+        let retention = chrono::Duration::minutes(RESTART_RETENTION_MINUTES);
+        if !first_sample {
+            for process in current.values() {
+                let is_new = self.lifetimes.get(&process.pid).is_none_or(|old| old.start_time != process.start_time);
+                let key = process.instance_key();
+                if !is_new || self.last_exit.remove(&key).is_none_or(|t| now - t > retention) {
+                    continue;
+                }
+
+                let entry = self.restarts.entry(key).or_insert_with(|| ProcessRestarts {
+                    name: process.name.clone(),
+                    exe: process.exe.clone(),
+                    pid: process.pid,
+                    restarts: Vec::new(),
+                });
+                entry.pid = process.pid;
+                entry.restarts.push(now);
+            }
+        }
+
+        self.last_exit.retain(|_, t| now - *t <= retention);
+        self.restarts.retain(|_, r| {
+            r.restarts.retain(|t| now - *t <= retention);
+            !r.restarts.is_empty()
+        });
+
+        self.lifetimes = current.into_iter().map(|(pid, p)| (pid, p.clone())).collect();
+        exited
+    }
+
     /// Automatically discovers and aggregates memory for all application bundles.
     /// Uses heuristics to identify app roots (paths containing .app) and bridges process tree gaps.
     pub fn collect_aggregated(&mut self) -> SystemMetrics {
@@ -205,20 +592,13 @@ impl MetricsCollector {
             let parent_pid = ppid_map.get(&pid).copied()
                 .or_else(|| proc.parent().map(|p| p.as_u32()));
 
-            let info = ProcessInfo {
-                pid,
-                parent_pid,
-                name: proc.name().to_string_lossy().to_string(),
-                memory_bytes: proc.memory(),
-                memory_mb: proc.memory() as f64 / 1024.0 / 1024.0,
-                cpu_usage: proc.cpu_usage(),
-                exe: proc.exe().map(|p| p.to_string_lossy().to_string()),
-            };
+            let info = self.process_info(proc, parent_pid);
             
             all_procs.insert(pid, info);
 
             if let Some(ppid) = parent_pid {
                 children_map.entry(ppid).or_default().push(pid);
+                metrics.parent_pids.insert(pid, ppid);
             }
         }
 
@@ -233,7 +613,7 @@ impl MetricsCollector {
                     let is_root = match info.parent_pid {
                         Some(ppid) => {
                             match all_procs.get(&ppid) {
-                                Some(parent) => !parent.exe.as_ref().map_or(false, |p| p.contains(&format!("{}.app", app_name))),
+                                Some(parent) => !parent.exe.as_ref().is_some_and(|p| p.contains(&format!("{}.app", app_name))),
                                 None => true // Parent unknown (or pid 1), so this is a root
                             }
                         },
@@ -278,6 +658,11 @@ impl MetricsCollector {
                     memory_mb: total_bytes as f64 / 1024.0 / 1024.0,
                     cpu_usage: total_cpu,
                     exe: None,
+                    memory_growth_rate: None,
+                    open_fds: None,
+                    threads: None,
+                    fd_growth_rate: None,
+                    thread_growth_rate: None,
                 });
              }
         }
@@ -286,45 +671,30 @@ impl MetricsCollector {
         metrics
     }
 
-    /// Calculate memory growth rate in GB/hour from historical data
-    /// Uses Linear Least Squares Regression to be robust against noise.
-    fn calculate_growth_rate(&self) -> Option<f64> {
-        let n = self.memory_history.len() as f64;
-        if n < 2.0 {
-            return None;
-        }
-
-        let (oldest_time, _) = self.memory_history.front()?;
-        
-        // Convert to relative time (hours) and memory (GB) points
-        let mut x_sum = 0.0;
-        let mut y_sum = 0.0;
-        let mut xy_sum = 0.0;
-        let mut xx_sum = 0.0;
-
-        for (time, mem) in &self.memory_history {
-            let x = (*time - *oldest_time).num_seconds() as f64 / 3600.0; // Hours since start of window
-            let y = *mem as f64 / 1024.0 / 1024.0 / 1024.0; // GB
-
-            x_sum += x;
-            y_sum += y;
-            xy_sum += x * y;
-            xx_sum += x * x;
-        }
-
-        // Linear least squares slope: (N∑xy - ∑x∑y) / (N∑x² - (∑x)²)
-        let numerator = n * xy_sum - x_sum * y_sum;
-        let denominator = n * xx_sum - x_sum * x_sum;
-
-        if denominator.abs() < 1e-9 {
-            return None; 
+    /// Current figures of a process, with growth rates from the collected history
+    fn process_info(&self, p: &sysinfo::Process, parent_pid: Option<u32>) -> ProcessInfo {
+        let pid = p.pid().as_u32();
+        let latest = |history: &HashMap<u32, History>| history.get(&pid).and_then(|h| h.back()).map(|(_, n)| *n);
+        ProcessInfo {
+            pid,
+            parent_pid,
+            name: p.name().to_string_lossy().to_string(),
+            memory_bytes: p.memory(),
+            memory_mb: p.memory() as f64 / 1024.0 / 1024.0,
+            cpu_usage: p.cpu_usage(),
+            exe: p.exe().map(|path| path.to_string_lossy().to_string()),
+            memory_growth_rate: self.process_history.get(&pid).and_then(process_growth_rate),
+            open_fds: latest(&self.fd_history),
+            threads: latest(&self.thread_history),
+            fd_growth_rate: self.fd_history.get(&pid).and_then(count_growth_rate),
+            thread_growth_rate: self.thread_history.get(&pid).and_then(count_growth_rate),
         }
-
-        let slope_gb_per_hour = numerator / denominator;
-        
-        Some(slope_gb_per_hour)
     }
 
+    /// Calculate memory growth rate in GB/hour from historical data
+    fn calculate_growth_rate(&self) -> Option<f64> {
+        growth_rate(&self.memory_history)
+    }
 
     /// Bridges gaps in the process tree by calling the system 'ps' utility.
     /// 'ps' has special kernel permissions to see parent/child relationships
@@ -335,7 +705,7 @@ impl MetricsCollector {
         let mut map = HashMap::new();
         
         let output = Command::new("ps")
-            .args(&["-ax", "-o", "pid,ppid"])
+            .args(["-ax", "-o", "pid,ppid"])
             .output();
             
         if let Ok(output) = output {
@@ -361,3 +731,175 @@ impl Default for MetricsCollector {
         Self::new()
     }
 }
+
+/// Append a sample to `pid`'s history. A value that could not be read drops
+/// the history, so the latest entry is always the current value.
+fn record(histories: &mut HashMap<u32, History>, pid: u32, now: chrono::DateTime<chrono::Local>, value: Option<u64>, max: usize) {
+    let Some(value) = value else {
+        histories.remove(&pid);
+        return;
+    };
+    let history = histories.entry(pid).or_default();
+    history.push_back((now, value));
+    while history.len() > max {
+        history.pop_front();
+    }
+}
+
+/// The five processes with the highest positive `rate`, fastest first
+fn fastest_growing(processes: &[ProcessInfo], rate: impl Fn(&ProcessInfo) -> Option<f64>) -> Vec<ProcessInfo> {
+    let mut growers: Vec<ProcessInfo> = processes.iter().filter(|p| rate(p).is_some_and(|r| r > 0.0)).cloned().collect();
+    growers.sort_by(|a, b| rate(b).unwrap_or(0.0).total_cmp(&rate(a).unwrap_or(0.0)));
+    growers.truncate(5);
+    growers
+}
+
+/// Growth of a count (descriptors, threads) per hour, from at least
+/// MIN_HANDLE_SAMPLES samples
+fn count_growth_rate(history: &History) -> Option<f64> {
+    if history.len() < MIN_HANDLE_SAMPLES {
+        return None;
+    }
+    slope_per_hour(history, 1.0)
+}
+
+/// Memory growth of a process in GB/hour, from at least
+/// MIN_PROCESS_GROWTH_SAMPLES samples
+fn process_growth_rate(history: &History) -> Option<f64> {
+    if history.len() < MIN_PROCESS_GROWTH_SAMPLES {
+        return None;
+    }
+    growth_rate(history)
+}
+
+/// Memory growth rate in GB/hour from a (time, bytes) history.
+fn growth_rate(history: &VecDeque<(chrono::DateTime<chrono::Local>, u64)>) -> Option<f64> {
+    slope_per_hour(history, 1024.0 * 1024.0 * 1024.0)
+}
+
+/// Change per hour of `value / unit` over a (time, value) history.
+/// Uses Linear Least Squares Regression to be robust against noise.
+fn slope_per_hour(history: &History, unit: f64) -> Option<f64> {
+    let n = history.len() as f64;
+    if n < 2.0 {
+        return None;
+    }
+
+    let (oldest_time, _) = history.front()?;
+    
+    // Convert to relative time (hours) and value (in units) points
+    let mut x_sum = 0.0;
+    let mut y_sum = 0.0;
+    let mut xy_sum = 0.0;
+    let mut xx_sum = 0.0;
+
+    for (time, value) in history {
+        let x = (*time - *oldest_time).num_seconds() as f64 / 3600.0; // Hours since start of window
+        let y = *value as f64 / unit;
+
+        x_sum += x;
+        y_sum += y;
+        xy_sum += x * y;
+        xx_sum += x * x;
+    }
+
+    // Linear least squares slope: (N∑xy - ∑x∑y) / (N∑x² - (∑x)²)
+    let numerator = n * xy_sum - x_sum * y_sum;
+    let denominator = n * xx_sum - x_sum * x_sum;
+
+    if denominator.abs() < 1e-9 {
+        return None; 
+    }
+
+    Some(numerator / denominator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, exe: &str, start_time: u64) -> ProcessIdentity {
+        ProcessIdentity {
+            pid,
+            name: exe.rsplit('/').next().unwrap().to_string(),
+            exe: Some(exe.to_string()),
+            start_time,
+            parent_pid: Some(1),
+            cmd: exe.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_track_lifetimes() {
+        let mut collector = MetricsCollector::new();
+        let t0 = chrono::Local::now();
+        let tick = |n: i64| t0 + chrono::Duration::seconds(30 * n);
+
+        let shell = process(10, "/bin/zsh", 100);
+        assert!(collector.track_lifetimes(tick(0), &[shell.clone(), process(20, "/usr/bin/flappy", 100)]).is_empty());
+
+        // flappy dies and comes back under a new PID, then under its old PID
+        let exited = collector.track_lifetimes(tick(1), &[shell.clone(), process(21, "/usr/bin/flappy", 130)]);
+        assert_eq!(exited.iter().map(|p| p.pid).collect::<Vec<_>>(), vec![20]);
+        let exited = collector.track_lifetimes(tick(2), &[shell.clone(), process(20, "/usr/bin/flappy", 160)]);
+        assert_eq!(exited.iter().map(|p| p.pid).collect::<Vec<_>>(), vec![21]);
+
+        let restarts = &collector.restarts[&process(20, "/usr/bin/flappy", 0).instance_key()];
+        assert_eq!(restarts.restarts.len(), 2);
+        assert_eq!(restarts.pid, 20);
+
+        // A new program is not a restart; neither is one that stays up
+        collector.track_lifetimes(tick(3), &[shell.clone(), process(20, "/usr/bin/flappy", 160), process(30, "/usr/bin/vim", 190)]);
+        assert_eq!(collector.restarts.len(), 1);
+
+        // Build jobs: a cc1 exits and others start, for other sources and
+        // under other compiler drivers. One exit pairs with at most one start.
+        let cc1 = |pid, parent, source: &str, start_time| ProcessIdentity {
+            parent_pid: Some(parent),
+            cmd: format!("/usr/libexec/cc1 {}", source),
+            ..process(pid, "/usr/libexec/cc1", start_time)
+        };
+        collector.track_lifetimes(tick(4), &[shell.clone(), cc1(40, 400, "a.c", 200)]);
+        collector.track_lifetimes(tick(5), &[shell.clone(), cc1(41, 401, "a.c", 230), cc1(42, 400, "b.c", 230)]);
+        assert_eq!(collector.restarts.len(), 1);
+        collector.track_lifetimes(tick(6), &[shell.clone(), cc1(43, 400, "a.c", 260)]);
+        let key = cc1(0, 400, "a.c", 0).instance_key();
+        assert_eq!(collector.restarts[&key].restarts.len(), 1, "same parent and arguments as PID 40");
+        collector.track_lifetimes(tick(7), &[shell, cc1(44, 400, "a.c", 290), cc1(45, 400, "a.c", 290)]);
+        assert_eq!(collector.restarts[&key].restarts.len(), 2);
+
+        // Restarts age out
+        collector.track_lifetimes(tick(200), &[]);
+        assert!(collector.restarts.is_empty());
+    }
+
+    #[test]
+    fn test_handle_history() {
+        let t0 = chrono::Local::now();
+        let mut histories = HashMap::new();
+        // 40 descriptors more every 30s: 4800 per hour
+        for n in 0..MIN_HANDLE_SAMPLES as i64 {
+            assert_eq!(histories.get(&7).and_then(count_growth_rate), None, "too few samples");
+            record(&mut histories, 7, t0 + chrono::Duration::seconds(30 * n), Some(100 + 40 * n as u64), 60);
+        }
+        let rate = histories.get(&7).and_then(count_growth_rate).unwrap();
+        assert!((rate - 4800.0).abs() < 1e-6, "{}", rate);
+
+        // An unreadable count forgets the history rather than keeping a stale value
+        record(&mut histories, 7, t0, None, 60);
+        assert!(histories.is_empty());
+    }
+
+    #[test]
+    fn test_process_growth_needs_history() {
+        let t0 = chrono::Local::now();
+        let mut history = History::new();
+        // 64 MiB more every 30s: 7.5 GB per hour
+        for n in 0..MIN_PROCESS_GROWTH_SAMPLES as i64 {
+            assert_eq!(process_growth_rate(&history), None, "too few samples");
+            history.push_back((t0 + chrono::Duration::seconds(30 * n), (n as u64 + 1) << 26));
+        }
+        let rate = process_growth_rate(&history).unwrap();
+        assert!((rate - 7.5).abs() < 1e-6, "{}", rate);
+    }
+}
@@ -1,11 +1,15 @@
 use anyhow::{Context, Result};
 use std::io::{Read, Write};
 use std::os::unix::net::UnixStream;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
 use serde::Serialize;
+use socket2::{Domain, SockAddr, Socket, Type};
 use tracing::{debug, warn};
 
-const SOCKET_PATH: &str = "/tmp/claude-tts-daemon.sock";
+use crate::config::{expand_tilde, NarrationConfig};
+use crate::detector::AlertLevel;
 
 #[derive(Serialize)]
 struct NarrationRequest {
@@ -18,53 +22,84 @@ struct NarrationRequest {
 }
 
 pub struct Narrator {
+    enabled: bool,
     socket_path: String,
-    hooks_path: std::path::PathBuf,
+    voice: String,
+    sound_dir: PathBuf,
+    warning_sound: Option<String>,
+    critical_sound: Option<String>,
+    connect_timeout: Duration,
+    read_timeout: Duration,
+    fallback_command: Vec<String>,
+    fallback_player: Vec<String>,
 }
 
 impl Narrator {
-    pub fn new() -> Self {
-        let hooks_path = dirs::home_dir()
-            .unwrap_or_else(|| std::path::PathBuf::from("/Users/fredrikbranstrom"))
-            .join(".claude/hooks");
-            
+    pub fn new(config: &NarrationConfig) -> Self {
         Self {
-            socket_path: SOCKET_PATH.to_string(),
-            hooks_path,
+            enabled: config.enabled,
+            socket_path: config.socket_path.clone(),
+            voice: config.voice.clone(),
+            sound_dir: expand_tilde(&config.sound_dir),
+            warning_sound: config.warning_sound.clone(),
+            critical_sound: config.critical_sound.clone(),
+            connect_timeout: Duration::from_millis(config.connect_timeout_ms),
+            read_timeout: Duration::from_millis(config.read_timeout_ms),
+            fallback_command: config.fallback_command.clone(),
+            fallback_player: config.fallback_player.clone(),
         }
     }
 
-    /// Send a narration request to the background daemon.
-    /// This is non-blocking on the audio playback itself, 
-    /// but blocking on the socket communication (which is instant).
-    pub fn narrate(&self, text: &str, sound_hint: Option<&str>) -> Result<()> {
-        if !Path::new(&self.socket_path).exists() {
-            debug!("TTS daemon socket not found at {}, skipping narration", self.socket_path);
+    /// Speak `text`, preceded by the sound configured for `level`.
+    /// Uses the TTS daemon when its socket exists, otherwise the local fallback.
+    /// Socket IO is bounded by the configured timeouts so a hung daemon can't block.
+    pub fn narrate(&self, text: &str, level: AlertLevel) -> Result<()> {
+        if !self.enabled {
             return Ok(());
         }
 
-        // Resolve absolute path if hint is provided
-        let resolved_sound = sound_hint.map(|hint| {
-            if hint.starts_with("sounds/") {
-                self.hooks_path.join(hint).to_string_lossy().to_string()
-            } else {
-                hint.to_string()
+        let sound = self.sound_for(level);
+
+        if !Path::new(&self.socket_path).exists() {
+            debug!("TTS daemon socket not found at {}, using local fallback", self.socket_path);
+            return self.narrate_locally(text, sound.as_deref());
+        }
+
+        match self.narrate_via_daemon(text, sound.as_deref()) {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                warn!("TTS daemon narration failed ({}), using local fallback", e);
+                self.narrate_locally(text, sound.as_deref())
             }
-        });
+        }
+    }
+
+    /// Resolve the sound file for an alert level against the sound directory
+    fn sound_for(&self, level: AlertLevel) -> Option<PathBuf> {
+        let sound = match level {
+            AlertLevel::Warning => self.warning_sound.as_deref(),
+            AlertLevel::Critical => self.critical_sound.as_deref(),
+        }?;
+
+        let path = expand_tilde(sound);
+        Some(if path.is_absolute() { path } else { self.sound_dir.join(path) })
+    }
 
+    fn narrate_via_daemon(&self, text: &str, sound: Option<&Path>) -> Result<()> {
         let request = NarrationRequest {
             request_type: "arbitrary_narration".to_string(),
             text: text.to_string(),
-            voice: "lucy".to_string(), // Matches user's preferred voice in config
-            sound_file: resolved_sound,
+            voice: self.voice.clone(),
+            sound_file: sound.map(|p| p.to_string_lossy().to_string()),
         };
 
         let payload = serde_json::to_vec(&request)?;
         let length_prefix = (payload.len() as u32).to_be_bytes();
 
         debug!("Connecting to TTS daemon at {}", self.socket_path);
-        let mut stream = UnixStream::connect(&self.socket_path)
-            .context("Failed to connect to TTS daemon socket")?;
+        let mut stream = self.connect()?;
+        stream.set_read_timeout(Some(self.read_timeout))?;
+        stream.set_write_timeout(Some(self.read_timeout))?;
 
         // Format is [4 bytes length][JSON payload]
         stream.write_all(&length_prefix)?;
@@ -74,7 +109,7 @@ impl Narrator {
         // Wait for OK response
         let mut response = [0u8; 2];
         let bytes_read = stream.read_exact(&mut response);
-        
+
         if bytes_read.is_ok() && &response == b"OK" {
             debug!("Narration request accepted by daemon");
         } else {
@@ -83,4 +118,135 @@ impl Narrator {
 
         Ok(())
     }
+
+    /// `UnixStream` has no connect timeout, so connect through socket2,
+    /// which connects non-blocking and waits at most `connect_timeout`.
+    fn connect(&self) -> Result<UnixStream> {
+        let socket = Socket::new(Domain::UNIX, Type::STREAM, None)?;
+        let address = SockAddr::unix(&self.socket_path)?;
+        socket
+            .connect_timeout(&address, self.connect_timeout)
+            .with_context(|| format!("Failed to connect to TTS daemon socket {}", self.socket_path))?;
+        Ok(socket.into())
+    }
+
+    /// Play the sound, then speak with the local TTS command. Both run detached.
+    fn narrate_locally(&self, text: &str, sound: Option<&Path>) -> Result<()> {
+        let Some((program, args)) = self.fallback_command.split_first() else {
+            debug!("No fallback TTS command configured, skipping narration");
+            return Ok(());
+        };
+
+        let mut script = Vec::new();
+        if let (Some(sound), Some((player, player_args))) = (sound, self.fallback_player.split_first()) {
+            if sound.exists() {
+                let mut cmd = Command::new(player);
+                cmd.args(player_args).arg(sound);
+                script.push(cmd);
+            }
+        }
+        let mut cmd = Command::new(program);
+        cmd.args(args).arg(text);
+        script.push(cmd);
+
+        // Run sequentially in the background so the sound precedes speech
+        // without the notifier waiting on either
+        std::thread::spawn(move || {
+            for mut cmd in script {
+                let status = cmd
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status();
+                if let Err(e) = status {
+                    warn!("Local narration command {:?} failed: {}", cmd.get_program(), e);
+                }
+            }
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use std::time::Instant;
+
+    fn narrator(socket_path: &Path) -> Narrator {
+        Narrator::new(&NarrationConfig {
+            socket_path: socket_path.to_string_lossy().to_string(),
+            voice: "alba".to_string(),
+            sound_dir: "/sounds".to_string(),
+            warning_sound: Some("warn.wav".to_string()),
+            critical_sound: Some("/opt/critical.wav".to_string()),
+            read_timeout_ms: 200,
+            fallback_command: Vec::new(),
+            ..NarrationConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_sound_paths() {
+        let mut narrator = narrator(Path::new("/nonexistent.sock"));
+        assert_eq!(narrator.sound_for(AlertLevel::Warning), Some(PathBuf::from("/sounds/warn.wav")));
+        assert_eq!(narrator.sound_for(AlertLevel::Critical), Some(PathBuf::from("/opt/critical.wav")));
+        narrator.warning_sound = None;
+        assert_eq!(narrator.sound_for(AlertLevel::Warning), None);
+
+        // No socket and no fallback command: nothing to do, but no error
+        assert!(narrator.narrate("Memory high.", AlertLevel::Warning).is_ok());
+    }
+
+    #[test]
+    fn test_daemon_request() {
+        let dir = std::env::temp_dir().join(format!("sentinel-narration-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("tts.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let daemon = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut length = [0u8; 4];
+            stream.read_exact(&mut length).unwrap();
+            let mut payload = vec![0u8; u32::from_be_bytes(length) as usize];
+            stream.read_exact(&mut payload).unwrap();
+            stream.write_all(b"OK").unwrap();
+            serde_json::from_slice::<serde_json::Value>(&payload).unwrap()
+        });
+
+        narrator(&path).narrate("Memory critical.", AlertLevel::Critical).unwrap();
+        let request = daemon.join().unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(request["type"], "arbitrary_narration");
+        assert_eq!(request["text"], "Memory critical.");
+        assert_eq!(request["voice"], "alba");
+        assert_eq!(request["sound_file"], "/opt/critical.wav");
+    }
+
+    #[test]
+    fn test_unresponsive_or_stale_daemon() {
+        let dir = std::env::temp_dir().join(format!("sentinel-narration-stale-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("tts.sock");
+
+        // Accepts the connection but never answers: bounded by the read timeout
+        let listener = UnixListener::bind(&path).unwrap();
+        let started = Instant::now();
+        let result = narrator(&path).narrate("Swap high.", AlertLevel::Warning);
+        let elapsed = started.elapsed();
+
+        // Socket file left behind by a daemon that is gone: falls back
+        drop(listener);
+        let stale = narrator(&path).narrate("Swap high.", AlertLevel::Warning);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert!(result.is_ok());
+        assert!(elapsed < Duration::from_secs(2), "took {:?}", elapsed);
+        assert!(stale.is_ok());
+    }
 }
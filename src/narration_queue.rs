@@ -0,0 +1,239 @@
+//! Priority queue and rate limiting in front of the `Narrator`
+//!
+//! Narrations that arrive within a short merge window, or while the previous
+//! one's `min_gap_seconds` is running, are spoken as one utterance instead of
+//! talking over each other; each part keeps its template-rendered wording
+//! ("Memory critical. 95 percent. Swap critical."). A Critical pre-empts any
+//! Warnings still waiting to be spoken, and a rolling hourly cap stops the
+//! sentinel from chattering; overflow stays visual-only.
+
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use crate::config::NarrationConfig;
+use crate::detector::AlertLevel;
+use crate::narration::Narrator;
+
+/// A narration waiting to be spoken
+#[derive(Debug, Clone, PartialEq)]
+pub struct NarrationItem {
+    /// Alert name; a newer narration for the same alert replaces a queued one
+    pub name: String,
+    pub level: AlertLevel,
+    pub text: String,
+}
+
+/// What the worker should actually say
+#[derive(Debug, Clone, PartialEq)]
+pub struct Utterance {
+    pub text: String,
+    pub level: AlertLevel,
+}
+
+/// Merge/priority/rate-limit logic, independent of threads and audio
+pub struct NarrationQueue {
+    merge_window: Duration,
+    min_gap: Duration,
+    max_per_hour: u32,
+    pending: Vec<NarrationItem>,
+    batch_started: Option<Instant>,
+    last_spoken: Option<Instant>,
+    spoken: VecDeque<Instant>,
+}
+
+impl NarrationQueue {
+    pub fn new(config: &NarrationConfig) -> Self {
+        Self {
+            merge_window: Duration::from_millis(config.merge_window_ms),
+            min_gap: Duration::from_secs(config.min_gap_seconds),
+            max_per_hour: config.max_per_hour,
+            pending: Vec::new(),
+            batch_started: None,
+            last_spoken: None,
+            spoken: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, item: NarrationItem, now: Instant) {
+        let has_critical = self.pending.iter().any(|p| p.level == AlertLevel::Critical);
+
+        match item.level {
+            AlertLevel::Critical if !has_critical => {
+                let dropped = self.pending.len();
+                if dropped > 0 {
+                    debug!("Critical narration pre-empts {} queued warning(s)", dropped);
+                }
+                self.pending.clear();
+            }
+            AlertLevel::Warning if has_critical => {
+                debug!("Dropping warning narration while a critical is queued: {}", item.text);
+                return;
+            }
+            _ => {}
+        }
+
+        // Replace an earlier narration for the same alert with the fresher one
+        self.pending.retain(|p| p.name != item.name);
+        self.pending.push(item);
+        self.batch_started.get_or_insert(now);
+    }
+
+    /// When the pending batch may be spoken, if anything is pending
+    pub fn due(&self) -> Option<Instant> {
+        let batch_ready = self.batch_started? + self.merge_window;
+        let gap_over = self.last_spoken.map(|t| t + self.min_gap);
+        Some(gap_over.map_or(batch_ready, |g| g.max(batch_ready)))
+    }
+
+    /// Take the pending batch if it is due, applying the hourly cap
+    pub fn pop_ready(&mut self, now: Instant) -> Option<Utterance> {
+        if self.due()? > now {
+            return None;
+        }
+
+        let batch = std::mem::take(&mut self.pending);
+        self.batch_started = None;
+
+        let hour = Duration::from_secs(3600);
+        while self.spoken.front().is_some_and(|t| now.duration_since(*t) >= hour) {
+            self.spoken.pop_front();
+        }
+
+        if self.max_per_hour > 0 && self.spoken.len() >= self.max_per_hour as usize {
+            info!("Narration cap of {}/hour reached; {} alert(s) shown visually only", self.max_per_hour, batch.len());
+            return None;
+        }
+
+        self.spoken.push_back(now);
+        self.last_spoken = Some(now);
+        Some(merge(&batch))
+    }
+}
+
+/// The batch's rendered narrations in arrival order, each ending as a sentence
+fn merge(batch: &[NarrationItem]) -> Utterance {
+    let level = batch.iter().map(|i| i.level).max().unwrap_or(AlertLevel::Warning);
+    let sentences: Vec<String> = batch
+        .iter()
+        .map(|i| i.text.trim())
+        .filter(|text| !text.is_empty())
+        .map(|text| if text.ends_with(['.', '!', '?']) { text.to_string() } else { format!("{}.", text) })
+        .collect();
+    Utterance { text: sentences.join(" "), level }
+}
+
+/// Handle for submitting narrations to the background worker
+pub struct NarrationHandle {
+    tx: mpsc::Sender<NarrationItem>,
+}
+
+impl NarrationHandle {
+    /// Start the worker thread that owns the queue and the `Narrator`
+    pub fn spawn(config: &NarrationConfig) -> Self {
+        let (tx, rx) = mpsc::channel::<NarrationItem>();
+        let narrator = Narrator::new(config);
+        let mut queue = NarrationQueue::new(config);
+
+        std::thread::spawn(move || loop {
+            let received = match queue.due() {
+                Some(due) => rx.recv_timeout(due.saturating_duration_since(Instant::now())),
+                None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+
+            match received {
+                Ok(item) => queue.push(item, Instant::now()),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            if let Some(utterance) = queue.pop_ready(Instant::now()) {
+                if let Err(e) = narrator.narrate(&utterance.text, utterance.level) {
+                    warn!("Narration failed: {}", e);
+                }
+            }
+        });
+
+        Self { tx }
+    }
+
+    pub fn submit(&self, item: NarrationItem) {
+        if self.tx.send(item).is_err() {
+            warn!("Narration worker has stopped; dropping narration");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(name: &str, level: AlertLevel, text: &str) -> NarrationItem {
+        NarrationItem { name: name.to_string(), level, text: text.to_string() }
+    }
+
+    fn queue(min_gap_seconds: u64, max_per_hour: u32) -> NarrationQueue {
+        NarrationQueue::new(&NarrationConfig { merge_window_ms: 1000, min_gap_seconds, max_per_hour, ..NarrationConfig::default() })
+    }
+
+    #[test]
+    fn test_merges_within_window() {
+        let mut q = queue(0, 0);
+        let t0 = Instant::now();
+        q.push(item("memory", AlertLevel::Critical, "Memory critical. 95 percent."), t0);
+        q.push(item("swap", AlertLevel::Critical, "Swap critical"), t0 + Duration::from_millis(200));
+
+        assert!(q.pop_ready(t0 + Duration::from_millis(500)).is_none());
+        let u = q.pop_ready(t0 + Duration::from_millis(1000)).unwrap();
+        assert_eq!(u.text, "Memory critical. 95 percent. Swap critical.");
+        assert_eq!(u.level, AlertLevel::Critical);
+        assert!(q.due().is_none());
+    }
+
+    #[test]
+    fn test_merges_during_gap() {
+        let mut q = queue(5, 0);
+        let t0 = Instant::now();
+        q.push(item("memory", AlertLevel::Critical, "Memory critical."), t0);
+        assert_eq!(q.pop_ready(t0 + Duration::from_secs(1)).unwrap().text, "Memory critical.");
+
+        // Both arrive while the gap runs and a newer memory narration replaces the older
+        q.push(item("memory", AlertLevel::Critical, "Memory critical. 97 percent."), t0 + Duration::from_secs(2));
+        q.push(item("swap", AlertLevel::Critical, "Swap critical."), t0 + Duration::from_secs(3));
+        q.push(item("memory", AlertLevel::Critical, "Memory critical. 98 percent."), t0 + Duration::from_secs(4));
+        assert!(q.pop_ready(t0 + Duration::from_secs(5)).is_none(), "must wait for the gap");
+        let u = q.pop_ready(t0 + Duration::from_secs(6)).unwrap();
+        assert_eq!(u.text, "Swap critical. Memory critical. 98 percent.");
+    }
+
+    #[test]
+    fn test_critical_preempts_warnings() {
+        let mut q = queue(0, 0);
+        let t0 = Instant::now();
+        q.push(item("load", AlertLevel::Warning, "Load high."), t0);
+        q.push(item("memory", AlertLevel::Critical, "Memory critical. 95 percent."), t0);
+        q.push(item("swap", AlertLevel::Warning, "Swap high."), t0);
+
+        let at = t0 + Duration::from_secs(1);
+        assert_eq!(q.pop_ready(at).unwrap().text, "Memory critical. 95 percent.");
+        assert!(q.pop_ready(at).is_none());
+    }
+
+    #[test]
+    fn test_hourly_cap() {
+        let mut q = queue(0, 2);
+        let t0 = Instant::now();
+        for i in 0..3 {
+            let t = t0 + Duration::from_secs(i * 10);
+            q.push(item("memory", AlertLevel::Warning, "Memory high."), t);
+            let spoken = q.pop_ready(t + Duration::from_secs(1));
+            assert_eq!(spoken.is_some(), i < 2);
+        }
+
+        // An hour later the cap has rolled over
+        let later = t0 + Duration::from_secs(3700);
+        q.push(item("memory", AlertLevel::Warning, "Memory high."), later);
+        assert!(q.pop_ready(later + Duration::from_secs(1)).is_some());
+    }
+}
@@ -0,0 +1,246 @@
+//! Network interface throughput and TCP connection states (Linux)
+//!
+//! `NetworkMonitor` reads /proc/net/dev for per-interface byte, error and
+//! drop counters and turns byte counters into rates between ticks. TCP
+//! sockets are counted by state from /proc/net/tcp and /proc/net/tcp6.
+//! Optionally, sockets are attributed to processes by matching the socket
+//! inodes behind /proc/<pid>/fd, which costs a readlink per open descriptor.
+//! Elsewhere the files are missing and nothing is reported.
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+/// Names of the `st` codes in /proc/net/tcp
+const TCP_STATES: &[&str] = &[
+    "", "ESTABLISHED", "SYN_SENT", "SYN_RECV", "FIN_WAIT1", "FIN_WAIT2", "TIME_WAIT", "CLOSE", "CLOSE_WAIT", "LAST_ACK",
+    "LISTEN", "CLOSING",
+];
+
+/// Processes listed in `NetworkMetrics::process_sockets`
+const TOP_SOCKET_PROCESSES: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InterfaceStats {
+    pub name: String,
+    pub rx_bytes_per_sec: f64,
+    pub tx_bytes_per_sec: f64,
+    /// Cumulative counters since the interface came up
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+    pub rx_dropped: u64,
+    pub tx_dropped: u64,
+}
+
+/// TCP sockets held by one process
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessSockets {
+    pub pid: u32,
+    pub name: String,
+    pub tcp: u32,
+    pub close_wait: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NetworkMetrics {
+    pub interfaces: Vec<InterfaceStats>,
+    /// TCP sockets (IPv4 and IPv6) by state, e.g. "ESTABLISHED" -> 42
+    pub tcp_states: BTreeMap<String, u32>,
+    /// Processes holding the most TCP sockets, when per-process counting is on
+    pub process_sockets: Vec<ProcessSockets>,
+}
+
+impl NetworkMetrics {
+    pub fn tcp_state(&self, state: &str) -> u32 {
+        self.tcp_states.get(state).copied().unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct InterfaceCounters {
+    rx_bytes: u64,
+    rx_errors: u64,
+    rx_dropped: u64,
+    tx_bytes: u64,
+    tx_errors: u64,
+    tx_dropped: u64,
+}
+
+pub struct NetworkMonitor {
+    proc_root: PathBuf,
+    per_process: bool,
+    previous: HashMap<String, InterfaceCounters>,
+    previous_at: Option<DateTime<Local>>,
+}
+
+impl NetworkMonitor {
+    pub fn new(per_process: bool) -> Self {
+        Self::with_root("/proc", per_process)
+    }
+
+    pub fn with_root(proc_root: impl Into<PathBuf>, per_process: bool) -> Self {
+        Self { proc_root: proc_root.into(), per_process, previous: HashMap::new(), previous_at: None }
+    }
+
+    /// Interface rates are zero on the first call
+    pub fn collect(&mut self, now: DateTime<Local>) -> NetworkMetrics {
+        let counters = std::fs::read_to_string(self.proc_root.join("net/dev"))
+            .map(|text| parse_net_dev(&text))
+            .unwrap_or_default();
+        let secs = self.previous_at.map_or(0.0, |at| (now - at).num_milliseconds() as f64 / 1000.0);
+        let rate = |before: Option<u64>, now: u64| match before {
+            Some(before) if secs > 0.0 => now.saturating_sub(before) as f64 / secs,
+            _ => 0.0,
+        };
+
+        let interfaces = counters
+            .iter()
+            .map(|(name, c)| {
+                let before = self.previous.get(name);
+                InterfaceStats {
+                    name: name.clone(),
+                    rx_bytes_per_sec: rate(before.map(|b| b.rx_bytes), c.rx_bytes),
+                    tx_bytes_per_sec: rate(before.map(|b| b.tx_bytes), c.tx_bytes),
+                    rx_errors: c.rx_errors,
+                    tx_errors: c.tx_errors,
+                    rx_dropped: c.rx_dropped,
+                    tx_dropped: c.tx_dropped,
+                }
+            })
+            .collect();
+        self.previous = counters.into_iter().collect();
+        self.previous_at = Some(now);
+
+        let sockets: Vec<(u64, &'static str)> = ["net/tcp", "net/tcp6"]
+            .iter()
+            .filter_map(|file| std::fs::read_to_string(self.proc_root.join(file)).ok())
+            .flat_map(|text| parse_net_tcp(&text))
+            .collect();
+        let mut tcp_states = BTreeMap::new();
+        for (_, state) in &sockets {
+            *tcp_states.entry(state.to_string()).or_insert(0) += 1;
+        }
+
+        // Socket inode -> state, for attribution. TIME_WAIT and orphaned
+        // sockets belong to no process and all have inode 0.
+        let sockets: HashMap<u64, &'static str> = sockets.into_iter().filter(|(inode, _)| *inode != 0).collect();
+        let process_sockets = if self.per_process { self.process_sockets(&sockets) } else { Vec::new() };
+        NetworkMetrics { interfaces, tcp_states, process_sockets }
+    }
+
+    fn process_sockets(&self, sockets: &HashMap<u64, &'static str>) -> Vec<ProcessSockets> {
+        let Ok(entries) = std::fs::read_dir(&self.proc_root) else { return Vec::new() };
+        let mut processes: Vec<ProcessSockets> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let pid: u32 = entry.file_name().to_string_lossy().parse().ok()?;
+                let mut held = ProcessSockets { pid, name: String::new(), tcp: 0, close_wait: 0 };
+                for fd in std::fs::read_dir(entry.path().join("fd")).ok()?.flatten() {
+                    let Ok(target) = std::fs::read_link(fd.path()) else { continue };
+                    let target = target.to_string_lossy();
+                    let Some(inode) = target.strip_prefix("socket:[").and_then(|s| s.strip_suffix(']')) else { continue };
+                    let Some(state) = inode.parse().ok().and_then(|inode: u64| sockets.get(&inode)) else { continue };
+                    held.tcp += 1;
+                    held.close_wait += u32::from(*state == "CLOSE_WAIT");
+                }
+                (held.tcp > 0).then(|| {
+                    held.name = std::fs::read_to_string(entry.path().join("comm")).unwrap_or_default().trim().to_string();
+                    held
+                })
+            })
+            .collect();
+        processes.sort_by_key(|p| std::cmp::Reverse((p.close_wait, p.tcp)));
+        processes.truncate(TOP_SOCKET_PROCESSES);
+        processes
+    }
+}
+
+/// "  eth0: rx_bytes packets errs drop fifo frame compressed multicast tx_bytes packets errs drop ..."
+fn parse_net_dev(text: &str) -> Vec<(String, InterfaceCounters)> {
+    text.lines()
+        .filter_map(|line| {
+            let (name, fields) = line.split_once(':')?;
+            let fields: Vec<u64> = fields.split_whitespace().filter_map(|f| f.parse().ok()).collect();
+            let counters = InterfaceCounters {
+                rx_bytes: *fields.first()?,
+                rx_errors: *fields.get(2)?,
+                rx_dropped: *fields.get(3)?,
+                tx_bytes: *fields.get(8)?,
+                tx_errors: *fields.get(10)?,
+                tx_dropped: *fields.get(11)?,
+            };
+            Some((name.trim().to_string(), counters))
+        })
+        .collect()
+}
+
+/// Socket inode and state of every line of /proc/net/tcp{,6}
+fn parse_net_tcp(text: &str) -> Vec<(u64, &'static str)> {
+    text.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let state = u8::from_str_radix(fields.get(3)?, 16).ok()?;
+            let inode = fields.get(9)?.parse().ok()?;
+            Some((inode, *TCP_STATES.get(usize::from(state)).filter(|s| !s.is_empty())?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TCP_HEADER: &str = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n";
+
+    fn tcp_line(state: &str, inode: u64) -> String {
+        format!("   0: 0100007F:1F90 0100007F:C350 {} 00000000:00000000 00:00000000 00000000  1000        0 {} 1 0000000000000000 20 4 30 10 -1\n", state, inode)
+    }
+
+    #[test]
+    fn test_collect() {
+        let root = std::env::temp_dir().join(format!("sentinel-net-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("net")).unwrap();
+        std::fs::create_dir_all(root.join("4242/fd")).unwrap();
+        std::fs::write(root.join("4242/comm"), "node\n").unwrap();
+        let dev = |tx: u64| {
+            format!(
+                "Inter-|   Receive                                                |  Transmit\n \
+                 face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed\n    \
+                 lo: 1000 10 0 0 0 0 0 0 1000 10 0 0 0 0 0 0\n  \
+                 eth0: 5000 50 3 7 0 0 0 0 {} 90 1 2 0 0 0 0\n",
+                tx
+            )
+        };
+        std::fs::write(root.join("net/dev"), dev(10_000)).unwrap();
+        let time_wait = tcp_line("06", 0).repeat(3);
+        let tcp = format!("{}{}{}{}{}", TCP_HEADER, tcp_line("0A", 100), tcp_line("08", 101), tcp_line("08", 102), time_wait);
+        std::fs::write(root.join("net/tcp"), tcp).unwrap();
+        std::fs::write(root.join("net/tcp6"), format!("{}{}", TCP_HEADER, tcp_line("01", 103))).unwrap();
+        for (fd, inode) in [(3, 101), (4, 102), (5, 103)] {
+            std::os::unix::fs::symlink(format!("socket:[{}]", inode), root.join(format!("4242/fd/{}", fd))).unwrap();
+        }
+        std::os::unix::fs::symlink("/dev/null", root.join("4242/fd/0")).unwrap();
+
+        let mut monitor = NetworkMonitor::with_root(&root, true);
+        let start = Local::now();
+        let first = monitor.collect(start);
+        assert_eq!(first.interfaces[1].tx_bytes_per_sec, 0.0);
+
+        std::fs::write(root.join("net/dev"), dev(310_000)).unwrap();
+        let metrics = monitor.collect(start + chrono::Duration::seconds(30));
+        let _ = std::fs::remove_dir_all(&root);
+
+        let eth0 = &metrics.interfaces[1];
+        assert_eq!(eth0.name, "eth0");
+        assert_eq!(eth0.tx_bytes_per_sec, 10_000.0);
+        assert_eq!((eth0.rx_errors, eth0.rx_dropped, eth0.tx_errors, eth0.tx_dropped), (3, 7, 1, 2));
+        assert_eq!(metrics.tcp_state("CLOSE_WAIT"), 2);
+        assert_eq!(metrics.tcp_state("LISTEN"), 1);
+        assert_eq!(metrics.tcp_state("ESTABLISHED"), 1);
+        assert_eq!(metrics.tcp_state("TIME_WAIT"), 3, "inode 0 sockets are all counted");
+        assert_eq!(metrics.process_sockets, vec![ProcessSockets { pid: 4242, name: "node".to_string(), tcp: 3, close_wait: 2 }]);
+    }
+}
@@ -1,18 +1,22 @@
 //! Notification sending via Hammerspoon or fallback
 
 use anyhow::{Context, Result};
-use std::process::Command;
+use std::io::Write;
+use std::process::{Command, Stdio};
 use tracing::{debug, info, warn};
 
 use crate::config::Config;
 use crate::detector::{AlertLevel, Anomaly};
+use crate::digest::Digest;
+use crate::narration_queue::{NarrationHandle, NarrationItem};
 
 pub struct Notifier {
     use_hammerspoon: bool,
     fallback_to_terminal_notifier: bool,
     warning_color: String,
     critical_color: String,
-    narrator: crate::narration::Narrator,
+    narration: NarrationHandle,
+    digest: Digest,
 }
 
 impl Notifier {
@@ -22,21 +26,56 @@ impl Notifier {
             fallback_to_terminal_notifier: config.notification.fallback_to_terminal_notifier,
             warning_color: config.notification.warning_color.clone(),
             critical_color: config.notification.critical_color.clone(),
-            narrator: crate::narration::Narrator::new(),
+            narration: NarrationHandle::spawn(&config.narration),
+            digest: Digest::new(&config.notification),
         }
     }
 
-    /// Send notification for detected anomaly
-    pub fn send(&self, anomaly: &Anomaly) -> Result<()> {
+    /// Send notification for detected anomaly.
+    /// Warnings are held back for the digest during quiet hours.
+    pub fn send(&mut self, anomaly: &Anomaly) -> Result<()> {
+        let now = chrono::Local::now();
+        if self.digest.should_buffer(anomaly, now) {
+            info!("Buffering {} for digest: {}", anomaly.level, anomaly.message);
+            self.digest.record(anomaly, now);
+            return Ok(());
+        }
+
         info!("Sending {} notification: {}", anomaly.level, anomaly.message);
 
-        // Trigger narration (non-blocking on audio, but blocks on socket IPC)
-        if let Err(e) = self.narrator.narrate(&anomaly.narration_message, anomaly.sound_hint.as_deref()) {
-            warn!("Narration failed: {}", e);
+        self.narrate(anomaly);
+        self.show(anomaly.level, &anomaly.message, &anomaly.details)
+    }
+
+    /// Feed a breach sample to the digest. Called for every breaching anomaly
+    /// each tick, notified or not, so that buffered entries keep their true
+    /// peak and duration while the alert cools down.
+    pub fn observe(&mut self, anomaly: &Anomaly) {
+        self.digest.sample(anomaly, chrono::Local::now());
+    }
+
+    /// Queue the anomaly's narration text (spaced out and rate-limited by the worker)
+    pub fn narrate(&self, anomaly: &Anomaly) {
+        self.narration.submit(NarrationItem {
+            name: anomaly.name(),
+            level: anomaly.level,
+            text: anomaly.narration_message.clone(),
+        });
+    }
+
+    /// Deliver the pending digest if it is due. Call once per monitoring tick.
+    pub fn tick(&mut self) -> Result<()> {
+        if let Some(summary) = self.digest.poll(chrono::Local::now()) {
+            info!("Sending digest: {}", summary.message);
+            self.show(AlertLevel::Warning, &summary.message, &summary.details)?;
         }
+        Ok(())
+    }
 
+    /// Show a visual alert via Hammerspoon, falling back to terminal-notifier
+    pub fn show(&self, level: AlertLevel, message: &str, details: &[String]) -> Result<()> {
         if self.use_hammerspoon {
-            match self.send_hammerspoon(anomaly) {
+            match self.send_hammerspoon(level, message, details) {
                 Ok(()) => return Ok(()),
                 Err(e) => {
                     warn!("Hammerspoon notification failed: {}", e);
@@ -48,27 +87,27 @@ impl Notifier {
         }
 
         if self.fallback_to_terminal_notifier {
-            self.send_terminal_notifier(anomaly)?;
+            self.send_terminal_notifier(level, message, details)?;
         }
 
         Ok(())
     }
 
     /// Send notification via Hammerspoon's `hs` CLI
-    fn send_hammerspoon(&self, anomaly: &Anomaly) -> Result<()> {
-        let (icon, color, duration) = match anomaly.level {
+    fn send_hammerspoon(&self, level: AlertLevel, message: &str, details: &[String]) -> Result<()> {
+        let (icon, color, duration) = match level {
             AlertLevel::Warning => ("⚠️", &self.warning_color, 10),
             AlertLevel::Critical => ("🚨", &self.critical_color, 15),
         };
 
         // Format message with details
-        let details_str = if anomaly.details.is_empty() {
+        let details_str = if details.is_empty() {
             String::new()
         } else {
-            format!("\\n{}", anomaly.details.join("\\n"))
+            format!("\\n{}", details.join("\\n"))
         };
 
-        let message = format!("{} {}{}", icon, anomaly.message, details_str);
+        let message = format!("{} {}{}", icon, message, details_str);
 
         // Build Hammerspoon Lua command
         // Style matches existing TTS hotkeys alerts
@@ -107,13 +146,13 @@ impl Notifier {
     }
 
     /// Fallback to terminal-notifier
-    fn send_terminal_notifier(&self, anomaly: &Anomaly) -> Result<()> {
-        let title = match anomaly.level {
+    fn send_terminal_notifier(&self, level: AlertLevel, message: &str, details: &[String]) -> Result<()> {
+        let title = match level {
             AlertLevel::Warning => "System Sentinel Warning",
             AlertLevel::Critical => "System Sentinel CRITICAL",
         };
 
-        let message = format!("{}\n{}", anomaly.message, anomaly.details.join("\n"));
+        let message = format!("{}\n{}", message, details.join("\n"));
 
         let output = Command::new("terminal-notifier")
             .arg("-title")
@@ -133,4 +172,71 @@ impl Notifier {
             anyhow::bail!("terminal-notifier failed: {}", stderr)
         }
     }
+
+    /// POST the anomaly as JSON to a webhook (via curl)
+    pub fn send_webhook(&self, url: &str, anomaly: &Anomaly) -> Result<()> {
+        let payload = serde_json::json!({
+            "source": "system-sentinel",
+            "name": anomaly.name(),
+            "anomaly_type": anomaly.anomaly_type,
+            "level": anomaly.level,
+            "message": anomaly.message,
+            "details": anomaly.details,
+            "value": anomaly.value,
+            "started_at": anomaly.started_at,
+        });
+
+        let output = Command::new("curl")
+            .args(["-sS", "--fail", "--max-time", "10", "-X", "POST"])
+            .args(["-H", "Content-Type: application/json"])
+            .arg("--data")
+            .arg(payload.to_string())
+            .arg(url)
+            .output()
+            .context("Failed to execute curl")?;
+
+        if output.status.success() {
+            debug!("Webhook delivered to {}", url);
+            Ok(())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("Webhook failed: {}", stderr)
+        }
+    }
+
+    /// Send the anomaly by email via the local `sendmail`
+    pub fn send_email(&self, to: &str, anomaly: &Anomaly) -> Result<()> {
+        // Messages carry process names; a CR or LF in one would start a new header
+        let subject = anomaly.message.replace(['\r', '\n'], " ");
+        let body = format!(
+            "To: {}\nSubject: [System Sentinel] {}: {}\n\n{}\n{}\n\nStarted: {}\n",
+            to,
+            anomaly.level,
+            subject,
+            anomaly.message,
+            anomaly.details.join("\n"),
+            anomaly.started_at.format("%Y-%m-%d %H:%M:%S"),
+        );
+
+        let mut child = Command::new("sendmail")
+            .arg("-t")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to execute sendmail")?;
+
+        if let Some(stdin) = child.stdin.as_mut() {
+            stdin.write_all(body.as_bytes())?;
+        }
+
+        let output = child.wait_with_output()?;
+        if output.status.success() {
+            debug!("Email sent to {}", to);
+            Ok(())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("sendmail failed: {}", stderr)
+        }
+    }
 }
@@ -0,0 +1,53 @@
+//! Case-insensitive glob matching for process names and executable paths
+
+/// Match `text` against a glob `pattern` supporting `*` (any run of
+/// characters, including `/`) and `?` (any single character).
+/// Matching is case-insensitive and anchored at both ends.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern and the text index it matched up to
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` swallow one more character and retry
+                Some((star_p, star_t)) => {
+                    backtrack = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("Ghostty", "ghostty"));
+        assert!(!glob_match("Arc", "Archive Utility"));
+        assert!(glob_match("Arc*", "Archive Utility"));
+        assert!(glob_match("/Applications/*.app/*", "/Applications/Zed.app/Contents/MacOS/zed"));
+        assert!(glob_match("node?", "node2"));
+        assert!(!glob_match("node?", "node"));
+        assert!(glob_match("*", ""));
+    }
+}
@@ -0,0 +1,278 @@
+//! External check plugins following the Nagios plugin convention
+//!
+//! Each configured command runs on its own interval with a timeout. The exit
+//! code gives the state (0 OK, 1 WARNING, 2 CRITICAL, 3 or anything else
+//! UNKNOWN); the first line of output is the status text, optionally followed
+//! by `|` and performance data (`'label'=value[UOM];[warn];[crit];[min];[max]`).
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tracing::{debug, info};
+
+use crate::config::PluginConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginState {
+    Ok,
+    Warning,
+    Critical,
+    Unknown,
+}
+
+impl PluginState {
+    pub fn from_exit_code(code: Option<i32>) -> Self {
+        match code {
+            Some(0) => PluginState::Ok,
+            Some(1) => PluginState::Warning,
+            Some(2) => PluginState::Critical,
+            _ => PluginState::Unknown,
+        }
+    }
+}
+
+/// One performance data value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PerfData {
+    pub label: String,
+    pub value: f64,
+    /// Unit of measure as printed by the plugin ("s", "%", "B", "KB", "c", ...)
+    pub unit: String,
+    /// Threshold ranges are kept verbatim (e.g. "10:", "@5:10")
+    pub warn: Option<String>,
+    pub crit: Option<String>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+/// Latest outcome of one plugin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginResult {
+    pub name: String,
+    pub state: PluginState,
+    /// Status text from the first line of output
+    pub output: String,
+    pub perfdata: Vec<PerfData>,
+    pub checked_at: DateTime<Local>,
+    pub duration_ms: f64,
+}
+
+/// Runs the configured plugins in the background
+pub struct PluginRunner {
+    results: Arc<Mutex<BTreeMap<String, PluginResult>>>,
+    /// Results produced since the last `take_completed`
+    completed: Arc<Mutex<Vec<PluginResult>>>,
+}
+
+impl PluginRunner {
+    /// Start one task per plugin. Must be called within the tokio runtime.
+    pub fn spawn(plugins: &[PluginConfig]) -> Self {
+        let runner = Self {
+            results: Arc::new(Mutex::new(BTreeMap::new())),
+            completed: Arc::new(Mutex::new(Vec::new())),
+        };
+        if !plugins.is_empty() {
+            info!("Starting {} check plugin(s)", plugins.len());
+        }
+
+        for plugin in plugins.iter().cloned() {
+            let results = runner.results.clone();
+            let completed = runner.completed.clone();
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(Duration::from_secs(plugin.interval_seconds.max(1)));
+                ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                loop {
+                    ticker.tick().await;
+                    let result = run_plugin(&plugin).await;
+                    debug!("Plugin {}: {:?} {}", result.name, result.state, result.output);
+                    results.lock().unwrap().insert(result.name.clone(), result.clone());
+                    completed.lock().unwrap().push(result);
+                }
+            });
+        }
+
+        runner
+    }
+
+    /// Latest result of every plugin that has run at least once, by name
+    pub fn results(&self) -> Vec<PluginResult> {
+        self.results.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Results of runs finished since the previous call, oldest first
+    pub fn take_completed(&self) -> Vec<PluginResult> {
+        std::mem::take(&mut *self.completed.lock().unwrap())
+    }
+}
+
+async fn run_plugin(plugin: &PluginConfig) -> PluginResult {
+    let started = Instant::now();
+    let checked_at = Local::now();
+    let result = |state, output: String, perfdata| PluginResult {
+        name: plugin.name.clone(),
+        state,
+        output,
+        perfdata,
+        checked_at,
+        duration_ms: started.elapsed().as_secs_f64() * 1000.0,
+    };
+
+    let Some((program, args)) = plugin.command.split_first() else {
+        return result(PluginState::Unknown, "no command configured".to_string(), Vec::new());
+    };
+    let child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output();
+
+    let output = match tokio::time::timeout(Duration::from_secs(plugin.timeout_seconds), child).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => return result(PluginState::Unknown, format!("failed to run {}: {}", program, e), Vec::new()),
+        Err(_) => {
+            return result(PluginState::Unknown, format!("timed out after {}s", plugin.timeout_seconds), Vec::new());
+        }
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let first_line = match stdout.lines().next() {
+        Some(line) => line.to_string(),
+        // Some plugins only complain on stderr
+        None => String::from_utf8_lossy(&output.stderr).lines().next().unwrap_or("").to_string(),
+    };
+    let (text, perfdata) = parse_output(&first_line);
+    result(PluginState::from_exit_code(output.status.code()), text, perfdata)
+}
+
+/// Split a status line into its text and performance data
+pub fn parse_output(line: &str) -> (String, Vec<PerfData>) {
+    match line.split_once('|') {
+        Some((text, perf)) => (text.trim().to_string(), parse_perfdata(perf)),
+        None => (line.trim().to_string(), Vec::new()),
+    }
+}
+
+/// Parse `'label'=value[UOM];[warn];[crit];[min];[max]` items. Labels may be
+/// single-quoted to contain spaces (`''` is a literal quote). Malformed items
+/// and values of "U" (undetermined) are skipped.
+fn parse_perfdata(text: &str) -> Vec<PerfData> {
+    let mut items = Vec::new();
+    let mut rest = text.trim_start();
+
+    while !rest.is_empty() {
+        let label;
+        if let Some(quoted) = rest.strip_prefix('\'') {
+            let mut end = None;
+            let mut chars = quoted.char_indices().peekable();
+            let mut unescaped = String::new();
+            while let Some((i, c)) = chars.next() {
+                if c == '\'' {
+                    if chars.peek().is_some_and(|(_, next)| *next == '\'') {
+                        chars.next();
+                        unescaped.push('\'');
+                        continue;
+                    }
+                    end = Some(i + 1);
+                    break;
+                }
+                unescaped.push(c);
+            }
+            let Some(end) = end else { break };
+            label = unescaped;
+            rest = &quoted[end..];
+        } else {
+            let end = rest.find(['=', ' ']).unwrap_or(rest.len());
+            label = rest[..end].to_string();
+            rest = &rest[end..];
+        }
+
+        let Some(after_eq) = rest.strip_prefix('=') else {
+            // Not a perfdata item; skip to the next whitespace-separated token
+            rest = rest.find(' ').map_or("", |i| &rest[i..]).trim_start();
+            continue;
+        };
+        let end = after_eq.find(char::is_whitespace).unwrap_or(after_eq.len());
+        let spec = &after_eq[..end];
+        rest = after_eq[end..].trim_start();
+
+        let mut fields = spec.split(';');
+        let raw_value = fields.next().unwrap_or("");
+        let number_end = raw_value
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E')))
+            .unwrap_or(raw_value.len());
+        let Ok(value) = raw_value[..number_end].parse::<f64>() else { continue };
+
+        let optional = |s: Option<&str>| s.filter(|s| !s.is_empty()).map(str::to_string);
+        let warn = optional(fields.next());
+        let crit = optional(fields.next());
+        let min = fields.next().and_then(|s| s.parse().ok());
+        let max = fields.next().and_then(|s| s.parse().ok());
+
+        items.push(PerfData {
+            label,
+            value,
+            unit: raw_value[number_end..].to_string(),
+            warn,
+            crit,
+            min,
+            max,
+        });
+    }
+
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_output() {
+        let (text, perf) = parse_output("BACKUP OK - last run 3h ago | age=10800s;86400;172800;0 'free space'=41.5%;;10 bad=U");
+        assert_eq!(text, "BACKUP OK - last run 3h ago");
+        assert_eq!(perf.len(), 2);
+        assert_eq!(perf[0], PerfData {
+            label: "age".to_string(),
+            value: 10800.0,
+            unit: "s".to_string(),
+            warn: Some("86400".to_string()),
+            crit: Some("172800".to_string()),
+            min: Some(0.0),
+            max: None,
+        });
+        assert_eq!(perf[1].label, "free space");
+        assert_eq!(perf[1].unit, "%");
+        assert_eq!(perf[1].crit.as_deref(), Some("10"));
+
+        let (text, perf) = parse_output("CERT CRITICAL - expires in 2 days");
+        assert_eq!(text, "CERT CRITICAL - expires in 2 days");
+        assert!(perf.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_run_plugin() {
+        let plugin: PluginConfig = toml::from_str(
+            r#"
+            name = "disk"
+            command = ["sh", "-c", "echo 'DISK WARNING - 91% used | used=91%;90;95'; exit 1"]
+            "#,
+        )
+        .unwrap();
+        let result = run_plugin(&plugin).await;
+        assert_eq!(result.state, PluginState::Warning);
+        assert_eq!(result.output, "DISK WARNING - 91% used");
+        assert_eq!(result.perfdata[0].value, 91.0);
+
+        let slow = PluginConfig { command: vec!["sleep".into(), "5".into()], timeout_seconds: 1, ..plugin };
+        let result = run_plugin(&slow).await;
+        assert_eq!(result.state, PluginState::Unknown);
+        assert!(result.output.contains("timed out"));
+    }
+}
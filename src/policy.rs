@@ -0,0 +1,155 @@
+//! Safety policy for actions that signal or modify processes
+//!
+//! Every remediation that targets a PID (from the UI, the AI advisor, or the
+//! daemon itself) must pass `ProcessPolicy::check` first. A refusal always
+//! carries a `PolicyViolation` explaining which rule blocked the action.
+
+use serde::Serialize;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use thiserror::Error;
+
+use crate::config::PolicyConfig;
+use crate::metrics::extract_app_name;
+use crate::pattern::glob_match;
+
+/// A process that passed the policy checks
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessTarget {
+    pub pid: u32,
+    pub name: String,
+    pub exe: Option<String>,
+    pub memory_bytes: u64,
+}
+
+/// Why an action against a process was refused
+#[derive(Debug, Clone, Error)]
+pub enum PolicyViolation {
+    #[error("PID {0} is not running")]
+    NotFound(u32),
+    #[error("PID 1 is the init process and can never be targeted")]
+    InitProcess,
+    #[error("PID {0} is the sentinel daemon itself")]
+    Daemon(u32),
+    #[error("PID {0} is the process that requested the action")]
+    Requester(u32),
+    #[error("{name} (PID {pid}) matches protected pattern \"{pattern}\"")]
+    Protected { pid: u32, name: String, pattern: String },
+    #[error("{name} (PID {pid}) is owned by another user")]
+    OtherUser { pid: u32, name: String },
+    #[error("could not determine the owner of {name} (PID {pid})")]
+    UnknownOwner { pid: u32, name: String },
+    #[error("{name} (PID {pid}) is a session leader; signalling it would take down its whole session")]
+    SessionLeader { pid: u32, name: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessPolicy {
+    protected: Vec<String>,
+    own_pid: u32,
+}
+
+impl ProcessPolicy {
+    pub fn new(config: &PolicyConfig) -> Self {
+        Self {
+            protected: config.protected_processes.clone(),
+            own_pid: std::process::id(),
+        }
+    }
+
+    /// Resolve `pid` and decide whether it may be signalled.
+    /// `requester_pid` is the PID of the client asking (e.g. the UI), which is never a valid target.
+    pub fn check(&self, pid: u32, requester_pid: Option<u32>) -> Result<ProcessTarget, PolicyViolation> {
+        if pid == 1 {
+            return Err(PolicyViolation::InitProcess);
+        }
+        if pid == self.own_pid {
+            return Err(PolicyViolation::Daemon(pid));
+        }
+        if requester_pid == Some(pid) {
+            return Err(PolicyViolation::Requester(pid));
+        }
+
+        let target = Pid::from_u32(pid);
+        let own = Pid::from_u32(self.own_pid);
+        let mut system = System::new();
+        system.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[target, own]),
+            true,
+            ProcessRefreshKind::everything(),
+        );
+
+        let process = system.process(target).ok_or(PolicyViolation::NotFound(pid))?;
+        let name = process.name().to_string_lossy().to_string();
+        let exe = process.exe().map(|p| p.to_string_lossy().to_string());
+
+        if let Some(pattern) = self.protected_match(&name, exe.as_deref()) {
+            return Err(PolicyViolation::Protected { pid, name, pattern: pattern.to_string() });
+        }
+
+        let own_user = system.process(own).and_then(|p| p.user_id());
+        match (process.user_id(), own_user) {
+            (Some(owner), Some(me)) if owner == me => {}
+            (Some(_), Some(_)) => return Err(PolicyViolation::OtherUser { pid, name }),
+            _ => return Err(PolicyViolation::UnknownOwner { pid, name }),
+        }
+
+        if process.session_id() == Some(target) {
+            return Err(PolicyViolation::SessionLeader { pid, name });
+        }
+
+        Ok(ProcessTarget {
+            pid,
+            name,
+            exe,
+            memory_bytes: process.memory(),
+        })
+    }
+
+    /// First protected pattern matching the process name, executable path,
+    /// executable file name, or app bundle name
+    fn protected_match(&self, name: &str, exe: Option<&str>) -> Option<&str> {
+        let mut candidates = vec![name.to_string()];
+        if let Some(exe) = exe {
+            candidates.push(exe.to_string());
+            if let Some(file) = std::path::Path::new(exe).file_name() {
+                candidates.push(file.to_string_lossy().to_string());
+            }
+            if let Some(app) = extract_app_name(exe) {
+                candidates.push(app);
+            }
+        }
+
+        self.protected
+            .iter()
+            .find(|pattern| candidates.iter().any(|c| glob_match(pattern, c)))
+            .map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> ProcessPolicy {
+        ProcessPolicy::new(&PolicyConfig::default())
+    }
+
+    #[test]
+    fn test_refuses_init_and_self() {
+        let p = policy();
+        assert!(matches!(p.check(1, None), Err(PolicyViolation::InitProcess)));
+        assert!(matches!(p.check(std::process::id(), None), Err(PolicyViolation::Daemon(_))));
+        assert!(matches!(p.check(4242, Some(4242)), Err(PolicyViolation::Requester(4242))));
+    }
+
+    #[test]
+    fn test_protected_patterns() {
+        let p = policy();
+        assert_eq!(p.protected_match("ghostty", None), Some("Ghostty"));
+        assert_eq!(
+            p.protected_match("Code Helper (Renderer)", Some("/Applications/Visual Studio Code.app/Contents/Frameworks/Code Helper")),
+            Some("Visual Studio Code")
+        );
+        assert_eq!(p.protected_match("node", Some("/usr/local/bin/node")), None);
+    }
+}
@@ -0,0 +1,254 @@
+//! Local service health probes
+//!
+//! Each configured probe runs in its own task on its own interval: connect
+//! (TCP, Unix socket, or plain HTTP), optionally send a payload, and check the
+//! response. The latest result of every probe is shared with the main loop,
+//! which copies it into `SystemMetrics::probes` for the detector and clients.
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, UnixStream};
+use tracing::{debug, info};
+
+use crate::config::{ProbeConfig, ProbeKind};
+
+/// Responses are read up to this size; enough for any health endpoint
+const MAX_RESPONSE_BYTES: u64 = 64 * 1024;
+
+/// Latest outcome of one probe
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbeResult {
+    pub name: String,
+    pub kind: ProbeKind,
+    pub target: String,
+    pub ok: bool,
+    /// Time to a complete answer, also reported for failed expectations
+    pub latency_ms: Option<f64>,
+    pub error: Option<String>,
+    pub checked_at: DateTime<Local>,
+    pub consecutive_failures: u32,
+    pub consecutive_successes: u32,
+}
+
+/// Runs the configured probes in the background
+pub struct ProbeRunner {
+    results: Arc<Mutex<BTreeMap<String, ProbeResult>>>,
+}
+
+impl ProbeRunner {
+    /// Start one task per probe. Must be called within the tokio runtime.
+    pub fn spawn(probes: &[ProbeConfig]) -> Self {
+        let results: Arc<Mutex<BTreeMap<String, ProbeResult>>> = Arc::new(Mutex::new(BTreeMap::new()));
+        if !probes.is_empty() {
+            info!("Starting {} service probe(s)", probes.len());
+        }
+
+        for probe in probes.iter().cloned() {
+            let results = results.clone();
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(Duration::from_secs(probe.interval_seconds.max(1)));
+                ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                loop {
+                    ticker.tick().await;
+                    let (latency_ms, error) = match run_probe(&probe).await {
+                        Ok(latency) => (Some(latency), None),
+                        Err(ProbeError { latency_ms, message }) => (latency_ms, Some(message)),
+                    };
+                    debug!("Probe {}: latency={:?} error={:?}", probe.name, latency_ms, error);
+
+                    let mut results = results.lock().unwrap();
+                    let previous = results.get(&probe.name);
+                    let ok = error.is_none();
+                    let result = ProbeResult {
+                        name: probe.name.clone(),
+                        kind: probe.kind,
+                        target: probe.target.clone(),
+                        ok,
+                        latency_ms,
+                        error,
+                        checked_at: Local::now(),
+                        consecutive_failures: if ok { 0 } else { previous.map_or(0, |p| p.consecutive_failures) + 1 },
+                        consecutive_successes: if ok { previous.map_or(0, |p| p.consecutive_successes) + 1 } else { 0 },
+                    };
+                    results.insert(probe.name.clone(), result);
+                }
+            });
+        }
+
+        Self { results }
+    }
+
+    /// Latest result of every probe that has run at least once, by name
+    pub fn results(&self) -> Vec<ProbeResult> {
+        self.results.lock().unwrap().values().cloned().collect()
+    }
+}
+
+#[derive(Debug)]
+struct ProbeError {
+    latency_ms: Option<f64>,
+    message: String,
+}
+
+impl ProbeError {
+    fn new(message: impl Into<String>) -> Self {
+        Self { latency_ms: None, message: message.into() }
+    }
+}
+
+/// Run a probe once; Ok holds the latency in milliseconds
+async fn run_probe(probe: &ProbeConfig) -> Result<f64, ProbeError> {
+    let started = Instant::now();
+    let timeout = Duration::from_millis(probe.timeout_ms);
+
+    let response = match tokio::time::timeout(timeout, exchange_for(probe)).await {
+        Ok(result) => result?,
+        Err(_) => return Err(ProbeError::new(format!("no answer within {}ms", probe.timeout_ms))),
+    };
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+    let fail = |message: String| ProbeError { latency_ms: Some(latency_ms), message };
+
+    let body = match probe.kind {
+        ProbeKind::Http => {
+            let (status, body) = parse_http_response(&response).ok_or_else(|| fail("malformed HTTP response".to_string()))?;
+            let healthy = match probe.expect_status {
+                Some(expected) => status == expected,
+                None => (200..400).contains(&status),
+            };
+            if !healthy {
+                return Err(fail(format!("HTTP {}", status)));
+            }
+            body
+        }
+        ProbeKind::Tcp | ProbeKind::Unix => response.as_str(),
+    };
+
+    if let Some(expected) = &probe.expect {
+        if !body.contains(expected.as_str()) {
+            return Err(fail(format!("response does not contain \"{}\"", expected)));
+        }
+    }
+    if let Some(max) = probe.max_latency_ms {
+        if latency_ms > max as f64 {
+            return Err(fail(format!("answered in {:.0}ms, limit {}ms", latency_ms, max)));
+        }
+    }
+
+    Ok(latency_ms)
+}
+
+/// Connect and exchange data as the probe kind requires; returns the raw response
+async fn exchange_for(probe: &ProbeConfig) -> Result<String, ProbeError> {
+    let connect_error = |e: std::io::Error| ProbeError::new(format!("connect to {} failed: {}", probe.target, e));
+    let read = probe.expect.is_some();
+
+    match probe.kind {
+        ProbeKind::Tcp => {
+            let stream = TcpStream::connect(&probe.target).await.map_err(connect_error)?;
+            exchange(stream, probe.send.as_deref(), read).await
+        }
+        ProbeKind::Unix => {
+            let stream = UnixStream::connect(&probe.target).await.map_err(connect_error)?;
+            exchange(stream, probe.send.as_deref(), read).await
+        }
+        ProbeKind::Http => {
+            let (host, path) = parse_http_url(&probe.target).map_err(ProbeError::new)?;
+            let stream = TcpStream::connect(&host).await.map_err(connect_error)?;
+            let request = format!(
+                "GET {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: system-sentinel\r\nConnection: close\r\n\r\n",
+                path, host
+            );
+            exchange(stream, Some(&request), true).await
+        }
+    }
+}
+
+async fn exchange<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S, send: Option<&str>, read: bool) -> Result<String, ProbeError> {
+    let io_error = |e: std::io::Error| ProbeError::new(e.to_string());
+    if let Some(payload) = send {
+        stream.write_all(payload.as_bytes()).await.map_err(io_error)?;
+        stream.flush().await.map_err(io_error)?;
+    }
+    if !read {
+        return Ok(String::new());
+    }
+
+    let mut response = Vec::new();
+    (&mut stream).take(MAX_RESPONSE_BYTES).read_to_end(&mut response).await.map_err(io_error)?;
+    Ok(String::from_utf8_lossy(&response).into_owned())
+}
+
+/// "http://host:port/path" -> ("host:port", "/path"). Only plain HTTP is
+/// supported; probe an HTTPS port with `kind = "tcp"` instead.
+fn parse_http_url(url: &str) -> Result<(String, String), String> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| format!("unsupported URL {:?}: only http:// is supported", url))?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let host = if authority.contains(':') { authority.to_string() } else { format!("{}:80", authority) };
+    Ok((host, path.to_string()))
+}
+
+/// Status code and body of an HTTP/1.x response
+fn parse_http_response(response: &str) -> Option<(u16, &str)> {
+    let status = response.lines().next()?.split_whitespace().nth(1)?.parse().ok()?;
+    let body = response.split_once("\r\n\r\n").map_or("", |(_, body)| body);
+    Some((status, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn probe(kind: &str, target: String) -> ProbeConfig {
+        toml::from_str(&format!("name = \"test\"\nkind = \"{}\"\ntarget = \"{}\"", kind, target)).unwrap()
+    }
+
+    /// Serve one canned HTTP response per connection
+    async fn http_server(response: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0u8; 1024];
+                let _ = socket.read(&mut buf).await;
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_http_probe() {
+        let addr = http_server("HTTP/1.1 200 OK\r\nContent-Length: 9\r\n\r\n{\"ok\":true}").await;
+        let mut p = probe("http", format!("http://{}/health", addr));
+        assert!(run_probe(&p).await.is_ok());
+
+        p.expect = Some("\"ok\":true".to_string());
+        assert!(run_probe(&p).await.is_ok());
+        p.expect = Some("ready".to_string());
+        assert!(run_probe(&p).await.unwrap_err().message.contains("does not contain"));
+
+        let addr = http_server("HTTP/1.1 503 Service Unavailable\r\n\r\n").await;
+        let p = probe("http", format!("http://{}/health", addr));
+        let err = run_probe(&p).await.unwrap_err();
+        assert_eq!(err.message, "HTTP 503");
+        assert!(err.latency_ms.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_tcp_probe_refused() {
+        // Bind and drop to find a port nobody listens on
+        let addr = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().to_string();
+        let p = probe("tcp", addr);
+        assert!(run_probe(&p).await.unwrap_err().message.contains("connect"));
+    }
+}
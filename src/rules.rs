@@ -0,0 +1,460 @@
+//! Declarative threshold rules
+//!
+//! One engine evaluates every `[[rules]]` entry together with the built-in
+//! memory, swap, load and memory-growth checks, which are default rules built
+//! from `[thresholds]`. A rule reads one metric path and compares it with its
+//! warning and critical thresholds in its direction. Once alerting, the
+//! thresholds are relaxed by the recovery margin (hysteresis). A breach must
+//! last `sustain_seconds` before the rule fires. A rule may also have
+//! correlation gates, conditions on other metrics that must hold for a breach
+//! to count; a breach held back by a gate is reported as a suppression.
+//! Inhibition between rules (`inhibited_by`) is applied by the detector.
+//!
+//! Metric paths walk the serialized `SystemMetrics`: `load_5m`,
+//! `disks./.used_percent`, `group."Arc".memory_mb`. On a list, a segment
+//! selects the element whose name, mount point, path or label equals it
+//! (case-insensitively), or the element at that index. Quote segments that
+//! contain dots. `group` and `process` are short for `aggregated_processes`
+//! and `top_processes`.
+
+use chrono::{DateTime, Local};
+use serde_json::Value;
+use std::collections::HashMap;
+use tracing::debug;
+
+use crate::config::{Config, RuleCondition, RuleConfig, RuleCulprit, RuleDirection, RuleGate};
+use crate::detector::{AlertLevel, AnomalyType, Suppression};
+use crate::metrics::SystemMetrics;
+
+/// A rule that fired this tick
+#[derive(Debug, Clone)]
+pub struct RuleHit {
+    pub rule: String,
+    pub anomaly_type: AnomalyType,
+    pub level: AlertLevel,
+    pub value: f64,
+    /// The nominal threshold of `level` (before hysteresis)
+    pub threshold: f64,
+    pub direction: RuleDirection,
+    pub metric: String,
+    pub culprit: RuleCulprit,
+    pub message: Option<String>,
+    pub narration: Option<String>,
+}
+
+struct Rule {
+    config: RuleConfig,
+    anomaly_type: AnomalyType,
+    path: Vec<String>,
+    /// Every gate must hold; a gate holds when any of its conditions does
+    gates: Vec<Vec<Condition>>,
+}
+
+struct Condition {
+    config: RuleCondition,
+    path: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct RuleState {
+    /// Level the rule is alerting at, which relaxes its thresholds
+    active: Option<AlertLevel>,
+    /// Start of the current breach, for `sustain_seconds`
+    breaching_since: Option<DateTime<Local>>,
+}
+
+pub struct RuleEngine {
+    rules: Vec<Rule>,
+    state: HashMap<String, RuleState>,
+    /// Breaches held back by a gate in the last evaluation
+    suppressed: Vec<Suppression>,
+}
+
+impl RuleEngine {
+    /// Built-in rules from `[thresholds]`, then `[[rules]]`. A configured rule
+    /// named like a built-in one ("memory", "swap", "load", "memory_growth")
+    /// replaces it but keeps its anomaly type, and every option it leaves
+    /// unset (per_core, sustain_seconds, recovery_margin, inhibited_by, gates,
+    /// culprit).
+    pub fn new(config: &Config) -> Self {
+        let mut rules = builtin_rules(config);
+        for rule in &config.rules {
+            match rules.iter_mut().find(|r| r.config.name == rule.name) {
+                Some(builtin) => {
+                    let base = &builtin.config;
+                    let replacement = RuleConfig {
+                        per_core: rule.per_core.or(base.per_core),
+                        sustain_seconds: rule.sustain_seconds.or(base.sustain_seconds),
+                        recovery_margin: rule.recovery_margin.or(base.recovery_margin),
+                        inhibited_by: rule.inhibited_by.clone().or_else(|| base.inhibited_by.clone()),
+                        gates: rule.gates.clone().or_else(|| base.gates.clone()),
+                        culprit: rule.culprit.or(base.culprit),
+                        ..rule.clone()
+                    };
+                    *builtin = Rule::new(replacement, builtin.anomaly_type);
+                }
+                None => rules.push(Rule::new(rule.clone(), AnomalyType::Rule)),
+            }
+        }
+        Self { rules, state: HashMap::new(), suppressed: Vec::new() }
+    }
+
+    /// `(source, target)` pairs from the rules' `inhibited_by` lists
+    pub fn inhibitions(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.rules
+            .iter()
+            .flat_map(|r| r.config.inhibited_by.iter().flatten().map(move |source| (source.as_str(), r.config.name.as_str())))
+    }
+
+    /// Breaches held back by a gate in the last evaluation
+    pub fn suppressed(&self) -> &[Suppression] {
+        &self.suppressed
+    }
+
+    /// Rules firing for these metrics, in configuration order
+    pub fn evaluate(&mut self, metrics: &SystemMetrics) -> Vec<RuleHit> {
+        let json = serde_json::to_value(metrics).unwrap_or(Value::Null);
+        let mut hits = Vec::new();
+        self.suppressed.clear();
+
+        for rule in &self.rules {
+            let state = self.state.entry(rule.config.name.clone()).or_default();
+            let breach = resolve(&json, &rule.path)
+                .map(|value| if rule.config.per_core == Some(true) { value / metrics.logical_cores.max(1) as f64 } else { value })
+                .and_then(|value| rule.level_for(value, state.active).map(|(level, threshold)| (value, level, threshold)));
+
+            let Some((value, level, threshold)) = breach else {
+                *state = RuleState::default();
+                continue;
+            };
+            if let Some(reason) = rule.closed_gate(&json) {
+                debug!("Rule {} breached but gated: {}", rule.config.name, reason);
+                self.suppressed.push(Suppression { alert: rule.config.name.clone(), level, reason });
+                *state = RuleState::default();
+                continue;
+            }
+
+            let since = *state.breaching_since.get_or_insert(metrics.timestamp);
+            if metrics.timestamp - since < chrono::Duration::seconds(rule.config.sustain_seconds.unwrap_or(0) as i64) {
+                debug!("Rule {} breached, sustaining since {}", rule.config.name, since.format("%H:%M:%S"));
+                continue;
+            }
+            state.active = Some(level);
+
+            hits.push(RuleHit {
+                rule: rule.config.name.clone(),
+                anomaly_type: rule.anomaly_type,
+                level,
+                value,
+                threshold,
+                direction: rule.config.direction,
+                metric: rule.config.metric.clone(),
+                culprit: rule.config.culprit.unwrap_or_default(),
+                message: rule.config.message.clone(),
+                narration: rule.config.narration.clone(),
+            });
+        }
+
+        hits
+    }
+}
+
+impl Rule {
+    fn new(config: RuleConfig, anomaly_type: AnomalyType) -> Self {
+        let condition = |c: &RuleCondition| Condition { path: parse_path(&c.metric), config: c.clone() };
+        let gates = config
+            .gates
+            .iter()
+            .flatten()
+            .map(|gate| match gate {
+                RuleGate::Any { any } => any.iter().map(condition).collect(),
+                RuleGate::Condition(c) => vec![condition(c)],
+            })
+            .collect();
+        Self { path: parse_path(&config.metric), config, anomaly_type, gates }
+    }
+
+    /// Why the first gate that does not hold is closed
+    fn closed_gate(&self, json: &Value) -> Option<String> {
+        let gate = self.gates.iter().find(|gate| !gate.iter().any(|c| c.holds(json)))?;
+        let conditions: Vec<String> = gate.iter().map(|c| c.describe(json)).collect();
+        Some(format!("needs {}", conditions.join(" or ")))
+    }
+
+    /// Level and nominal threshold for `value`, with hysteresis while `active`
+    fn level_for(&self, value: f64, active: Option<AlertLevel>) -> Option<(AlertLevel, f64)> {
+        let c = &self.config;
+        let margin = c.recovery_margin.unwrap_or(0.0);
+        let (relax, beyond): (f64, fn(f64, f64) -> bool) = match c.direction {
+            RuleDirection::Above => (-margin, |v, t| v >= t),
+            RuleDirection::Below => (margin, |v, t| v <= t),
+        };
+        let (warning_relax, critical_relax) = match active {
+            Some(AlertLevel::Critical) => (relax, relax),
+            Some(AlertLevel::Warning) => (relax, 0.0),
+            None => (0.0, 0.0),
+        };
+
+        if let Some(critical) = c.critical.filter(|&t| beyond(value, t + critical_relax)) {
+            Some((AlertLevel::Critical, critical))
+        } else {
+            c.warning
+                .filter(|&t| beyond(value, t + warning_relax))
+                .map(|warning| (AlertLevel::Warning, warning))
+        }
+    }
+}
+
+impl Condition {
+    fn holds(&self, json: &Value) -> bool {
+        resolve(json, &self.path).is_some_and(|value| {
+            self.config.above.is_none_or(|above| value > above) && self.config.below.is_none_or(|below| value < below)
+        })
+    }
+
+    /// "memory_percent > 80 (is 70.0)"
+    fn describe(&self, json: &Value) -> String {
+        let mut bounds = Vec::new();
+        if let Some(above) = self.config.above {
+            bounds.push(format!("> {}", above));
+        }
+        if let Some(below) = self.config.below {
+            bounds.push(format!("< {}", below));
+        }
+        let current = resolve(json, &self.path).map_or_else(|| "missing".to_string(), |v| format!("is {:.1}", v));
+        format!("{} {} ({})", self.config.metric, bounds.join(" and "), current)
+    }
+}
+
+fn builtin_rules(config: &Config) -> Vec<Rule> {
+    let t = &config.thresholds;
+    let rule = |name: &str, anomaly_type, metric: &str, warning, critical, recovery_margin, culprit, gates: Vec<RuleGate>| {
+        let config = RuleConfig {
+            name: name.to_string(),
+            metric: metric.to_string(),
+            warning: Some(warning),
+            critical: Some(critical),
+            direction: RuleDirection::Above,
+            per_core: Some(false),
+            sustain_seconds: Some(0),
+            recovery_margin: Some(recovery_margin),
+            inhibited_by: Some(Vec::new()),
+            gates: Some(gates),
+            culprit: Some(culprit),
+            message: None,
+            narration: None,
+        };
+        Rule::new(config, anomaly_type)
+    };
+    let above = |metric: &str, above: f64| RuleCondition { metric: metric.to_string(), above: Some(above), below: None };
+
+    let memory = rule("memory", AnomalyType::Memory, "memory_percent", t.memory_warning, t.memory_critical, t.recovery_margin, RuleCulprit::Memory, vec![]);
+    // Swap alone is not a problem: macOS swaps opportunistically while memory
+    // pressure is low
+    let swap_gates = vec![RuleGate::Condition(above("swap_total", 0.0)), RuleGate::Condition(above("memory_percent", 80.0))];
+    let swap = rule("swap", AnomalyType::Swap, "swap_percent", t.swap_warning, t.swap_critical, t.recovery_margin, RuleCulprit::Memory, swap_gates);
+    let mut load = rule("load", AnomalyType::Load, "load_1m", t.load_warning, t.load_critical, 1.0, RuleCulprit::Cpu, vec![]);
+    load.config.sustain_seconds = Some(120);
+    if t.load_per_core_warning.is_some() || t.load_per_core_critical.is_some() {
+        load.config.per_core = Some(true);
+        load.config.warning = t.load_per_core_warning;
+        load.config.critical = t.load_per_core_critical;
+        load.config.recovery_margin = Some(0.1);
+    }
+    // Growth matters only when memory use is significant and the system is
+    // under pressure, unless it is so fast that it is an emergency anyway
+    let growth_gates = vec![
+        RuleGate::Condition(above("memory_percent", 60.0)),
+        RuleGate::Any {
+            any: vec![above("memory_percent", 80.0), above("swap_percent", 80.0), above("memory_growth_rate", 10.0)],
+        },
+    ];
+    let mut growth = rule(
+        "memory_growth",
+        AnomalyType::MemoryGrowthRate,
+        "memory_growth_rate",
+        t.memory_growth_rate_warning,
+        t.memory_growth_rate_critical,
+        0.0,
+        RuleCulprit::Memory,
+        growth_gates,
+    );
+    // A Memory alert already covers it; growth on top is noise
+    growth.config.inhibited_by = Some(vec!["memory".to_string()]);
+
+    vec![memory, swap, load, growth]
+}
+
+/// Split a metric path on dots; double-quoted segments may contain dots
+pub fn parse_path(path: &str) -> Vec<String> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in path.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '.' if !quoted => segments.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    segments.push(current);
+    segments
+}
+
+/// Numeric value at `path`; booleans count as 1 and 0
+fn resolve(root: &Value, path: &[String]) -> Option<f64> {
+    let mut current = root;
+    for (i, segment) in path.iter().enumerate() {
+        let key = match (i, segment.as_str()) {
+            (0, "group") => "aggregated_processes",
+            (0, "process") => "top_processes",
+            _ => segment,
+        };
+        current = match current {
+            Value::Object(map) => map.get(key)?,
+            Value::Array(items) => select(items, segment)?,
+            _ => return None,
+        };
+    }
+    match current {
+        Value::Bool(b) => Some(f64::from(u8::from(*b))),
+        other => other.as_f64(),
+    }
+}
+
+/// The list element identified by `segment`
+fn select<'a>(items: &'a [Value], segment: &str) -> Option<&'a Value> {
+    let group = format!("{} (Group)", segment);
+    items
+        .iter()
+        .find(|item| {
+            ["name", "mount_point", "path", "label"].iter().any(|key| {
+                item.get(key)
+                    .and_then(Value::as_str)
+                    .is_some_and(|s| s.eq_ignore_ascii_case(segment) || s.eq_ignore_ascii_case(&group))
+            })
+        })
+        .or_else(|| segment.parse::<usize>().ok().and_then(|i| items.get(i)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{DiskInfo, ProcessInfo};
+
+    fn metrics() -> SystemMetrics {
+        SystemMetrics {
+            timestamp: Local::now(),
+            load_5m: 3.5,
+            disks: vec![DiskInfo {
+                name: "Macintosh HD".to_string(),
+                mount_point: "/".to_string(),
+                file_system: "apfs".to_string(),
+                total_bytes: 100,
+                available_bytes: 8,
+                used_percent: 92.0,
+            }],
+            aggregated_processes: vec![ProcessInfo {
+                pid: 0,
+                parent_pid: None,
+                name: "Arc (Group)".to_string(),
+                memory_bytes: 0,
+                memory_mb: 4096.0,
+                cpu_usage: 0.0,
+                exe: None,
+                memory_growth_rate: None,
+                open_fds: None,
+                threads: None,
+                fd_growth_rate: None,
+                thread_growth_rate: None,
+            }],
+            ..SystemMetrics::default()
+        }
+    }
+
+    #[test]
+    fn test_resolve_paths() {
+        let json = serde_json::to_value(metrics()).unwrap();
+        let get = |path: &str| resolve(&json, &parse_path(path));
+        assert_eq!(get("load_5m"), Some(3.5));
+        assert_eq!(get("disks./.used_percent"), Some(92.0));
+        assert_eq!(get("disks.0.available_bytes"), Some(8.0));
+        assert_eq!(get("group.\"Arc\".memory_mb"), Some(4096.0));
+        assert_eq!(get("group.Safari.memory_mb"), None);
+        assert_eq!(parse_path("cgroups.\"app.slice\".current_bytes"), vec!["cgroups", "app.slice", "current_bytes"]);
+    }
+
+    #[test]
+    fn test_direction_sustain_and_hysteresis() {
+        let config: Config = toml::from_str(
+            r#"
+            [[rules]]
+            name = "disk_free"
+            metric = "disks./.available_bytes"
+            warning = 10
+            critical = 5
+            direction = "below"
+            sustain_seconds = 60
+            recovery_margin = 5
+            "#,
+        )
+        .unwrap();
+        let mut engine = RuleEngine::new(&config);
+        let fired = |engine: &mut RuleEngine, m: &SystemMetrics| {
+            engine.evaluate(m).into_iter().filter(|h| h.rule == "disk_free").map(|h| h.level).next()
+        };
+
+        let mut m = metrics();
+        assert_eq!(fired(&mut engine, &m), None, "not sustained yet");
+        m.timestamp += chrono::Duration::seconds(60);
+        assert_eq!(fired(&mut engine, &m), Some(AlertLevel::Warning));
+
+        // Back above the warning threshold but within the recovery margin
+        m.disks[0].available_bytes = 12;
+        assert_eq!(fired(&mut engine, &m), Some(AlertLevel::Warning));
+        m.disks[0].available_bytes = 16;
+        assert_eq!(fired(&mut engine, &m), None);
+    }
+
+    #[test]
+    fn test_builtin_override_keeps_unset_options() {
+        let config: Config = toml::from_str(
+            r#"
+            [thresholds]
+            load_per_core_warning = 1.5
+
+            [[rules]]
+            name = "memory_growth"
+            metric = "memory_growth_rate"
+            warning = 3
+
+            [[rules]]
+            name = "load"
+            metric = "load_1m"
+            warning = 2.0
+
+            [[rules]]
+            name = "swap"
+            metric = "swap_percent"
+            warning = 50
+            inhibited_by = ["memory"]
+            gates = []
+            "#,
+        )
+        .unwrap();
+        let engine = RuleEngine::new(&config);
+        let rule = |name: &str| &engine.rules.iter().find(|r| r.config.name == name).unwrap().config;
+
+        let inhibitions: Vec<_> = engine.inhibitions().collect();
+        assert_eq!(inhibitions, vec![("memory", "swap"), ("memory", "memory_growth")]);
+        assert_eq!(rule("memory_growth").warning, Some(3.0));
+        assert_eq!(rule("memory_growth").culprit, Some(RuleCulprit::Memory));
+        assert!(rule("memory_growth").gates.as_ref().is_some_and(|g| !g.is_empty()));
+
+        assert_eq!(rule("load").sustain_seconds, Some(120));
+        assert_eq!(rule("load").per_core, Some(true));
+        assert_eq!(rule("load").recovery_margin, Some(0.1));
+        assert_eq!(rule("load").culprit, Some(RuleCulprit::Cpu));
+        assert_eq!(rule("swap").gates, Some(vec![]));
+    }
+}
@@ -0,0 +1,313 @@
+//! Custom detection rules written in Rhai
+//!
+//! Every configured script runs once per tick with `metrics` (the current
+//! `SystemMetrics` as a map) in scope, and `history(n)` returning the metrics
+//! of `n` ticks ago (`()` when not available yet; `history_len()` tells how far
+//! back it goes). A script returns `()`, one alert map, or an array of them:
+//!
+//! ```text
+//! #{ level: "warning", message: "Docker VM 12GB", narration: "Docker is big.",
+//!    type: "docker_vm", value: 12.0, details: ["..."] }
+//! ```
+//!
+//! Only `level` and `message` are required; `type` defaults to the script's
+//! file name. Rhai has no file, network or process access, and each run is
+//! capped in operations and wall time. Errors are logged and kept in the
+//! script's status; they never stop the daemon or the other scripts.
+
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use crate::config::{expand_tilde, ScriptingConfig};
+use crate::detector::AlertLevel;
+use crate::metrics::SystemMetrics;
+
+/// How often (in operations) the wall-time limit is checked
+const DEADLINE_CHECK_INTERVAL: u64 = 256;
+
+/// An alert returned by a script
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptAlert {
+    /// Custom anomaly type name
+    pub name: String,
+    pub level: AlertLevel,
+    pub message: String,
+    pub narration: String,
+    pub details: Vec<String>,
+    pub value: f64,
+}
+
+/// Health of one script, reported by the `status` IPC command
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptStatus {
+    pub name: String,
+    pub path: String,
+    pub runs: u64,
+    pub errors: u64,
+    pub last_error: Option<String>,
+    pub last_duration_ms: f64,
+}
+
+struct Script {
+    ast: Option<AST>,
+    status: ScriptStatus,
+}
+
+pub struct ScriptEngine {
+    engine: Engine,
+    scripts: Vec<Script>,
+    /// Past metrics as script values, newest last
+    history: Arc<Mutex<VecDeque<Dynamic>>>,
+    history_len: usize,
+    timeout: Duration,
+    /// Wall-time deadline of the script currently running
+    deadline: Arc<Mutex<Instant>>,
+}
+
+impl ScriptEngine {
+    pub fn new(config: &ScriptingConfig) -> Self {
+        let mut engine = Self::empty(config);
+        for path in &config.scripts {
+            let full = expand_tilde(path);
+            let name = full.file_stem().map_or_else(|| path.clone(), |s| s.to_string_lossy().to_string());
+            let source = std::fs::read_to_string(&full).map_err(|e| format!("Failed to read {:?}: {}", full, e));
+            engine.add(&name, &full.to_string_lossy(), source);
+        }
+        if !engine.scripts.is_empty() {
+            info!("Loaded {} detection script(s)", engine.scripts.len());
+        }
+        engine
+    }
+
+    fn empty(config: &ScriptingConfig) -> Self {
+        let history: Arc<Mutex<VecDeque<Dynamic>>> = Arc::new(Mutex::new(VecDeque::new()));
+        let deadline = Arc::new(Mutex::new(Instant::now()));
+
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(config.max_operations)
+            .set_max_call_levels(32)
+            .set_max_expr_depths(64, 32)
+            .set_max_string_size(64 * 1024)
+            .set_max_array_size(10_000)
+            .set_max_map_size(10_000);
+
+        let limit = deadline.clone();
+        engine.on_progress(move |ops| {
+            (ops % DEADLINE_CHECK_INTERVAL == 0 && Instant::now() > *limit.lock().unwrap())
+                .then(|| Dynamic::from("time limit exceeded"))
+        });
+        engine.on_print(|text| debug!("script: {}", text));
+        engine.on_debug(|text, source, pos| debug!("script {:?} {}: {}", source, pos, text));
+
+        let past = history.clone();
+        engine.register_fn("history", move |n: i64| -> Dynamic {
+            let past = past.lock().unwrap();
+            match usize::try_from(n) {
+                Ok(n) if n >= 1 && n <= past.len() => past[past.len() - n].clone(),
+                _ => Dynamic::UNIT,
+            }
+        });
+        let past = history.clone();
+        engine.register_fn("history_len", move || past.lock().unwrap().len() as i64);
+
+        Self {
+            engine,
+            scripts: Vec::new(),
+            history,
+            history_len: config.history_len,
+            timeout: Duration::from_millis(config.timeout_ms),
+            deadline,
+        }
+    }
+
+    fn add(&mut self, name: &str, path: &str, source: Result<String, String>) {
+        let compiled = source.and_then(|s| self.engine.compile(&s).map_err(|e| format!("Compile error: {}", e)));
+        if let Err(e) = &compiled {
+            warn!("Detection script {} disabled: {}", path, e);
+        }
+        self.scripts.push(Script {
+            status: ScriptStatus {
+                name: name.to_string(),
+                path: path.to_string(),
+                runs: 0,
+                errors: u64::from(compiled.is_err()),
+                last_error: compiled.as_ref().err().cloned(),
+                last_duration_ms: 0.0,
+            },
+            ast: compiled.ok(),
+        });
+    }
+
+    /// Run every script against this tick's metrics
+    pub fn run(&mut self, metrics: &SystemMetrics) -> Vec<ScriptAlert> {
+        if self.scripts.is_empty() {
+            return Vec::new();
+        }
+        let current = match rhai::serde::to_dynamic(metrics) {
+            Ok(value) => value,
+            Err(e) => {
+                warn!("Failed to pass metrics to detection scripts: {}", e);
+                return Vec::new();
+            }
+        };
+
+        let mut alerts = Vec::new();
+        for script in &mut self.scripts {
+            let Some(ast) = &script.ast else { continue };
+            let mut scope = Scope::new();
+            scope.push_constant("metrics", current.clone());
+
+            let started = Instant::now();
+            *self.deadline.lock().unwrap() = started + self.timeout;
+            let result = self
+                .engine
+                .eval_ast_with_scope::<Dynamic>(&mut scope, ast)
+                .map_err(|e| e.to_string())
+                .and_then(|value| parse_alerts(&script.status.name, value));
+
+            let status = &mut script.status;
+            status.runs += 1;
+            status.last_duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+            match result {
+                Ok(found) => {
+                    status.last_error = None;
+                    alerts.extend(found);
+                }
+                Err(e) => {
+                    // Same error every tick: log it once
+                    if status.last_error.as_ref() != Some(&e) {
+                        warn!("Detection script {} failed: {}", status.path, e);
+                    }
+                    status.errors += 1;
+                    status.last_error = Some(e);
+                }
+            }
+        }
+
+        let mut history = self.history.lock().unwrap();
+        history.push_back(current);
+        while history.len() > self.history_len {
+            history.pop_front();
+        }
+        alerts
+    }
+
+    pub fn status(&self) -> Vec<ScriptStatus> {
+        self.scripts.iter().map(|s| s.status.clone()).collect()
+    }
+}
+
+/// Alerts from a script's return value
+fn parse_alerts(script: &str, value: Dynamic) -> Result<Vec<ScriptAlert>, String> {
+    if value.is_unit() {
+        return Ok(Vec::new());
+    }
+    if value.is_map() {
+        return Ok(vec![parse_alert(script, value.cast::<Map>())?]);
+    }
+    if value.is_array() {
+        return value
+            .cast::<rhai::Array>()
+            .into_iter()
+            .map(|item| match item.try_cast::<Map>() {
+                Some(map) => parse_alert(script, map),
+                None => Err("returned array must contain only maps".to_string()),
+            })
+            .collect();
+    }
+    Err(format!("must return (), a map or an array of maps, not {}", value.type_name()))
+}
+
+fn parse_alert(script: &str, map: Map) -> Result<ScriptAlert, String> {
+    let text = |key: &str| map.get(key).and_then(|v| v.clone().into_string().ok());
+
+    let level = match text("level").map(|l| l.to_lowercase()).as_deref() {
+        Some("warning") => AlertLevel::Warning,
+        Some("critical") => AlertLevel::Critical,
+        other => return Err(format!("level must be \"warning\" or \"critical\", got {:?}", other)),
+    };
+    let message = text("message").ok_or("alert needs a message")?;
+    let value = match map.get("value") {
+        Some(v) if v.is_float() => v.as_float().unwrap_or(0.0),
+        Some(v) if v.is_int() => v.as_int().unwrap_or(0) as f64,
+        _ => 0.0,
+    };
+    let details = map
+        .get("details")
+        .and_then(|d| d.clone().try_cast::<rhai::Array>())
+        .map(|items| items.into_iter().map(|i| i.to_string()).collect())
+        .unwrap_or_default();
+
+    Ok(ScriptAlert {
+        name: text("type").unwrap_or_else(|| script.to_string()),
+        level,
+        narration: text("narration").unwrap_or_else(|| message.clone()),
+        message,
+        details,
+        value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine_with(sources: &[(&str, &str)]) -> ScriptEngine {
+        let config: ScriptingConfig = toml::from_str("max_operations = 10000\ntimeout_ms = 200").unwrap();
+        let mut engine = ScriptEngine::empty(&config);
+        for (name, source) in sources {
+            engine.add(name, &format!("{}.rhai", name), Ok(source.to_string()));
+        }
+        engine
+    }
+
+    #[test]
+    fn test_script_alerts_and_history() {
+        let mut engine = engine_with(&[(
+            "swap_jump",
+            r#"
+            let before = history(1);
+            if before == () { return; }
+            let jump = metrics.swap_percent - before.swap_percent;
+            if jump > 20.0 {
+                #{ level: "warning", message: `Swap jumped ${jump}%`, value: jump }
+            }
+            "#,
+        )]);
+
+        let mut metrics = SystemMetrics { swap_percent: 10.0, ..SystemMetrics::default() };
+        assert!(engine.run(&metrics).is_empty());
+        metrics.swap_percent = 40.0;
+        let alerts = engine.run(&metrics);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].name, "swap_jump");
+        assert_eq!(alerts[0].level, AlertLevel::Warning);
+        assert_eq!(alerts[0].value, 30.0);
+        assert_eq!(alerts[0].narration, alerts[0].message);
+    }
+
+    #[test]
+    fn test_errors_and_limits_are_contained() {
+        let mut engine = engine_with(&[
+            ("spin", "loop { }"),
+            ("broken", "let x = ;"),
+            ("bad_level", r#"#{ level: "panic", message: "x" }"#),
+            ("fine", r#"[#{ level: "critical", message: "ok", type: "custom" }]"#),
+        ]);
+
+        let alerts = engine.run(&SystemMetrics::default());
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].name, "custom");
+
+        let status = engine.status();
+        assert!(status[0].last_error.as_ref().unwrap().contains("operations"));
+        assert!(status[1].last_error.as_ref().unwrap().contains("Compile error"));
+        assert!(status[2].last_error.as_ref().unwrap().contains("level"));
+        assert!(status[3].last_error.is_none());
+    }
+}
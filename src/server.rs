@@ -1,28 +1,96 @@
 //! Unix Domain Socket server for IPC with the Tauri UI
-//! 
+//!
 //! Broadcasts current system metrics to all connected clients.
+//! Clients may also send commands, one JSON object per line, e.g.
+//! `{"command": "ack"}`. Each command gets a single `{"type": "response", ...}`
+//! line back, interleaved with the metrics stream.
 
 use std::sync::Arc;
+use serde::{Deserialize, Serialize};
 use tokio::net::{UnixListener, UnixStream};
-use tokio::io::AsyncWriteExt;
-use tokio::sync::broadcast;
-use tracing::{error, info};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tracing::{debug, error, info};
+use crate::actions::ActionRequest;
+use crate::detector::AnomalyType;
 use crate::metrics::SystemMetrics;
 
+/// Commands accepted from IPC clients
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum IpcRequest {
+    /// Acknowledge escalating alerts: one alert by name, every alert of an
+    /// anomaly type, or all of them
+    Ack {
+        #[serde(default)]
+        anomaly_type: Option<AnomalyType>,
+        #[serde(default)]
+        name: Option<String>,
+    },
+    /// Report daemon state (active escalations, ...)
+    Status,
+    /// Ask whether a process may be signalled under the protected-process policy
+    CheckProcess {
+        pid: u32,
+        /// PID of the asking client, which is never a valid target
+        #[serde(default)]
+        requester_pid: Option<u32>,
+    },
+    /// Run a remediation action (kill, freeze, renice, purge) in the daemon
+    Action(ActionRequest),
+    /// Recently delivered alerts and kernel events, newest first
+    History {
+        #[serde(default)]
+        limit: Option<usize>,
+        /// Also return the samples of this metric series
+        #[serde(default)]
+        series: Option<String>,
+    },
+}
+
+/// Reply to an `IpcRequest`
+#[derive(Debug, Clone, Serialize)]
+pub struct IpcResponse {
+    /// Always "response", so clients can tell replies apart from metrics lines
+    #[serde(rename = "type")]
+    kind: &'static str,
+    pub ok: bool,
+    pub message: String,
+    #[serde(skip_serializing_if = "serde_json::Value::is_null")]
+    pub data: serde_json::Value,
+}
+
+impl IpcResponse {
+    pub fn ok(message: impl Into<String>, data: serde_json::Value) -> Self {
+        Self { kind: "response", ok: true, message: message.into(), data }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self { kind: "response", ok: false, message: message.into(), data: serde_json::Value::Null }
+    }
+}
+
+/// A request from a client together with the channel to answer it on
+pub type IpcCommand = (IpcRequest, oneshot::Sender<IpcResponse>);
+
 pub struct IpcServer {
     socket_path: String,
     tx: broadcast::Sender<Arc<SystemMetrics>>,
+    cmd_tx: mpsc::Sender<IpcCommand>,
 }
 
 impl IpcServer {
-    pub fn new(socket_path: &str) -> (Self, broadcast::Sender<Arc<SystemMetrics>>) {
+    pub fn new(socket_path: &str) -> (Self, broadcast::Sender<Arc<SystemMetrics>>, mpsc::Receiver<IpcCommand>) {
         let (tx, _rx) = broadcast::channel(16);
+        let (cmd_tx, cmd_rx) = mpsc::channel(16);
         (
             Self {
                 socket_path: socket_path.to_string(),
                 tx: tx.clone(),
+                cmd_tx,
             },
             tx,
+            cmd_rx,
         )
     }
 
@@ -37,8 +105,9 @@ impl IpcServer {
             match listener.accept().await {
                 Ok((stream, _)) => {
                     let rx = self.tx.subscribe();
+                    let cmd_tx = self.cmd_tx.clone();
                     tokio::spawn(async move {
-                        if let Err(e) = handle_client(stream, rx).await {
+                        if let Err(e) = handle_client(stream, rx, cmd_tx).await {
                             error!("IPC client error: {}", e);
                         }
                     });
@@ -49,12 +118,43 @@ impl IpcServer {
     }
 }
 
-async fn handle_client(mut stream: UnixStream, mut rx: broadcast::Receiver<Arc<SystemMetrics>>) -> Result<(), anyhow::Error> {
-    while let Ok(metrics) = rx.recv().await {
-        let json = serde_json::to_string(&*metrics)?;
-        stream.write_all(json.as_bytes()).await?;
-        stream.write_all(b"\n").await?;
-        stream.flush().await?;
+async fn handle_client(
+    stream: UnixStream,
+    mut rx: broadcast::Receiver<Arc<SystemMetrics>>,
+    cmd_tx: mpsc::Sender<IpcCommand>,
+) -> Result<(), anyhow::Error> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    loop {
+        tokio::select! {
+            metrics = rx.recv() => {
+                let Ok(metrics) = metrics else { break };
+                let json = serde_json::to_string(&*metrics)?;
+                writer.write_all(json.as_bytes()).await?;
+            }
+            line = lines.next_line() => {
+                let Some(line) = line? else { break };
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                let response = match serde_json::from_str::<IpcRequest>(&line) {
+                    Ok(request) => {
+                        debug!("IPC request: {:?}", request);
+                        let (reply_tx, reply_rx) = oneshot::channel();
+                        cmd_tx.send((request, reply_tx)).await?;
+                        reply_rx.await.unwrap_or_else(|_| IpcResponse::error("Daemon dropped the request"))
+                    }
+                    Err(e) => IpcResponse::error(format!("Invalid request: {}", e)),
+                };
+
+                let json = serde_json::to_string(&response)?;
+                writer.write_all(json.as_bytes()).await?;
+            }
+        }
+        writer.write_all(b"\n").await?;
+        writer.flush().await?;
     }
     Ok(())
 }
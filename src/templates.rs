@@ -0,0 +1,277 @@
+//! Message templates for alert text and narration
+//!
+//! Every anomaly's message, details and narration are rendered from templates
+//! such as `"Mem {value:.0}%: {culprit}"`. Built-in templates reproduce the
+//! stock wording; `[[templates]]` entries in the config override them per
+//! anomaly type, per alert level, or both (the most specific match wins).
+
+use std::collections::HashMap;
+
+use crate::config::TemplateConfig;
+use crate::detector::{AlertLevel, AnomalyType};
+
+/// A value that can be substituted into a template
+#[derive(Debug, Clone)]
+pub enum TemplateValue {
+    Number(f64),
+    Text(String),
+}
+
+impl From<f64> for TemplateValue {
+    fn from(v: f64) -> Self {
+        TemplateValue::Number(v)
+    }
+}
+
+impl From<String> for TemplateValue {
+    fn from(v: String) -> Self {
+        TemplateValue::Text(v)
+    }
+}
+
+impl From<&str> for TemplateValue {
+    fn from(v: &str) -> Self {
+        TemplateValue::Text(v.to_string())
+    }
+}
+
+/// Named variables available to a template
+#[derive(Debug, Clone, Default)]
+pub struct TemplateVars {
+    values: HashMap<&'static str, TemplateValue>,
+}
+
+impl TemplateVars {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, name: &'static str, value: impl Into<TemplateValue>) -> Self {
+        self.values.insert(name, value.into());
+        self
+    }
+
+    /// Like `with`, but leaves the variable unset for `None`
+    pub fn with_opt(self, name: &'static str, value: Option<impl Into<TemplateValue>>) -> Self {
+        match value {
+            Some(value) => self.with(name, value),
+            None => self,
+        }
+    }
+}
+
+/// Rendered text for one anomaly
+#[derive(Debug, Clone)]
+pub struct RenderedMessage {
+    pub message: String,
+    pub details: Vec<String>,
+    pub narration: String,
+}
+
+/// Built-in and user-configured templates
+pub struct MessageTemplates {
+    overrides: Vec<TemplateConfig>,
+}
+
+impl MessageTemplates {
+    pub fn new(overrides: &[TemplateConfig]) -> Self {
+        Self { overrides: overrides.to_vec() }
+    }
+
+    pub fn render(&self, anomaly_type: AnomalyType, level: AlertLevel, vars: &TemplateVars) -> RenderedMessage {
+        let (default_message, default_narration) = builtin(anomaly_type, level);
+
+        let message = self
+            .lookup(anomaly_type, level, |t| t.message.as_deref())
+            .unwrap_or(default_message);
+        let narration = self
+            .lookup(anomaly_type, level, |t| t.narration.as_deref())
+            .unwrap_or(default_narration);
+        let details = self
+            .lookup(anomaly_type, level, |t| t.details.as_deref())
+            .unwrap_or(&[]);
+
+        RenderedMessage {
+            message: render(message, vars),
+            details: details.iter().map(|d| render(d, vars)).collect(),
+            narration: render(narration, vars),
+        }
+    }
+
+    /// Find the most specific override providing `field`:
+    /// type + level, then type only, then level only.
+    fn lookup<'a, T: ?Sized>(
+        &'a self,
+        anomaly_type: AnomalyType,
+        level: AlertLevel,
+        field: impl Fn(&'a TemplateConfig) -> Option<&'a T>,
+    ) -> Option<&'a T> {
+        let matches = |t: &TemplateConfig, want_type: bool, want_level: bool| {
+            t.anomaly_type.is_some() == want_type
+                && t.level.is_some() == want_level
+                && t.anomaly_type.is_none_or(|a| a == anomaly_type)
+                && t.level.is_none_or(|l| l == level)
+        };
+
+        [(true, true), (true, false), (false, true)]
+            .into_iter()
+            .find_map(|(want_type, want_level)| {
+                self.overrides
+                    .iter()
+                    .filter(|t| matches(t, want_type, want_level))
+                    .find_map(&field)
+            })
+    }
+}
+
+/// Stock (message, narration) wording for each anomaly type and level
+fn builtin(anomaly_type: AnomalyType, level: AlertLevel) -> (&'static str, &'static str) {
+    match (anomaly_type, level) {
+        (AnomalyType::Memory, AlertLevel::Critical) => ("Mem {value:.0}%: {culprit}", "Memory critical. {value:.0} percent. {culprit}"),
+        (AnomalyType::Memory, AlertLevel::Warning) => ("Mem {value:.0}%: {culprit}", "Memory high. {value:.0} percent. {culprit}"),
+        (AnomalyType::Swap, _) => ("Swap {value:.0}%: {culprit}", "Swap high. {value:.0} percent. {culprit}"),
+        (AnomalyType::Load, _) => ("Load {load:.1} on {cores:.0} cores, {trend}: {culprit}", "Load high and {trend}. {load:.1}. {culprit}"),
+        (AnomalyType::MemoryGrowthRate, AlertLevel::Critical) => ("Growth {value:.0}GB/h: {culprit}", "Memory growth critical. {value:.1} gigabytes per hour. {culprit}"),
+        (AnomalyType::MemoryGrowthRate, AlertLevel::Warning) => ("Growth {value:.0}GB/h: {culprit}", "Memory growth high. {value:.1} gigabytes per hour. {culprit}"),
+        (AnomalyType::ProcessWatchlist, _) => ("Heavy App: {culprit} ({memory_gb:.0}GB)", "Process {culprit} memory high."),
+        (AnomalyType::CgroupLimit, AlertLevel::Critical) => ("Limit hit: {culprit} ({value:.0}MB, {oom_kills:.0} OOM kills)", "Memory limit critical. {culprit} ran out of memory."),
+        (AnomalyType::OomKill, _) => ("OOM killed: {culprit}", "Out of memory. The kernel killed {culprit}."),
+        (AnomalyType::Crash, _) => ("Crashed: {culprit}", "{culprit} crashed."),
+        (AnomalyType::RestartLoop, _) => ("Restart loop: {culprit} ({value:.0}x in {window})", "{culprit} keeps restarting."),
+        (AnomalyType::ProcessExited, _) => ("Gone: {culprit} (PID {pid:.0}, {gone_for})", "{culprit} has stopped running."),
+        (AnomalyType::ProcessMissing, _) => ("Not running: {missing}", "{culprit} is not running."),
+        (AnomalyType::ProbeFailed, _) => ("Down: {culprit} ({error})", "{culprit} is not answering."),
+        (AnomalyType::Rule, _) => ("{rule}: {metric} {value:.1}", "Rule {rule}. {metric} at {value:.1}."),
+        (AnomalyType::CpuPinned, _) => ("Pinned: {culprit} ({value:.0}% CPU, {pinned_for})", "{culprit} has kept a core busy for {pinned_for}."),
+        (AnomalyType::Thermal, _) => ("{cause}: {sensor} {value:.0}°C, CPU {speed}", "{cause}. {sensor} at {value:.0} degrees, CPU {speed}."),
+        (AnomalyType::IoSaturation, _) => ("I/O saturated: {device} {utilization:.0}% busy, {value:.0}ms: {culprit}", "Disk {device} is saturated. {culprit}"),
+        (AnomalyType::UploadSpike, _) => ("Upload spike: {interface} {value:.1}MB/s, usually {usual:.1}MB/s", "Unusual upload. {interface} sending {value:.0} megabytes per second."),
+        (AnomalyType::SocketLeak, _) => ("Socket leak: {culprit}, {close_wait:.0} CLOSE_WAIT of {sockets:.0}", "{culprit} is leaking network connections."),
+        (AnomalyType::FdLeak, _) => ("FD leak: {culprit} holds {count:.0} descriptors, +{value:.0}/h", "{culprit} is leaking file descriptors."),
+        (AnomalyType::ThreadLeak, _) => ("Thread leak: {culprit} runs {count:.0} threads, +{value:.0}/h", "{culprit} keeps starting threads."),
+        (AnomalyType::FileTable, _) => ("File handles: {value:.0}% of {max:.0} in use, top: {culprit}", "The system is running out of file handles."),
+        (AnomalyType::Script, _) => ("{type}: {value}", "{type}."),
+        (AnomalyType::Plugin, _) => ("{type}: {output}", "{type} check failed. {output}"),
+        (AnomalyType::CgroupLimit, AlertLevel::Warning) => ("Capped: {culprit} held at {threshold:.0}MB", "{culprit} is being held at its memory limit."),
+    }
+}
+
+/// Substitute `{name}` and `{name:.N}` placeholders. `{{` and `}}` are literal
+/// braces; unknown variables are left in place so typos are visible.
+pub fn render(template: &str, vars: &TemplateVars) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            '{' => {
+                let mut spec = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    spec.push(c);
+                }
+
+                if !closed {
+                    out.push('{');
+                    out.push_str(&spec);
+                    break;
+                }
+
+                let (name, precision) = match spec.split_once(":.") {
+                    Some((name, p)) => (name, p.parse::<usize>().ok()),
+                    None => (spec.as_str(), None),
+                };
+
+                match vars.values.get(name) {
+                    Some(TemplateValue::Number(n)) => match precision {
+                        Some(p) => out.push_str(&format!("{:.*}", p, n)),
+                        None => out.push_str(&format_number(*n)),
+                    },
+                    Some(TemplateValue::Text(t)) => out.push_str(t),
+                    None => {
+                        out.push('{');
+                        out.push_str(&spec);
+                        out.push('}');
+                    }
+                }
+            }
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Numbers without an explicit precision get at most one decimal
+fn format_number(n: f64) -> String {
+    let s = format!("{:.1}", n);
+    s.strip_suffix(".0").map(str::to_string).unwrap_or(s)
+}
+
+/// Format a duration as "1h 20m" / "5m"
+pub fn format_duration(d: chrono::Duration) -> String {
+    let minutes = d.num_minutes().max(0);
+    if minutes >= 60 {
+        format!("{}h {}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_placeholders() {
+        let vars = TemplateVars::new()
+            .with("value", 91.46)
+            .with("culprit", "Arc (12GB)");
+
+        assert_eq!(render("Mem {value:.0}%: {culprit}", &vars), "Mem 91%: Arc (12GB)");
+        assert_eq!(render("{value} {{literal}} {missing}", &vars), "91.5 {literal} {missing}");
+    }
+
+    #[test]
+    fn test_override_precedence() {
+        let overrides = vec![
+            TemplateConfig {
+                anomaly_type: None,
+                level: Some(AlertLevel::Critical),
+                message: Some("CRIT {value:.0}".into()),
+                details: None,
+                narration: None,
+            },
+            TemplateConfig {
+                anomaly_type: Some(AnomalyType::Memory),
+                level: None,
+                message: None,
+                details: None,
+                narration: Some("Minnet {value:.0} procent".into()),
+            },
+        ];
+        let templates = MessageTemplates::new(&overrides);
+        let vars = TemplateVars::new().with("value", 95.0).with("culprit", "x");
+
+        let r = templates.render(AnomalyType::Memory, AlertLevel::Critical, &vars);
+        assert_eq!(r.message, "CRIT 95");
+        assert_eq!(r.narration, "Minnet 95 procent");
+
+        // Swap has no type override, so narration falls back to the built-in
+        let r = templates.render(AnomalyType::Swap, AlertLevel::Warning, &vars);
+        assert_eq!(r.message, "Swap 95%: x");
+        assert_eq!(r.narration, "Swap high. 95 percent. x");
+    }
+}
@@ -0,0 +1,238 @@
+//! Temperature, fan and CPU frequency sensors (Linux sysfs)
+//!
+//! `SensorFs` reads below a configurable root (normally /sys):
+//! - hwmon chips: class/hwmon/hwmon*/{name, temp*_input, temp*_label,
+//!   temp*_max, temp*_crit, fan*_input, fan*_label}
+//! - thermal zones: class/thermal/thermal_zone*/{type, temp}
+//! - cpufreq: devices/system/cpu/cpu*/cpufreq/{scaling_cur_freq, cpuinfo_max_freq}
+//!
+//! Temperatures are millidegrees Celsius and frequencies kHz in sysfs. Missing
+//! directories simply yield no readings, so this is a no-op elsewhere.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// One temperature reading
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Temperature {
+    /// hwmon chip name ("coretemp", "k10temp") or "thermal_zone0"
+    pub chip: String,
+    /// Sensor label ("Package id 0", "Tctl"), the zone type, or "temp1"
+    pub label: String,
+    pub celsius: f64,
+    /// Chip's own high and critical limits, when it reports them
+    #[serde(default)]
+    pub max_celsius: Option<f64>,
+    #[serde(default)]
+    pub critical_celsius: Option<f64>,
+}
+
+impl Temperature {
+    /// "coretemp/Package id 0"
+    pub fn name(&self) -> String {
+        format!("{}/{}", self.chip, self.label)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fan {
+    pub chip: String,
+    pub label: String,
+    pub rpm: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CpuFrequency {
+    pub cpu: usize,
+    pub current_mhz: f64,
+    pub max_mhz: f64,
+}
+
+/// Everything read from the sensors in one tick
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThermalMetrics {
+    pub temperatures: Vec<Temperature>,
+    pub fans: Vec<Fan>,
+    pub cpu_frequencies: Vec<CpuFrequency>,
+}
+
+impl ThermalMetrics {
+    /// Average current/max frequency over all CPUs (1.0 = full speed)
+    pub fn frequency_ratio(&self) -> Option<f64> {
+        let known: Vec<&CpuFrequency> = self.cpu_frequencies.iter().filter(|f| f.max_mhz > 0.0).collect();
+        if known.is_empty() {
+            return None;
+        }
+        Some(known.iter().map(|f| f.current_mhz / f.max_mhz).sum::<f64>() / known.len() as f64)
+    }
+}
+
+/// Access to the sensor files under a sysfs root
+#[derive(Debug, Clone)]
+pub struct SensorFs {
+    root: PathBuf,
+}
+
+impl SensorFs {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn read(&self) -> ThermalMetrics {
+        let mut metrics = ThermalMetrics::default();
+        self.read_hwmon(&mut metrics);
+        self.read_thermal_zones(&mut metrics);
+        self.read_cpufreq(&mut metrics);
+        metrics
+    }
+
+    fn read_hwmon(&self, metrics: &mut ThermalMetrics) {
+        for dir in numbered_dirs(&self.root.join("class/hwmon"), "hwmon") {
+            let chip = read_string(&dir.join("name")).unwrap_or_else(|| file_name(&dir));
+            for (prefix, index) in numbered_files(&dir, "temp", "_input") {
+                let Some(millidegrees) = read_f64(&dir.join(format!("{}_input", prefix))) else { continue };
+                let limit = |suffix: &str| read_f64(&dir.join(format!("{}_{}", prefix, suffix))).map(|m| m / 1000.0);
+                metrics.temperatures.push(Temperature {
+                    chip: chip.clone(),
+                    label: read_string(&dir.join(format!("{}_label", prefix))).unwrap_or_else(|| format!("temp{}", index)),
+                    celsius: millidegrees / 1000.0,
+                    max_celsius: limit("max"),
+                    critical_celsius: limit("crit"),
+                });
+            }
+            for (prefix, index) in numbered_files(&dir, "fan", "_input") {
+                let Some(rpm) = read_f64(&dir.join(format!("{}_input", prefix))) else { continue };
+                metrics.fans.push(Fan {
+                    chip: chip.clone(),
+                    label: read_string(&dir.join(format!("{}_label", prefix))).unwrap_or_else(|| format!("fan{}", index)),
+                    rpm: rpm as u64,
+                });
+            }
+        }
+    }
+
+    fn read_thermal_zones(&self, metrics: &mut ThermalMetrics) {
+        for dir in numbered_dirs(&self.root.join("class/thermal"), "thermal_zone") {
+            let Some(millidegrees) = read_f64(&dir.join("temp")) else { continue };
+            metrics.temperatures.push(Temperature {
+                chip: file_name(&dir),
+                label: read_string(&dir.join("type")).unwrap_or_else(|| "zone".to_string()),
+                celsius: millidegrees / 1000.0,
+                max_celsius: None,
+                critical_celsius: None,
+            });
+        }
+    }
+
+    fn read_cpufreq(&self, metrics: &mut ThermalMetrics) {
+        let cpus = self.root.join("devices/system/cpu");
+        for dir in numbered_dirs(&cpus, "cpu") {
+            let Some(cpu) = file_name(&dir).strip_prefix("cpu").and_then(|n| n.parse().ok()) else { continue };
+            let freq = dir.join("cpufreq");
+            let (Some(current), Some(max)) = (read_f64(&freq.join("scaling_cur_freq")), read_f64(&freq.join("cpuinfo_max_freq"))) else {
+                continue;
+            };
+            metrics.cpu_frequencies.push(CpuFrequency { cpu, current_mhz: current / 1000.0, max_mhz: max / 1000.0 });
+        }
+        metrics.cpu_frequencies.sort_by_key(|f| f.cpu);
+    }
+}
+
+/// `<prefix><n>` entries of `dir`, in numeric order
+fn numbered_dirs(dir: &Path, prefix: &str) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+    let mut found: Vec<(u32, PathBuf)> = entries
+        .flatten()
+        .filter_map(|e| {
+            let n = e.file_name().to_string_lossy().strip_prefix(prefix)?.parse().ok()?;
+            Some((n, e.path()))
+        })
+        .collect();
+    found.sort();
+    found.into_iter().map(|(_, path)| path).collect()
+}
+
+/// `<prefix><n><suffix>` files of `dir` as ("<prefix><n>", n), in numeric order
+fn numbered_files(dir: &Path, prefix: &str, suffix: &str) -> Vec<(String, u32)> {
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+    let mut found: Vec<(String, u32)> = entries
+        .flatten()
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            let n: u32 = name.strip_prefix(prefix)?.strip_suffix(suffix)?.parse().ok()?;
+            Some((format!("{}{}", prefix, n), n))
+        })
+        .collect();
+    found.sort_by_key(|(_, n)| *n);
+    found
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+fn read_string(path: &Path) -> Option<String> {
+    let text = std::fs::read_to_string(path).ok()?;
+    Some(text.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+fn read_f64(path: &Path) -> Option<f64> {
+    read_string(path)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_sensors() {
+        let root = std::env::temp_dir().join(format!("sentinel-sysfs-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let file = |path: &str, content: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        file("class/hwmon/hwmon0/name", "coretemp\n");
+        file("class/hwmon/hwmon0/temp1_input", "87000\n");
+        file("class/hwmon/hwmon0/temp1_label", "Package id 0\n");
+        file("class/hwmon/hwmon0/temp1_max", "100000\n");
+        file("class/hwmon/hwmon0/temp1_crit", "105000\n");
+        file("class/hwmon/hwmon0/temp2_input", "61500\n");
+        file("class/hwmon/hwmon1/name", "thinkpad\n");
+        file("class/hwmon/hwmon1/fan1_input", "4200\n");
+        file("class/thermal/thermal_zone0/type", "acpitz\n");
+        file("class/thermal/thermal_zone0/temp", "45000\n");
+        file("class/thermal/cooling_device0/type", "Processor\n");
+        for (cpu, current) in [(0, "1200000"), (1, "2400000")] {
+            file(&format!("devices/system/cpu/cpu{}/cpufreq/scaling_cur_freq", cpu), current);
+            file(&format!("devices/system/cpu/cpu{}/cpufreq/cpuinfo_max_freq", cpu), "4800000");
+        }
+        file("devices/system/cpu/cpufreq/policy0/scaling_cur_freq", "1");
+
+        let metrics = SensorFs::new(&root).read();
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert_eq!(metrics.temperatures.len(), 3);
+        assert_eq!(metrics.temperatures[0], Temperature {
+            chip: "coretemp".to_string(),
+            label: "Package id 0".to_string(),
+            celsius: 87.0,
+            max_celsius: Some(100.0),
+            critical_celsius: Some(105.0),
+        });
+        assert_eq!(metrics.temperatures[1].name(), "coretemp/temp2");
+        assert_eq!(metrics.temperatures[2].name(), "thermal_zone0/acpitz");
+        assert_eq!(metrics.fans, vec![Fan { chip: "thinkpad".to_string(), label: "fan1".to_string(), rpm: 4200 }]);
+        assert_eq!(metrics.cpu_frequencies.len(), 2);
+        assert_eq!(metrics.cpu_frequencies[1].current_mhz, 2400.0);
+        assert_eq!(metrics.frequency_ratio(), Some(0.375));
+    }
+
+    #[test]
+    fn test_missing_sysfs() {
+        let metrics = SensorFs::new("/nonexistent/sys").read();
+        assert_eq!(metrics, ThermalMetrics::default());
+        assert_eq!(metrics.frequency_ratio(), None);
+    }
+}